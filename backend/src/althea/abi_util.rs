@@ -1,19 +1,19 @@
-use clarity::{Address, Uint256};
 use log::info;
 
-/// Parses an Address from ABI-encoded `input`, with the relevant data beginning
-/// at byte index `start`. Addresses are 20 bytes long packed on the right side.
-pub fn parse_address(input: &[u8], start: usize) -> Result<Address, clarity::Error> {
+/// Parses a u8 from ABI-encoded `input`, with the relevant data beginning
+/// at byte index `start`. u8's are 1 byte long and packed on the right side.
+pub fn parse_u8(input: &[u8], start: usize) -> u8 {
     let end = start + 32;
-    Address::from_slice(&input[start + 12..end])
+    input[end - 1]
 }
 
-/// Parses a Uint256 from ABI-encoded `input`, with the relevant data beginning
-/// at byte index `start`.
-pub fn parse_uint256(input: &[u8], start: usize) -> Uint256 {
+/// Parses a u16 from ABI-encoded `input`, with the relevant data beginning
+/// at byte index `start`. u16's are 2 bytes long and packed on the right side.
+pub fn parse_u16(input: &[u8], start: usize) -> u16 {
     let end = start + 32;
-    let data = &input[start..end];
-    Uint256::from_be_bytes(data)
+    // u16 is smooshed against the right side
+    let data = &input[start + 30..end];
+    u16::from_be_bytes(data.try_into().unwrap())
 }
 
 /// Parses a u64 from ABI-encoded `input`, with the relevant data beginning
@@ -34,13 +34,14 @@ pub fn parse_u128(input: &[u8], start: usize) -> u128 {
     u128::from_be_bytes(data.try_into().unwrap())
 }
 
-/// Parses an i32 from ABI-encoded `input`, with the relevant data beginning
-/// at byte index `start`. i32's are 8 bytes long and packed on the right side.
-pub fn parse_i32(input: &[u8], start: usize) -> i32 {
-    let end = start + 32;
-    // i32 is smooshed against the right side
-    let data = &input[start + 28..end];
-    i32::from_be_bytes(data.try_into().unwrap())
+/// Formats bytes such as a transaction hash as a 0x prefixed lowercase hex string
+pub fn to_hex_string(input: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + input.len() * 2);
+    out.push_str("0x");
+    for b in input {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
 }
 
 /// Cleans a protobuf encoded string by removing control characters and formatting codes
@@ -58,18 +59,3 @@ pub fn clean_proto_string(input: &str) -> String {
         input.trim().to_string()
     }
 }
-
-/// Converts a string representing a number with 18 decimal places
-pub fn format_decimal_18(input: impl AsRef<str>) -> String {
-    if let Ok(val) = input.as_ref().parse::<u128>() {
-        let decimal = val as f64 / 1_000_000_000_000_000_000.0;
-        format!("{:.18}", decimal)
-    } else {
-        "0.000000000000000000".to_string()
-    }
-}
-
-/// Converts a u128 number to a decimal string with 18 decimal places
-pub fn format_u128_to_decimal_18(amount: u128, divisor: u128) -> String {
-    format!("{}.000000000000000000", amount / divisor)
-}
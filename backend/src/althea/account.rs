@@ -0,0 +1,158 @@
+// Aggregates everything held by a single account for the portfolio page. Althea is an ethermint chain so
+// every account has both a bech32 address and an EVM address derived from the same key, either is accepted
+
+use clarity::Address as EthAddress;
+use deep_space::{Address as CosmosAddress, Contact};
+use futures::future::join_all;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+use web30::client::Web3;
+
+use crate::althea::amount::Amount;
+use crate::althea::database::pools::get_init_pools;
+use crate::althea::database::positions::get_active_user_positions;
+use crate::althea::delegations::{
+    fetch_delegations, Balance, DelegationResponse, RedelegationEntry, RewardsResponse,
+    UnbondingEntry,
+};
+use crate::althea::endpoints::UserPosition;
+use crate::althea::error::AltheaError;
+use crate::althea::token_mappings::get_token_info;
+use crate::althea::ALTHEA_PREFIX;
+
+/// The bech32 and EVM forms of the same account
+#[derive(Debug, Clone, Copy)]
+pub struct AccountAddress {
+    pub cosmos: CosmosAddress,
+    pub evm: EthAddress,
+}
+
+impl FromStr for AccountAddress {
+    type Err = AltheaError;
+
+    /// Parses either a 0x EVM address or an althea1 bech32 address
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AltheaError::InvalidAddressError(s.to_string());
+        if s.starts_with("0x") {
+            let evm = EthAddress::from_str(s)?;
+            let cosmos =
+                CosmosAddress::from_slice(evm.as_bytes(), ALTHEA_PREFIX).map_err(|_| invalid())?;
+            Ok(AccountAddress { cosmos, evm })
+        } else {
+            let cosmos = CosmosAddress::from_bech32(s.to_string()).map_err(|_| invalid())?;
+            if cosmos.get_prefix() != ALTHEA_PREFIX {
+                return Err(invalid());
+            }
+            let evm = EthAddress::from_slice(cosmos.get_bytes())?;
+            Ok(AccountAddress { cosmos, evm })
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Erc20Balance {
+    pub token: EthAddress,
+    /// The token's symbol, if it is in the token registry
+    pub symbol: Option<String>,
+    /// The balance in the token's base units
+    pub amount: Amount,
+    /// The balance scaled by the token's decimals, if it is in the token registry
+    pub display_amount: Option<Amount>,
+}
+
+/// Everything held by an account
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountInfo {
+    pub address: String,
+    pub evm_address: EthAddress,
+    /// Bank module balances, including aalthea which is also the account's native EVM balance
+    pub balances: Vec<Balance>,
+    /// Nonzero balances of tokens in indexed Ambient pools
+    pub erc20_balances: Vec<Erc20Balance>,
+    pub delegations: Vec<DelegationResponse>,
+    pub unbonding_delegations: Vec<UnbondingEntry>,
+    pub redelegations: Vec<RedelegationEntry>,
+    pub rewards: RewardsResponse,
+    /// The address staking rewards are paid to when withdrawn
+    pub withdraw_address: String,
+    pub positions: Vec<UserPosition>,
+}
+
+/// Gathers the balances, staking state and Ambient positions of `address`
+pub async fn fetch_account(
+    db: &rocksdb::DB,
+    contact: &Contact,
+    web3: &Web3,
+    address: AccountAddress,
+) -> Result<AccountInfo, Box<dyn std::error::Error>> {
+    let balances = contact
+        .get_balances(address.cosmos)
+        .await?
+        .into_iter()
+        .map(|c| Balance::new(c.denom, Amount::new(c.amount, 0)))
+        .collect();
+    let erc20_balances = fetch_erc20_balances(db, web3, address.evm).await;
+    let delegations = fetch_delegations(db, contact, address.cosmos).await?;
+    let positions = get_active_user_positions(db, address.evm)
+        .into_iter()
+        .map(|p| UserPosition::from_position(db, p))
+        .collect();
+
+    Ok(AccountInfo {
+        address: address.cosmos.to_string(),
+        evm_address: address.evm,
+        balances,
+        erc20_balances,
+        delegations: delegations.delegations,
+        unbonding_delegations: delegations.unbonding_delegations,
+        redelegations: delegations.redelegations,
+        rewards: delegations.rewards,
+        withdraw_address: delegations.withdraw_address,
+        positions,
+    })
+}
+
+// Queries the balance of every token found in an indexed pool, tokens whose balance cannot be queried
+// are logged and left out
+async fn fetch_erc20_balances(
+    db: &rocksdb::DB,
+    web3: &Web3,
+    owner: EthAddress,
+) -> Vec<Erc20Balance> {
+    let mut tokens = HashSet::new();
+    for pool in get_init_pools(db) {
+        tokens.insert(pool.base);
+        tokens.insert(pool.quote);
+    }
+    // The zero address stands for the native token in Ambient pools, which is reported with the bank balances
+    tokens.remove(&EthAddress::default());
+    let tokens: Vec<EthAddress> = tokens.into_iter().collect();
+
+    let results = join_all(tokens.iter().map(|t| web3.get_erc20_balance(*t, owner))).await;
+    let mut balances = Vec::new();
+    for (token, result) in tokens.into_iter().zip(results) {
+        match result {
+            Ok(amount) if amount != 0u8.into() => {
+                let amount = Amount::new(amount, 0);
+                let info = get_token_info(db, token);
+                balances.push(Erc20Balance {
+                    token,
+                    symbol: info.as_ref().map(|i| i.symbol.clone()),
+                    display_amount: info.map(|i| {
+                        amount
+                            .clone()
+                            .shift(i.decimals.into())
+                            .with_scale(i.decimals.into())
+                    }),
+                    amount,
+                })
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to query balance of token {}: {}", token, e),
+        }
+    }
+    balances.sort_by(|a, b| a.token.cmp(&b.token));
+    balances
+}
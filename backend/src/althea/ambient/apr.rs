@@ -0,0 +1,374 @@
+// This file estimates the fee APR earned by liquidity positions from indexed pool data
+// A position earns its share of the active liquidity's cut of each swap fee while the pool's price
+// is inside its range, the APR annualizes those earnings against the position's current value.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clarity::{Address, Uint256};
+
+use super::croc_query::{CurveState, PoolParams};
+use super::curve_math::{
+    active_liquidity, ambient_reserves, ranged_reserves, sqrt_price_from_q64, tick_from_sqrt_price,
+    value_in_base,
+};
+use super::swaps::SwapEvent;
+use crate::althea::database::{
+    curve::{
+        get_curve, get_pool_params, get_price, get_price_history, PriceSnapshot,
+        PRICE_SNAPSHOT_RETENTION,
+    },
+    positions::{AmbientPosition, RangedPosition},
+    swaps::get_pool_swaps_after,
+};
+
+const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+/// Fees are observed over at most this many seconds, the period price snapshots are retained for
+const APR_WINDOW: u64 = PRICE_SNAPSHOT_RETENTION;
+
+/// The APR estimate for a single position, along with the inputs used to produce it
+#[derive(Debug, Default, Clone)]
+pub struct PositionApr {
+    /// The number of seconds over which fees were observed
+    pub duration: f64,
+    /// The pool's active liquidity including the position
+    pub post_liq: f64,
+    /// The liquidity contributed by the position
+    pub contributed_liq: f64,
+    /// The annualized fee return on the position's current value
+    pub apr: f64,
+}
+
+pub fn estimate_ranged_apr(db: &rocksdb::DB, p: &RangedPosition) -> PositionApr {
+    estimate_apr(
+        db,
+        p.base,
+        p.quote,
+        p.pool_idx,
+        p.start_block,
+        p.liq,
+        Some((p.bid_tick, p.ask_tick)),
+    )
+}
+
+pub fn estimate_ambient_apr(db: &rocksdb::DB, p: &AmbientPosition) -> PositionApr {
+    estimate_apr(db, p.base, p.quote, p.pool_idx, p.start_block, p.liq, None)
+}
+
+/// Estimates the APR of `liq` units of liquidity provided since `start_block`, or over the last APR_WINDOW
+/// for older positions. `range` is the (bid_tick, ask_tick) pair of concentrated positions and None for
+/// ambient positions. Returns a zeroed estimate when the pool has not been indexed long enough to produce one.
+fn estimate_apr(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    start_block: Uint256,
+    liq: u128,
+    range: Option<(i32, i32)>,
+) -> PositionApr {
+    let (curve, price) = match (
+        get_curve(db, base, quote, pool_idx),
+        get_price(db, base, quote, pool_idx),
+    ) {
+        (Some(curve), Some(price)) if price != 0 => (curve, price),
+        _ => return PositionApr::default(),
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let history = get_price_history(db, base, quote, pool_idx, now.saturating_sub(APR_WINDOW));
+    compute_apr(&curve, price, &history, now, start_block, liq, range)
+}
+
+/// Estimates the APR of `liq` units of liquidity in a pool with the given `curve` and current Q64.64 square
+/// root `price`, from the pool's price `history` up to `now`
+fn compute_apr(
+    curve: &CurveState,
+    price: u128,
+    history: &[PriceSnapshot],
+    now: u64,
+    start_block: Uint256,
+    liq: u128,
+    range: Option<(i32, i32)>,
+) -> PositionApr {
+    // Fees can only be attributed from the first recorded price at or after the position's start
+    let window = match history.iter().position(|s| s.block_height >= start_block) {
+        Some(idx) => &history[idx..],
+        None => return PositionApr::default(),
+    };
+    let window_start = &window[0];
+    if now <= window_start.time {
+        return PositionApr::default();
+    }
+    let duration = (now - window_start.time) as f64;
+    let in_range_fraction = match range {
+        Some((bid_tick, ask_tick)) => in_range_seconds(window, now, bid_tick, ask_tick) / duration,
+        None => 1.0,
+    };
+
+    let sqrt_price = sqrt_price_from_q64(price);
+    let in_range_now = match range {
+        Some((bid_tick, ask_tick)) => {
+            let tick = tick_from_sqrt_price(sqrt_price);
+            bid_tick <= tick && tick < ask_tick
+        }
+        None => true,
+    };
+    let contributed_liq = liq as f64;
+    // Out of range positions are not counted in the curve's active liquidity
+    let post_liq = if in_range_now {
+        active_liquidity(curve).max(contributed_liq)
+    } else {
+        active_liquidity(curve) + contributed_liq
+    };
+    if post_liq == 0.0 {
+        return PositionApr::default();
+    }
+
+    // Each snapshot holds the fees paid since the one before it, so the first snapshot's fees precede the window
+    let fees: f64 = window[1..].iter().map(|s| s.lp_fees).sum();
+    let earned = fees * (contributed_liq / post_liq) * in_range_fraction;
+
+    let reserves = match range {
+        Some((bid_tick, ask_tick)) => {
+            ranged_reserves(contributed_liq, sqrt_price, bid_tick, ask_tick)
+        }
+        None => ambient_reserves(contributed_liq, sqrt_price),
+    };
+    let value = value_in_base(reserves, sqrt_price);
+    let apr = if value > 0.0 {
+        earned / value * SECONDS_PER_YEAR / duration
+    } else {
+        0.0
+    };
+
+    PositionApr {
+        duration,
+        post_liq,
+        contributed_liq,
+        apr,
+    }
+}
+
+/// Sums the seconds during which the recorded price fell inside [bid_tick, ask_tick), each
+/// snapshot's price is assumed to hold until the next snapshot
+fn in_range_seconds(history: &[PriceSnapshot], now: u64, bid_tick: i32, ask_tick: i32) -> f64 {
+    let mut seconds = 0u64;
+    for (i, snapshot) in history.iter().enumerate() {
+        let end = history.get(i + 1).map(|s| s.time).unwrap_or(now);
+        if snapshot.price == 0 || end <= snapshot.time {
+            continue;
+        }
+        let tick = tick_from_sqrt_price(sqrt_price_from_q64(snapshot.price));
+        if bid_tick <= tick && tick < ask_tick {
+            seconds += end - snapshot.time;
+        }
+    }
+    seconds as f64
+}
+
+/// Sums the fees paid to liquidity providers by the pool's swaps after `after_block`, valued in raw base
+/// token units at `price`. The fee rate is the pool's own, as last queried from the contract, rather than
+/// its template's: governance can revise a pool's fee rate after the pool is initialized from a template,
+/// and swaps pay the pool's current rate
+pub fn lp_fees_after(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    after_block: Uint256,
+    price: u128,
+) -> f64 {
+    match get_pool_params(db, base, quote, pool_idx) {
+        Some(params) => {
+            let swaps = get_pool_swaps_after(db, base, quote, pool_idx, after_block);
+            lp_fees(&swaps, base, &params, price)
+        }
+        None => 0.0,
+    }
+}
+
+// Sums the liquidity provider share of the fees paid by `swaps`, converting fees paid in the quote token
+// to base token units at `price`
+fn lp_fees(swaps: &[SwapEvent], base: Address, params: &PoolParams, price: u128) -> f64 {
+    let sqrt_price = sqrt_price_from_q64(price);
+    let mut fees = 0.0;
+    for swap in swaps {
+        let fee = swap.sell_qty as f64 * params.fee_fraction() * params.lp_fraction();
+        fees += if swap.sell == base {
+            fee
+        } else {
+            fee * sqrt_price * sqrt_price
+        };
+    }
+    fees
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const Q64: u128 = 1 << 64;
+
+    fn snapshot(block_height: u64, time: u64, price: u128, lp_fees: f64) -> PriceSnapshot {
+        PriceSnapshot {
+            block_height: block_height.into(),
+            time,
+            price,
+            lp_fees,
+        }
+    }
+
+    // Three snapshots 1000 seconds apart ending 1000 seconds before `now`, at a price of 1 (tick 0) except
+    // for the second, at a price of 4 (tick 13863)
+    fn history() -> Vec<PriceSnapshot> {
+        vec![
+            snapshot(10, 1000, Q64, 999.0),
+            snapshot(20, 2000, 2 * Q64, 300.0),
+            snapshot(30, 3000, Q64, 600.0),
+        ]
+    }
+
+    fn curve() -> CurveState {
+        CurveState {
+            price_root: Q64,
+            ambient_seeds: 0,
+            conc_liq: 3000,
+            seed_deflator: 0,
+            conc_growth: 0,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_in_range_seconds() {
+        let history = history();
+        // Only the second snapshot's price falls outside the range
+        assert_eq!(in_range_seconds(&history, 4000, -100, 100), 2000.0);
+        // Only the second snapshot's price falls inside the range
+        assert_eq!(in_range_seconds(&history, 4000, 100, 20000), 1000.0);
+        // The ask tick is exclusive
+        assert_eq!(in_range_seconds(&history, 4000, -100, 0), 0.0);
+        // Snapshots without a price are skipped
+        let mut missing = history.clone();
+        missing[2].price = 0;
+        assert_eq!(in_range_seconds(&missing, 4000, -100, 100), 1000.0);
+    }
+
+    #[test]
+    fn test_compute_ranged_apr() {
+        // Over the whole history the position is in range for 2000 of 3000 seconds and holds a third of
+        // the active liquidity, so it earns 900 * 1/3 * 2/3 = 200 of the fees paid after the first snapshot.
+        // Its value at a price of 1 is 2 * 1000 * (1 - 1.0001^-50)
+        let apr = compute_apr(
+            &curve(),
+            Q64,
+            &history(),
+            4000,
+            0u8.into(),
+            1000,
+            Some((-100, 100)),
+        );
+        assert_eq!(apr.duration, 3000.0);
+        assert_eq!(apr.post_liq, 3000.0);
+        assert_eq!(apr.contributed_liq, 1000.0);
+        assert_close(
+            apr.apr,
+            200.0 / 9.974544141497121 * SECONDS_PER_YEAR / 3000.0,
+        );
+    }
+
+    #[test]
+    fn test_compute_apr_window_starts_at_position() {
+        // The window starts at the second snapshot, the first at or after the position's start, whose fees
+        // precede the window. The position is in range for 1000 of 2000 seconds and earns 600 * 1/3 * 1/2
+        let apr = compute_apr(
+            &curve(),
+            Q64,
+            &history(),
+            4000,
+            15u8.into(),
+            1000,
+            Some((-100, 100)),
+        );
+        assert_eq!(apr.duration, 2000.0);
+        assert_close(
+            apr.apr,
+            100.0 / 9.974544141497121 * SECONDS_PER_YEAR / 2000.0,
+        );
+
+        // No snapshot has been recorded since the position started
+        let apr = compute_apr(
+            &curve(),
+            Q64,
+            &history(),
+            4000,
+            31u8.into(),
+            1000,
+            Some((-100, 100)),
+        );
+        assert_eq!(apr.duration, 0.0);
+        assert_eq!(apr.apr, 0.0);
+    }
+
+    #[test]
+    fn test_compute_ambient_apr() {
+        // Ambient liquidity is always in range, earning 900 * 1/3 on 1000 base and 1000 quote
+        let apr = compute_apr(&curve(), Q64, &history(), 4000, 0u8.into(), 1000, None);
+        assert_eq!(apr.post_liq, 3000.0);
+        assert_close(apr.apr, 300.0 / 2000.0 * SECONDS_PER_YEAR / 3000.0);
+    }
+
+    #[test]
+    fn test_compute_out_of_range_apr() {
+        // A position out of range at the current price is added to the active liquidity, and earned no
+        // fees over the window since the price was never inside its range
+        let apr = compute_apr(
+            &curve(),
+            Q64,
+            &history(),
+            4000,
+            0u8.into(),
+            1000,
+            Some((200, 300)),
+        );
+        assert_eq!(apr.post_liq, 4000.0);
+        assert_eq!(apr.apr, 0.0);
+    }
+
+    #[test]
+    fn test_lp_fees() {
+        let base = Address::from_str("0x0412C7c846bb6b7DC462CF6B453f76D8440b2609").unwrap();
+        let quote = Address::from_str("0x30dA8589BFa1E509A319489E014d384b87815D89").unwrap();
+        let swap = |buy: Address, sell: Address| SwapEvent {
+            block_height: 1u8.into(),
+            log_index: 0u8.into(),
+            user: Address::default(),
+            buy,
+            sell,
+            pool_idx: 36000u64.into(),
+            buy_qty: 0,
+            sell_qty: 1_000_000,
+        };
+        // A 0.3% fee of which the protocol takes a quarter
+        let params = PoolParams {
+            fee_rate: 3000,
+            protocol_take: 64,
+            ..Default::default()
+        };
+        let swaps = [swap(quote, base), swap(base, quote)];
+        // 2250 base from the first swap and 2250 quote, worth 9000 base at a price of 4, from the second
+        assert_close(lp_fees(&swaps, base, &params, 2 * Q64), 11250.0);
+        assert_eq!(lp_fees(&[], base, &params, 2 * Q64), 0.0);
+    }
+}
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use web30::{client::Web3, types::TransactionRequest};
 
 use crate::althea::{
-    abi_util::{parse_u128, parse_u64},
+    abi_util::{parse_u128, parse_u16, parse_u64, parse_u8},
     error::AltheaError,
     DEFAULT_QUERIER,
 };
@@ -29,7 +29,7 @@ pub const QUERY_CURVE_SIG: &str = "queryCurve(address,address,uint256)";
 //     uint64 seedDeflator_;
 //     uint64 concGrowth_;
 // }
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct CurveState {
     pub price_root: u128,
     pub ambient_seeds: u128,
@@ -154,3 +154,98 @@ pub async fn get_price(
         .await?;
     Ok(parse_u128(&price_res, 0))
 }
+
+// @notice Queries and returns the current parameters of a given pool, these are copied from the
+//         pool's template when the pool is initialized.
+// @param base The base token address
+// @param quote The quote token address
+// @param poolIdx The pool index
+// @return The PoolSpecs.Pool struct of the underlying pool */
+// function queryPoolParams (address base, address quote, uint256 poolIdx)
+//     public view returns (PoolSpecs.Pool memory pool) {
+pub const QUERY_POOL_PARAMS_SIG: &str = "queryPoolParams(address,address,uint256)";
+
+//    struct Pool {
+//     uint8 schedule_;
+//     uint16 feeRate_;
+//     uint8 protocolTake_;
+//     uint16 tickSize_;
+//     uint8 jitThresh_;
+//     uint8 knockoutBits_;
+//     uint8 oracleFlags_;
+// }
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct PoolParams {
+    pub schedule: u8,
+    /// The swap fee rate in units of 0.0001%
+    pub fee_rate: u16,
+    /// The protocol's share of swap fees in units of 1/256
+    pub protocol_take: u8,
+    pub tick_size: u16,
+    pub jit_thresh: u8,
+    pub knockout_bits: u8,
+    pub oracle_flags: u8,
+}
+
+pub async fn get_pool_params(
+    web30: &Web3,
+    croc_query: Address,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+) -> Result<PoolParams, AltheaError> {
+    let params_res = web30
+        .simulate_transaction(
+            TransactionRequest::quick_tx(
+                Address::from_str(DEFAULT_QUERIER).unwrap(),
+                croc_query,
+                encode_call(
+                    QUERY_POOL_PARAMS_SIG,
+                    &[base.into(), quote.into(), pool_idx.into()],
+                )?,
+            ),
+            None,
+        )
+        .await?;
+    Ok(PoolParams::from_abi(&params_res))
+}
+
+impl PoolParams {
+    pub fn from_abi(input: &[u8]) -> Self {
+        // The Pool struct is static, so we can treat the response as its contents being returned directly
+        let mut index_start = 0;
+        let schedule = parse_u8(input, index_start);
+        index_start += 32;
+        let fee_rate = parse_u16(input, index_start);
+        index_start += 32;
+        let protocol_take = parse_u8(input, index_start);
+        index_start += 32;
+        let tick_size = parse_u16(input, index_start);
+        index_start += 32;
+        let jit_thresh = parse_u8(input, index_start);
+        index_start += 32;
+        let knockout_bits = parse_u8(input, index_start);
+        index_start += 32;
+        let oracle_flags = parse_u8(input, index_start);
+
+        Self {
+            schedule,
+            fee_rate,
+            protocol_take,
+            tick_size,
+            jit_thresh,
+            knockout_bits,
+            oracle_flags,
+        }
+    }
+
+    /// The fraction of each swap's input paid as fees
+    pub fn fee_fraction(&self) -> f64 {
+        self.fee_rate as f64 / 1_000_000.0
+    }
+
+    /// The fraction of swap fees paid to liquidity providers after the protocol takes its share
+    pub fn lp_fraction(&self) -> f64 {
+        1.0 - self.protocol_take as f64 / 256.0
+    }
+}
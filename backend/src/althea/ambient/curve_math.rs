@@ -0,0 +1,267 @@
+// This file implements the parts of Ambient's CurveMath library needed to interpret indexed pool data
+// Ambient prices are quoted as base tokens per quote token, and are stored as Q64.64 square roots.
+// Reserves follow from liquidity as base = L * sqrt(P) and quote = L / sqrt(P)
+// The floating point functions are only for ratios such as prices and APRs, reported token quantities
+// use the exact fixed point functions, which match the contract's own arithmetic
+
+use clarity::Uint256;
+
+use super::croc_query::CurveState;
+
+/// 2^64, the scaling factor of Q64.64 fixed point numbers
+const Q64: f64 = 18_446_744_073_709_551_616.0;
+/// 2^48, the scaling factor of the Q16.48 seed deflator
+const Q48: f64 = 281_474_976_710_656.0;
+/// The price ratio between two adjacent ticks
+const TICK_BASE: f64 = 1.0001;
+
+/// Converts a Q64.64 square root price into a floating point square root price
+pub fn sqrt_price_from_q64(price_root: u128) -> f64 {
+    price_root as f64 / Q64
+}
+
+/// Returns the tick containing the given (non-zero) floating point square root price
+pub fn tick_from_sqrt_price(sqrt_price: f64) -> i32 {
+    ((sqrt_price * sqrt_price).ln() / TICK_BASE.ln()).floor() as i32
+}
+
+/// Returns the floating point square root price at the lower bound of `tick`
+pub fn sqrt_price_at_tick(tick: i32) -> f64 {
+    TICK_BASE.powf(tick as f64 / 2.0)
+}
+
+/// The ambient (full range) liquidity in a curve, its seeds inflated by the accumulated
+/// rewards represented by the seed deflator
+pub fn ambient_liquidity(curve: &CurveState) -> f64 {
+    curve.ambient_seeds as f64 * (1.0 + curve.seed_deflator as f64 / Q48)
+}
+
+/// The total liquidity active at the curve's current price, ambient liquidity plus any
+/// concentrated liquidity whose range contains the current price
+pub fn active_liquidity(curve: &CurveState) -> f64 {
+    ambient_liquidity(curve) + curve.conc_liq as f64
+}
+
+/// The (base, quote) reserves backing `liq` units of ambient liquidity at `sqrt_price`
+pub fn ambient_reserves(liq: f64, sqrt_price: f64) -> (f64, f64) {
+    if sqrt_price <= 0.0 {
+        return (0.0, 0.0);
+    }
+    (liq * sqrt_price, liq / sqrt_price)
+}
+
+/// The (base, quote) reserves backing `liq` units of concentrated liquidity between `bid_tick`
+/// and `ask_tick` at `sqrt_price`. Below the range the position is entirely quote tokens, above
+/// the range it is entirely base tokens.
+pub fn ranged_reserves(liq: f64, sqrt_price: f64, bid_tick: i32, ask_tick: i32) -> (f64, f64) {
+    let lower = sqrt_price_at_tick(bid_tick);
+    let upper = sqrt_price_at_tick(ask_tick);
+    let price = sqrt_price.clamp(lower, upper);
+    let base = liq * (price - lower);
+    let quote = liq * (1.0 / price - 1.0 / upper);
+    (base, quote)
+}
+
+/// Values a (base, quote) reserve pair in base tokens at `sqrt_price`
+pub fn value_in_base(reserves: (f64, f64), sqrt_price: f64) -> f64 {
+    reserves.0 + reserves.1 * sqrt_price * sqrt_price
+}
+
+/// The Q128.128 square roots of 1.0001^-(2^i), the factors multiplied together by Ambient's TickMath
+const TICK_RATIOS: [u128; 20] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+fn q64() -> Uint256 {
+    Uint256::from(1u128 << 64)
+}
+
+/// Ambient's TickMath.getSqrtRatioAtTick, the Q64.64 square root price at the lower bound of `tick`
+/// rounded up
+pub fn sqrt_ratio_at_tick(tick: i32) -> Uint256 {
+    let abs_tick = tick.unsigned_abs();
+    let q128 = q64() * q64();
+    let mut ratio = if abs_tick & 1 != 0 {
+        Uint256::from(TICK_RATIOS[0])
+    } else {
+        q128
+    };
+    for (bit, factor) in TICK_RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = ratio * Uint256::from(*factor) / q128;
+        }
+    }
+    if tick > 0 {
+        // 2^256 - 1 divided by the Q128.128 ratio, as in the contract
+        let max = (q128 - 1u8.into()) * q128 + (q128 - 1u8.into());
+        ratio = max / ratio;
+    }
+    let root = ratio / q64();
+    if root * q64() == ratio {
+        root
+    } else {
+        root + 1u8.into()
+    }
+}
+
+/// The exact form of `ambient_liquidity`, computed as the contract does with Q16.48 arithmetic
+pub fn ambient_liquidity_exact(curve: &CurveState) -> Uint256 {
+    let seeds = Uint256::from(curve.ambient_seeds);
+    seeds + seeds * Uint256::from(curve.seed_deflator) / Uint256::from(1u128 << 48)
+}
+
+/// The exact form of `active_liquidity`
+pub fn active_liquidity_exact(curve: &CurveState) -> Uint256 {
+    ambient_liquidity_exact(curve) + Uint256::from(curve.conc_liq)
+}
+
+/// The exact form of `ambient_reserves` at the Q64.64 square root price `price_root`, rounded down
+pub fn ambient_reserves_exact(liq: Uint256, price_root: Uint256) -> (Uint256, Uint256) {
+    if price_root == 0u8.into() {
+        return (0u8.into(), 0u8.into());
+    }
+    (liq * price_root / q64(), liq * q64() / price_root)
+}
+
+/// The exact form of `ranged_reserves` at the Q64.64 square root price `price_root`, rounded down
+pub fn ranged_reserves_exact(
+    liq: Uint256,
+    price_root: Uint256,
+    bid_tick: i32,
+    ask_tick: i32,
+) -> (Uint256, Uint256) {
+    let lower = sqrt_ratio_at_tick(bid_tick);
+    let upper = sqrt_ratio_at_tick(ask_tick);
+    let price = price_root.max(lower).min(upper);
+    let base = liq * (price - lower) / q64();
+    let quote = liq * q64() / price - liq * q64() / upper;
+    (base, quote)
+}
+
+/// The exact form of `value_in_base` at the Q64.64 square root price `price_root`, rounded down
+pub fn value_in_base_exact(reserves: (Uint256, Uint256), price_root: Uint256) -> Uint256 {
+    reserves.0 + reserves.1 * price_root / q64() * price_root / q64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::althea::amount::Amount;
+
+    const Q64_INT: u128 = 1 << 64;
+
+    #[test]
+    fn test_sqrt_ratio_at_tick() {
+        assert_eq!(sqrt_ratio_at_tick(0), Q64_INT.into());
+        assert_eq!(sqrt_ratio_at_tick(1), 18447666387855959851u128.into());
+        assert_eq!(sqrt_ratio_at_tick(-1), 18445821805675392312u128.into());
+        assert_eq!(sqrt_ratio_at_tick(100), 18539204128674405813u128.into());
+        assert_eq!(sqrt_ratio_at_tick(-100), 18354745142194483564u128.into());
+        // Ambient's MIN_SQRT_RATIO and MAX_SQRT_RATIO at MIN_TICK and MAX_TICK
+        assert_eq!(sqrt_ratio_at_tick(-665454), 65538u128.into());
+        assert_eq!(
+            sqrt_ratio_at_tick(831818),
+            21267430153580247136652501917186561138u128.into()
+        );
+
+        // The floating point approximation agrees to well within a part per billion
+        for tick in [-200000, -13863, -1, 1, 13863, 200000] {
+            let exact = Amount::new(sqrt_ratio_at_tick(tick), 0).to_f64() / Q64;
+            let approx = sqrt_price_at_tick(tick);
+            assert!((exact - approx).abs() / approx < 1e-9, "tick {}", tick);
+        }
+    }
+
+    #[test]
+    fn test_ambient_liquidity() {
+        // A deflator of 0.5 in Q16.48 inflates the seeds by half
+        let curve = CurveState {
+            price_root: Q64_INT,
+            ambient_seeds: 1_000_000,
+            conc_liq: 250_000,
+            seed_deflator: 1 << 47,
+            conc_growth: 0,
+        };
+        assert_eq!(ambient_liquidity_exact(&curve), 1_500_000u128.into());
+        assert_eq!(active_liquidity_exact(&curve), 1_750_000u128.into());
+        assert_eq!(ambient_liquidity(&curve), 1_500_000.0);
+    }
+
+    #[test]
+    fn test_ambient_reserves() {
+        let liq = Uint256::from(1_000_000_000_000_000_000u128);
+        // At a price of 4 the reserves are L * 2 base and L / 2 quote
+        assert_eq!(
+            ambient_reserves_exact(liq, (2 * Q64_INT).into()),
+            (
+                2_000_000_000_000_000_000u128.into(),
+                500_000_000_000_000_000u128.into()
+            )
+        );
+        // At a price of 2.25 the quote reserve is rounded down
+        assert_eq!(
+            ambient_reserves_exact(liq, (3 * Q64_INT / 2).into()),
+            (
+                1_500_000_000_000_000_000u128.into(),
+                666_666_666_666_666_666u128.into()
+            )
+        );
+        assert_eq!(
+            ambient_reserves_exact(liq, 0u8.into()),
+            (0u8.into(), 0u8.into())
+        );
+    }
+
+    #[test]
+    fn test_ranged_reserves() {
+        let liq = Uint256::from(1_000_000_000_000_000_000u128);
+        let price_root = Uint256::from(2 * Q64_INT);
+        // In range from a price of 1 to just under 4: L * (2 - 1) base and L * (1/2 - 1/sqrt(upper)) quote
+        assert_eq!(
+            ranged_reserves_exact(liq, price_root, 0, 27726),
+            (
+                1_000_000_000_000_000_000u128.into(),
+                249_984_081_618_422_477u128.into()
+            )
+        );
+        // The price is above the range, so the position is entirely base tokens
+        assert_eq!(
+            ranged_reserves_exact(liq, price_root, -100, 100),
+            (9_999_541_693_800_299u128.into(), 0u8.into())
+        );
+        // The price is below the range, so the position is entirely quote tokens
+        assert_eq!(
+            ranged_reserves_exact(liq, price_root, 20000, 30000),
+            (0u8.into(), 144_750_939_954_764_285u128.into())
+        );
+    }
+
+    #[test]
+    fn test_value_in_base() {
+        let price_root = Uint256::from(2 * Q64_INT);
+        assert_eq!(
+            value_in_base_exact((100u8.into(), 25u8.into()), price_root),
+            200u8.into()
+        );
+        assert_eq!(value_in_base((100.0, 25.0), 2.0), 200.0);
+    }
+}
@@ -0,0 +1,797 @@
+// This file provides declarative decoding of the Ethereum event logs we index
+// Each event declares its fields in order, noting which are indexed, and every layout is checked against
+// its event's Solidity signature once when the indexer starts. Indexed values are read from topics[1], [2] and [3],
+// all other values are read from the data field as consecutive 32 byte words (every type we index is static).
+
+use clarity::{abi::derive_signature, Address, Uint256};
+use web30::types::Log;
+
+use crate::althea::{abi_util::to_hex_string, error::AltheaError};
+
+/// The Solidity types used by the events we index, `Uint` and `Int` carry their bit width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolType {
+    Address,
+    Bool,
+    Uint(u16),
+    Int(u16),
+}
+
+impl SolType {
+    /// The name of the type as it appears in an event signature, e.g. uint128 or int24
+    pub fn canonical_name(&self) -> String {
+        match self {
+            SolType::Address => "address".to_string(),
+            SolType::Bool => "bool".to_string(),
+            SolType::Uint(bits) => format!("uint{}", bits),
+            SolType::Int(bits) => format!("int{}", bits),
+        }
+    }
+}
+
+/// A single field of an event, in declaration order
+#[derive(Debug, Clone, Copy)]
+pub struct EventField {
+    pub name: &'static str,
+    pub ty: SolType,
+    pub indexed: bool,
+}
+
+impl EventField {
+    pub const fn indexed(name: &'static str, ty: SolType) -> EventField {
+        EventField {
+            name,
+            ty,
+            indexed: true,
+        }
+    }
+
+    pub const fn data(name: &'static str, ty: SolType) -> EventField {
+        EventField {
+            name,
+            ty,
+            indexed: false,
+        }
+    }
+}
+
+/// A decoded field value, unsigned integers keep their full 32 byte word and are narrowed on access
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiValue {
+    Address(Address),
+    Bool(bool),
+    Uint([u8; 32]),
+    Int(i128),
+}
+
+/// An event log decoded according to its declared layout
+#[derive(Debug, Clone)]
+pub struct DecodedLog {
+    pub signature: &'static str,
+    pub block_height: Uint256,
+    pub log_index: Uint256,
+    pub tx_hash: Option<String>,
+    pub values: Vec<(&'static str, AbiValue)>,
+}
+
+/// Implemented by every event we index, decoding is driven entirely by SIGNATURE and FIELDS
+pub trait EventDecoder: Sized {
+    /// The Solidity event signature, e.g. "SafeMode(bool)"
+    const SIGNATURE: &'static str;
+    /// The event's fields in declaration order
+    const FIELDS: &'static [EventField];
+
+    /// Builds the event from its decoded values
+    fn from_decoded(log: &DecodedLog) -> Result<Self, AltheaError>;
+
+    /// Parse a single event from a Log, both the indexed topics and the data bytes are decoded
+    fn from_log(input: &Log) -> Result<Self, AltheaError> {
+        let decoded = decode_log(Self::SIGNATURE, Self::FIELDS, input)?;
+        Self::from_decoded(&decoded)
+    }
+
+    /// Parse multiple logs into events
+    fn from_logs(input: &[Log]) -> Result<Vec<Self>, AltheaError> {
+        let mut res = Vec::new();
+        for item in input {
+            res.push(Self::from_log(item)?);
+        }
+        Ok(res)
+    }
+}
+
+/// Checks that `fields` describes the event `signature`, the parameter types must match in order and
+/// at most three fields may be indexed
+pub fn validate_layout(signature: &str, fields: &[EventField]) -> Result<(), AltheaError> {
+    let params = match (signature.find('('), signature.strip_suffix(')')) {
+        (Some(open), Some(stripped)) if open > 0 => &stripped[open + 1..],
+        _ => {
+            return Err(AltheaError::InvalidEventLogError(format!(
+                "Malformed event signature {}",
+                signature
+            )))
+        }
+    };
+    let params = if params.is_empty() {
+        vec![]
+    } else {
+        params.split(',').collect::<Vec<_>>()
+    };
+    if params.len() != fields.len() {
+        return Err(AltheaError::InvalidEventLogError(format!(
+            "{} has {} parameters but {} fields were declared",
+            signature,
+            params.len(),
+            fields.len()
+        )));
+    }
+    for (param, field) in params.iter().zip(fields) {
+        let supported = match field.ty {
+            SolType::Uint(bits) => bits % 8 == 0 && (8..=256).contains(&bits),
+            SolType::Int(bits) => bits % 8 == 0 && (8..=128).contains(&bits),
+            SolType::Address | SolType::Bool => true,
+        };
+        if !supported || *param != field.ty.canonical_name() {
+            return Err(AltheaError::InvalidEventLogError(format!(
+                "Field {} of {} declared as {} but the signature has {}",
+                field.name,
+                signature,
+                field.ty.canonical_name(),
+                param
+            )));
+        }
+    }
+    if fields.iter().filter(|f| f.indexed).count() > 3 {
+        return Err(AltheaError::InvalidEventLogError(format!(
+            "{} declares more than three indexed fields",
+            signature
+        )));
+    }
+    Ok(())
+}
+
+/// Decodes `input` as the event `signature` laid out as `fields`, which must have passed validate_layout
+pub fn decode_log(
+    signature: &'static str,
+    fields: &[EventField],
+    input: &Log,
+) -> Result<DecodedLog, AltheaError> {
+    let indexed = fields.iter().filter(|f| f.indexed).count();
+    let words = fields.len() - indexed;
+    // the first topic is the event's identifier, followed by each indexed value
+    if input.topics.len() < indexed + 1 {
+        return Err(AltheaError::InvalidEventLogError(format!(
+            "Too few topics for {}",
+            signature
+        )));
+    }
+    let topic0: &[u8] = &input.topics[0];
+    if topic0 != derive_signature(signature)?.as_slice() {
+        return Err(AltheaError::InvalidEventLogError(format!(
+            "Log is not a {} event",
+            signature
+        )));
+    }
+    if input.data.len() < words * 32 {
+        return Err(AltheaError::InvalidEventLogError(format!(
+            "too short for {}",
+            signature
+        )));
+    }
+    let block_height = if let Some(bn) = input.block_number {
+        bn
+    } else {
+        return Err(AltheaError::InvalidEventLogError(
+            "Log does not have block number, we only search logs already in blocks?".to_string(),
+        ));
+    };
+    // Several events may occur in the same block, the log index keeps them distinct
+    let log_index = input.log_index.unwrap_or_default();
+    let tx_hash = input.transaction_hash.as_ref().map(|h| to_hex_string(h));
+
+    let data: &[u8] = &input.data;
+    let mut values = Vec::with_capacity(fields.len());
+    let mut topic = 1;
+    let mut word = 0;
+    for field in fields {
+        let bytes: &[u8] = if field.indexed {
+            topic += 1;
+            &input.topics[topic - 1]
+        } else {
+            word += 1;
+            &data[(word - 1) * 32..word * 32]
+        };
+        values.push((field.name, decode_word(field, bytes)?));
+    }
+
+    Ok(DecodedLog {
+        signature,
+        block_height,
+        log_index,
+        tx_hash,
+        values,
+    })
+}
+
+// Decodes a single 32 byte word, rejecting any word whose unused high order bytes are not a valid
+// zero or sign extension of the declared type
+fn decode_word(field: &EventField, bytes: &[u8]) -> Result<AbiValue, AltheaError> {
+    let word: [u8; 32] = bytes.try_into().map_err(|_| {
+        AltheaError::InvalidEventLogError(format!("{} is not a 32 byte word", field.name))
+    })?;
+    let invalid = || {
+        AltheaError::InvalidEventLogError(format!(
+            "Invalid {} {}, probably incorrect parsing",
+            field.ty.canonical_name(),
+            field.name
+        ))
+    };
+    match field.ty {
+        SolType::Address => {
+            if word[..12].iter().any(|b| *b != 0) {
+                return Err(invalid());
+            }
+            let address = Address::from_slice(&word[12..]).map_err(|e| {
+                AltheaError::InvalidEventLogError(format!("Invalid {} address: {}", field.name, e))
+            })?;
+            Ok(AbiValue::Address(address))
+        }
+        SolType::Bool => {
+            if word[..31].iter().any(|b| *b != 0) || word[31] > 1 {
+                return Err(invalid());
+            }
+            Ok(AbiValue::Bool(word[31] == 1))
+        }
+        SolType::Uint(bits) => {
+            let width = bits as usize / 8;
+            if word[..32 - width].iter().any(|b| *b != 0) {
+                return Err(invalid());
+            }
+            Ok(AbiValue::Uint(word))
+        }
+        SolType::Int(bits) => {
+            // The value's own sign bit determines what every higher order byte must be
+            let width = bits as usize / 8;
+            let extension = if word[32 - width] & 0x80 != 0 {
+                0xff
+            } else {
+                0x00
+            };
+            if word[..32 - width].iter().any(|b| *b != extension) {
+                return Err(invalid());
+            }
+            let mut value = [extension; 16];
+            value[16 - width..].copy_from_slice(&word[32 - width..]);
+            Ok(AbiValue::Int(i128::from_be_bytes(value)))
+        }
+    }
+}
+
+impl DecodedLog {
+    fn get(&self, name: &str) -> Result<&AbiValue, AltheaError> {
+        self.values
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v)
+            .ok_or_else(|| {
+                AltheaError::InvalidEventLogError(format!(
+                    "{} has no field {}",
+                    self.signature, name
+                ))
+            })
+    }
+
+    fn mismatch(&self, name: &str, ty: &str) -> AltheaError {
+        AltheaError::InvalidEventLogError(format!(
+            "Field {} of {} does not fit in {}",
+            name, self.signature, ty
+        ))
+    }
+
+    pub fn address(&self, name: &str) -> Result<Address, AltheaError> {
+        match self.get(name)? {
+            AbiValue::Address(a) => Ok(*a),
+            _ => Err(self.mismatch(name, "address")),
+        }
+    }
+
+    pub fn bool(&self, name: &str) -> Result<bool, AltheaError> {
+        match self.get(name)? {
+            AbiValue::Bool(b) => Ok(*b),
+            _ => Err(self.mismatch(name, "bool")),
+        }
+    }
+
+    pub fn uint256(&self, name: &str) -> Result<Uint256, AltheaError> {
+        match self.get(name)? {
+            AbiValue::Uint(word) => Ok(Uint256::from_be_bytes(word)),
+            _ => Err(self.mismatch(name, "uint256")),
+        }
+    }
+
+    // Returns the low order `N` bytes of an unsigned field, erroring if any higher byte is set
+    fn uint_bytes<const N: usize>(&self, name: &str, ty: &str) -> Result<[u8; N], AltheaError> {
+        match self.get(name)? {
+            AbiValue::Uint(word) if word[..32 - N].iter().all(|b| *b == 0) => {
+                Ok(word[32 - N..].try_into().unwrap())
+            }
+            _ => Err(self.mismatch(name, ty)),
+        }
+    }
+
+    pub fn u128(&self, name: &str) -> Result<u128, AltheaError> {
+        Ok(u128::from_be_bytes(self.uint_bytes(name, "u128")?))
+    }
+
+    pub fn u64(&self, name: &str) -> Result<u64, AltheaError> {
+        Ok(u64::from_be_bytes(self.uint_bytes(name, "u64")?))
+    }
+
+    pub fn u16(&self, name: &str) -> Result<u16, AltheaError> {
+        Ok(u16::from_be_bytes(self.uint_bytes(name, "u16")?))
+    }
+
+    pub fn u8(&self, name: &str) -> Result<u8, AltheaError> {
+        Ok(u8::from_be_bytes(self.uint_bytes(name, "u8")?))
+    }
+
+    pub fn i32(&self, name: &str) -> Result<i32, AltheaError> {
+        match self.get(name)? {
+            AbiValue::Int(v) => i32::try_from(*v).map_err(|_| self.mismatch(name, "i32")),
+            _ => Err(self.mismatch(name, "i32")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::althea::ambient::{
+        events::{
+            AUTHORITY_TRANSFER_SIGNATURE, HOT_PATH_OPEN_SIGNATURE, PRICE_IMPROVE_THRESH_SIGNATURE,
+            PROTOCOL_DIVIDEND_SIGNATURE, RESYNC_TAKE_RATE_SIGNATURE, SAFE_MODE_SIGNATURE,
+            SET_NEW_POOL_LIQ_SIGNATURE, SET_RELAYER_TAKE_RATE_SIGNATURE, SET_TAKE_RATE_SIGNATURE,
+            TREASURY_SET_SIGNATURE, UPGRADE_PROXY_SIGNATURE,
+        },
+        governance::{GovernanceAction, GovernanceEvent},
+        pools::InitPoolEvent,
+        positions::{
+            BurnAmbientEvent, BurnRangedEvent, HarvestEvent, MintAmbientEvent, MintRangedEvent,
+        },
+        swaps::SwapEvent,
+        templates::{DisablePoolTemplateEvent, SetPoolTemplateEvent},
+        validate_event_layouts,
+    };
+
+    const USER: &str = "0x000000000000000000000000000000000000000000000000000000000000aaaa";
+    const BASE: &str = "0x000000000000000000000000000000000000000000000000000000000000bbbb";
+    const QUOTE: &str = "0x000000000000000000000000000000000000000000000000000000000000cccc";
+    // 36000
+    const POOL_IDX: &str = "0000000000000000000000000000000000000000000000000000000000008ca0";
+
+    // Builds a log as returned by eth_getLogs, `topics` follow the event's topic0
+    fn log(signature: &str, topics: &[&str], data: &[&str]) -> Log {
+        let topic0 = format!("0x{}", to_hex(&derive_signature(signature).unwrap()));
+        let mut all_topics = vec![topic0];
+        all_topics.extend(topics.iter().map(|t| t.to_string()));
+        serde_json::from_value(serde_json::json!({
+            "address": "0x7580bfe88dd3d07947908fae12d95872a260f2d8",
+            "blockHash": "0x1111111111111111111111111111111111111111111111111111111111111111",
+            "blockNumber": "0x3039",
+            "data": format!("0x{}", data.concat()),
+            "logIndex": "0x2",
+            "removed": false,
+            "topics": all_topics,
+            "transactionHash": "0x2222222222222222222222222222222222222222222222222222222222222222",
+            "transactionIndex": "0x0",
+        }))
+        .unwrap()
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn address(topic: &str) -> Address {
+        Address::from_str(&format!("0x{}", &topic[26..])).unwrap()
+    }
+
+    // An ABI encoded unsigned integer word
+    fn uint(value: u128) -> String {
+        format!("{:064x}", value)
+    }
+
+    // An ABI encoded signed integer word, sign extended to 256 bits
+    fn int(value: i32) -> String {
+        let fill = if value < 0 { "f" } else { "0" };
+        format!("{}{:08x}", fill.repeat(56), value)
+    }
+
+    // An address or word topic as a data word
+    fn word(topic: &str) -> &str {
+        &topic[2..]
+    }
+
+    #[test]
+    fn test_event_layouts() {
+        validate_event_layouts().unwrap();
+    }
+
+    #[test]
+    fn test_decode_swap() {
+        let input = log(
+            SwapEvent::SIGNATURE,
+            &[USER, BASE, QUOTE],
+            &[
+                POOL_IDX,
+                // 10^18
+                "0000000000000000000000000000000000000000000000000de0b6b3a7640000",
+                // 2500000
+                "00000000000000000000000000000000000000000000000000000000002625a0",
+            ],
+        );
+        let swap = SwapEvent::from_log(&input).unwrap();
+        assert_eq!(
+            swap,
+            SwapEvent {
+                block_height: 12345u32.into(),
+                log_index: 2u8.into(),
+                user: address(USER),
+                buy: address(BASE),
+                sell: address(QUOTE),
+                pool_idx: 36000u32.into(),
+                buy_qty: 1_000_000_000_000_000_000,
+                sell_qty: 2_500_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_negative_ticks() {
+        let input = log(
+            MintRangedEvent::SIGNATURE,
+            &[USER, BASE, QUOTE],
+            &[
+                POOL_IDX,
+                // liq 1000000
+                "00000000000000000000000000000000000000000000000000000000000f4240",
+                // bidTick -100
+                "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff9c",
+                // askTick 200
+                "00000000000000000000000000000000000000000000000000000000000000c8",
+                // baseQty 5, quoteQty 7
+                "0000000000000000000000000000000000000000000000000000000000000005",
+                "0000000000000000000000000000000000000000000000000000000000000007",
+            ],
+        );
+        let mint = MintRangedEvent::from_log(&input).unwrap();
+        assert_eq!(mint.bid_tick, -100);
+        assert_eq!(mint.ask_tick, 200);
+        assert_eq!(mint.liq, 1_000_000);
+        assert_eq!(mint.base_qty, 5);
+        assert_eq!(mint.quote_qty, 7);
+        assert_eq!(mint.pool_idx, 36000u32.into());
+        assert_eq!(mint.base, address(BASE));
+    }
+
+    #[test]
+    fn test_decode_init_pool() {
+        let input = log(
+            InitPoolEvent::SIGNATURE,
+            &[BASE, QUOTE, &format!("0x{}", POOL_IDX)],
+            &[
+                // price 2^64
+                &uint(1 << 64),
+                word(USER),
+                &uint(1_000_000),
+                &uint(5),
+                &uint(7),
+            ],
+        );
+        assert_eq!(
+            InitPoolEvent::from_log(&input).unwrap(),
+            InitPoolEvent {
+                block_height: 12345u32.into(),
+                base: address(BASE),
+                quote: address(QUOTE),
+                pool_idx: 36000u32.into(),
+                creator: address(USER),
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_ranged_events() {
+        let data: [&str; 6] = [
+            POOL_IDX,
+            &uint(1_000_000),
+            &int(-887272),
+            &int(887272),
+            &uint(u128::MAX),
+            &uint(7),
+        ];
+        let input = log(BurnRangedEvent::SIGNATURE, &[USER, BASE, QUOTE], &data);
+        assert_eq!(
+            BurnRangedEvent::from_log(&input).unwrap(),
+            BurnRangedEvent {
+                block_height: 12345u32.into(),
+                user: address(USER),
+                base: address(BASE),
+                quote: address(QUOTE),
+                pool_idx: 36000u32.into(),
+                bid_tick: -887272,
+                ask_tick: 887272,
+                liq: 1_000_000,
+                base_qty: u128::MAX,
+                quote_qty: 7,
+            }
+        );
+
+        // Harvest has the ranged layout without liq
+        let input = log(
+            HarvestEvent::SIGNATURE,
+            &[USER, BASE, QUOTE],
+            &[POOL_IDX, &int(-200), &int(-100), &uint(5), &uint(7)],
+        );
+        assert_eq!(
+            HarvestEvent::from_log(&input).unwrap(),
+            HarvestEvent {
+                block_height: 12345u32.into(),
+                user: address(USER),
+                base: address(BASE),
+                quote: address(QUOTE),
+                pool_idx: 36000u32.into(),
+                bid_tick: -200,
+                ask_tick: -100,
+                base_qty: 5,
+                quote_qty: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_ambient_events() {
+        let data: [&str; 4] = [POOL_IDX, &uint(1_000_000), &uint(5), &uint(7)];
+        let input = log(MintAmbientEvent::SIGNATURE, &[USER, BASE, QUOTE], &data);
+        assert_eq!(
+            MintAmbientEvent::from_log(&input).unwrap(),
+            MintAmbientEvent {
+                block_height: 12345u32.into(),
+                user: address(USER),
+                base: address(BASE),
+                quote: address(QUOTE),
+                pool_idx: 36000u32.into(),
+                liq: 1_000_000,
+                base_qty: 5,
+                quote_qty: 7,
+            }
+        );
+
+        let input = log(BurnAmbientEvent::SIGNATURE, &[USER, BASE, QUOTE], &data);
+        assert_eq!(
+            BurnAmbientEvent::from_log(&input).unwrap(),
+            BurnAmbientEvent {
+                block_height: 12345u32.into(),
+                user: address(USER),
+                base: address(BASE),
+                quote: address(QUOTE),
+                pool_idx: 36000u32.into(),
+                liq: 1_000_000,
+                base_qty: 5,
+                quote_qty: 7,
+            }
+        );
+        // The ambient layout does not decode a ranged log
+        let input = log(BurnRangedEvent::SIGNATURE, &[USER, BASE, QUOTE], &data);
+        assert!(BurnAmbientEvent::from_log(&input).is_err());
+    }
+
+    #[test]
+    fn test_decode_template_events() {
+        let pool_idx = format!("0x{}", POOL_IDX);
+        let input = log(
+            SetPoolTemplateEvent::SIGNATURE,
+            &[&pool_idx],
+            &[&uint(3000), &uint(16), &uint(30), &uint(0x41), &uint(0)],
+        );
+        assert_eq!(
+            SetPoolTemplateEvent::from_log(&input).unwrap(),
+            SetPoolTemplateEvent {
+                block_height: 12345u32.into(),
+                log_index: 2u8.into(),
+                pool_idx: 36000u32.into(),
+                fee_rate: 3000,
+                tick_size: 16,
+                jit_thresh: 30,
+                knockout: 0x41,
+                oracle_flags: 0,
+            }
+        );
+        // A fee rate wider than its uint16
+        let input = log(
+            SetPoolTemplateEvent::SIGNATURE,
+            &[&pool_idx],
+            &[&uint(0x10000), &uint(16), &uint(30), &uint(0x41), &uint(0)],
+        );
+        assert!(SetPoolTemplateEvent::from_log(&input).is_err());
+
+        let input = log(DisablePoolTemplateEvent::SIGNATURE, &[&pool_idx], &[]);
+        assert_eq!(
+            DisablePoolTemplateEvent::from_log(&input).unwrap(),
+            DisablePoolTemplateEvent {
+                block_height: 12345u32.into(),
+                log_index: 2u8.into(),
+                pool_idx: 36000u32.into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_governance_events() {
+        let start_time = format!("0x{}", uint(1_700_000_000));
+        let cases: Vec<(&'static str, Vec<&str>, Vec<String>, GovernanceAction)> = vec![
+            (
+                AUTHORITY_TRANSFER_SIGNATURE,
+                vec![USER],
+                vec![],
+                GovernanceAction::AuthorityTransfer {
+                    authority: address(USER),
+                },
+            ),
+            (
+                SET_NEW_POOL_LIQ_SIGNATURE,
+                vec![],
+                vec![uint(10_000)],
+                GovernanceAction::SetNewPoolLiq { liq: 10_000 },
+            ),
+            (
+                SET_TAKE_RATE_SIGNATURE,
+                vec![],
+                vec![uint(32)],
+                GovernanceAction::SetTakeRate { take_rate: 32 },
+            ),
+            (
+                SET_RELAYER_TAKE_RATE_SIGNATURE,
+                vec![],
+                vec![uint(128)],
+                GovernanceAction::SetRelayerTakeRate { take_rate: 128 },
+            ),
+            (
+                RESYNC_TAKE_RATE_SIGNATURE,
+                vec![
+                    BASE,
+                    QUOTE,
+                    "0x0000000000000000000000000000000000000000000000000000000000008ca0",
+                ],
+                vec![uint(32)],
+                GovernanceAction::ResyncTakeRate {
+                    base: address(BASE),
+                    quote: address(QUOTE),
+                    pool_idx: 36000u32.into(),
+                    take_rate: 32,
+                },
+            ),
+            (
+                PRICE_IMPROVE_THRESH_SIGNATURE,
+                vec![BASE],
+                vec![uint(1_000_000), uint(4)],
+                GovernanceAction::PriceImproveThresh {
+                    token: address(BASE),
+                    unit_tick_collateral: 1_000_000,
+                    away_tick_tol: 4,
+                },
+            ),
+            (
+                TREASURY_SET_SIGNATURE,
+                vec![USER, start_time.as_str()],
+                vec![],
+                GovernanceAction::TreasurySet {
+                    treasury: address(USER),
+                    start_time: 1_700_000_000,
+                },
+            ),
+            (
+                PROTOCOL_DIVIDEND_SIGNATURE,
+                vec![BASE, USER],
+                vec![],
+                GovernanceAction::ProtocolDividend {
+                    token: address(BASE),
+                    recv: address(USER),
+                },
+            ),
+            (
+                UPGRADE_PROXY_SIGNATURE,
+                vec![USER],
+                vec![uint(4)],
+                GovernanceAction::UpgradeProxy {
+                    proxy: address(USER),
+                    proxy_idx: 4,
+                },
+            ),
+            (
+                HOT_PATH_OPEN_SIGNATURE,
+                vec![],
+                vec![uint(0)],
+                GovernanceAction::HotPathOpen { open: false },
+            ),
+            (
+                SAFE_MODE_SIGNATURE,
+                vec![],
+                vec![uint(1)],
+                GovernanceAction::SafeMode { enabled: true },
+            ),
+        ];
+        for (signature, topics, data, action) in cases {
+            let data: Vec<&str> = data.iter().map(|d| d.as_str()).collect();
+            let event =
+                GovernanceEvent::from_log(signature, &log(signature, &topics, &data)).unwrap();
+            assert_eq!(event.action, action, "{}", signature);
+            assert_eq!(event.block_height, 12345u32.into());
+            assert_eq!(event.log_index, 2u8.into());
+        }
+
+        // A bool other than 0 or 1
+        let input = log(SAFE_MODE_SIGNATURE, &[], &[&uint(2)]);
+        assert!(GovernanceEvent::from_log(SAFE_MODE_SIGNATURE, &input).is_err());
+    }
+
+    #[test]
+    fn test_reject_malformed_logs() {
+        let data = [
+            POOL_IDX,
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+        ];
+        // A log of another event with the same layout
+        let input = log(
+            "Swap(address,address,address,uint256,uint128,uint256)",
+            &[USER, BASE, QUOTE],
+            &data,
+        );
+        assert!(SwapEvent::from_log(&input).is_err());
+        // An address topic with dirty high order bytes
+        let input = log(
+            SwapEvent::SIGNATURE,
+            &[
+                "0x000000000000000000000001000000000000000000000000000000000000aaaa",
+                BASE,
+                QUOTE,
+            ],
+            &data,
+        );
+        assert!(SwapEvent::from_log(&input).is_err());
+        // A uint128 wider than 128 bits
+        let input = log(
+            SwapEvent::SIGNATURE,
+            &[USER, BASE, QUOTE],
+            &[
+                POOL_IDX,
+                "0000000000000000000000000000000100000000000000000000000000000000",
+                data[2],
+            ],
+        );
+        assert!(SwapEvent::from_log(&input).is_err());
+        // Missing data
+        let input = log(SwapEvent::SIGNATURE, &[USER, BASE, QUOTE], &data[..2]);
+        assert!(SwapEvent::from_log(&input).is_err());
+    }
+
+    #[test]
+    fn test_reject_bad_sign_extension() {
+        let field = EventField::data("tick", SolType::Int(24));
+        // -100 sign extended to 24 bits but not to 256
+        let word = hex_word("0000000000000000000000000000000000000000000000000000000000ffff9c");
+        assert!(decode_word(&field, &word).is_err());
+        let word = hex_word("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff9c");
+        assert_eq!(decode_word(&field, &word).unwrap(), AbiValue::Int(-100));
+    }
+
+    fn hex_word(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+}
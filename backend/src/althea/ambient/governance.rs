@@ -0,0 +1,223 @@
+use clarity::{Address, Uint256};
+use serde::{Deserialize, Serialize};
+use web30::types::Log;
+
+use super::{
+    decoder::{decode_log, DecodedLog, EventField, SolType},
+    events::{
+        AUTHORITY_TRANSFER_SIGNATURE, HOT_PATH_OPEN_SIGNATURE, PRICE_IMPROVE_THRESH_SIGNATURE,
+        PROTOCOL_DIVIDEND_SIGNATURE, RESYNC_TAKE_RATE_SIGNATURE, SAFE_MODE_SIGNATURE,
+        SET_NEW_POOL_LIQ_SIGNATURE, SET_RELAYER_TAKE_RATE_SIGNATURE, SET_TAKE_RATE_SIGNATURE,
+        TREASURY_SET_SIGNATURE, UPGRADE_PROXY_SIGNATURE,
+    },
+};
+use crate::althea::error::AltheaError;
+
+/// The protocol-level events emitted by CrocSwapDex when governance changes its configuration,
+/// every one of these is recorded in the governance log
+pub const GOVERNANCE_SIGNATURES: &[&str] = &[
+    AUTHORITY_TRANSFER_SIGNATURE,
+    SET_NEW_POOL_LIQ_SIGNATURE,
+    SET_TAKE_RATE_SIGNATURE,
+    SET_RELAYER_TAKE_RATE_SIGNATURE,
+    RESYNC_TAKE_RATE_SIGNATURE,
+    PRICE_IMPROVE_THRESH_SIGNATURE,
+    TREASURY_SET_SIGNATURE,
+    PROTOCOL_DIVIDEND_SIGNATURE,
+    UPGRADE_PROXY_SIGNATURE,
+    HOT_PATH_OPEN_SIGNATURE,
+    SAFE_MODE_SIGNATURE,
+];
+
+/// A single entry in the governance log, see events.rs for the meaning of each action
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub struct GovernanceEvent {
+    pub block_height: Uint256,
+    pub log_index: Uint256,
+    pub tx_hash: Option<String>,
+    pub action: GovernanceAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum GovernanceAction {
+    AuthorityTransfer {
+        authority: Address,
+    },
+    SetNewPoolLiq {
+        liq: u128,
+    },
+    /// The take rate is in units of 1/256
+    SetTakeRate {
+        take_rate: u8,
+    },
+    /// The take rate is in units of 1/256
+    SetRelayerTakeRate {
+        take_rate: u8,
+    },
+    ResyncTakeRate {
+        base: Address,
+        quote: Address,
+        pool_idx: Uint256,
+        take_rate: u8,
+    },
+    PriceImproveThresh {
+        token: Address,
+        unit_tick_collateral: u128,
+        away_tick_tol: u16,
+    },
+    TreasurySet {
+        treasury: Address,
+        start_time: u64,
+    },
+    ProtocolDividend {
+        token: Address,
+        recv: Address,
+    },
+    UpgradeProxy {
+        proxy: Address,
+        proxy_idx: u16,
+    },
+    HotPathOpen {
+        open: bool,
+    },
+    SafeMode {
+        enabled: bool,
+    },
+}
+
+impl GovernanceEvent {
+    /// Parse multiple logs, all emitted for the event with the given `signature`, into GovernanceEvents
+    pub fn from_logs(
+        signature: &'static str,
+        input: &[Log],
+    ) -> Result<Vec<GovernanceEvent>, AltheaError> {
+        let mut res = Vec::new();
+        for item in input {
+            res.push(GovernanceEvent::from_log(signature, item)?);
+        }
+        Ok(res)
+    }
+
+    // Parse a single GovernanceEvent from a Log emitted for the event with the given `signature`
+    pub fn from_log(signature: &'static str, input: &Log) -> Result<GovernanceEvent, AltheaError> {
+        let decoded = decode_log(signature, governance_fields(signature)?, input)?;
+        let action = GovernanceAction::from_decoded(&decoded)?;
+
+        Ok(GovernanceEvent {
+            block_height: decoded.block_height,
+            log_index: decoded.log_index,
+            tx_hash: decoded.tx_hash,
+            action,
+        })
+    }
+}
+
+const AUTHORITY_TRANSFER_FIELDS: &[EventField] =
+    &[EventField::indexed("authority", SolType::Address)];
+const SET_NEW_POOL_LIQ_FIELDS: &[EventField] = &[EventField::data("liq", SolType::Uint(128))];
+// Shared by SetTakeRate and SetRelayerTakeRate
+const TAKE_RATE_FIELDS: &[EventField] = &[EventField::data("takeRate", SolType::Uint(8))];
+const RESYNC_TAKE_RATE_FIELDS: &[EventField] = &[
+    EventField::indexed("base", SolType::Address),
+    EventField::indexed("quote", SolType::Address),
+    EventField::indexed("poolIdx", SolType::Uint(256)),
+    EventField::data("takeRate", SolType::Uint(8)),
+];
+const PRICE_IMPROVE_THRESH_FIELDS: &[EventField] = &[
+    EventField::indexed("token", SolType::Address),
+    EventField::data("unitTickCollateral", SolType::Uint(128)),
+    EventField::data("awayTickTol", SolType::Uint(16)),
+];
+const TREASURY_SET_FIELDS: &[EventField] = &[
+    EventField::indexed("treasury", SolType::Address),
+    EventField::indexed("startTime", SolType::Uint(64)),
+];
+const PROTOCOL_DIVIDEND_FIELDS: &[EventField] = &[
+    EventField::indexed("token", SolType::Address),
+    EventField::indexed("recv", SolType::Address),
+];
+const UPGRADE_PROXY_FIELDS: &[EventField] = &[
+    EventField::indexed("proxy", SolType::Address),
+    EventField::data("proxyIdx", SolType::Uint(16)),
+];
+const HOT_PATH_OPEN_FIELDS: &[EventField] = &[EventField::data("open", SolType::Bool)];
+const SAFE_MODE_FIELDS: &[EventField] = &[EventField::data("enabled", SolType::Bool)];
+
+/// The field layout of each governance event, see events.rs for the full event declarations
+pub fn governance_fields(signature: &str) -> Result<&'static [EventField], AltheaError> {
+    let fields = match signature {
+        AUTHORITY_TRANSFER_SIGNATURE => AUTHORITY_TRANSFER_FIELDS,
+        SET_NEW_POOL_LIQ_SIGNATURE => SET_NEW_POOL_LIQ_FIELDS,
+        SET_TAKE_RATE_SIGNATURE | SET_RELAYER_TAKE_RATE_SIGNATURE => TAKE_RATE_FIELDS,
+        RESYNC_TAKE_RATE_SIGNATURE => RESYNC_TAKE_RATE_FIELDS,
+        PRICE_IMPROVE_THRESH_SIGNATURE => PRICE_IMPROVE_THRESH_FIELDS,
+        TREASURY_SET_SIGNATURE => TREASURY_SET_FIELDS,
+        PROTOCOL_DIVIDEND_SIGNATURE => PROTOCOL_DIVIDEND_FIELDS,
+        UPGRADE_PROXY_SIGNATURE => UPGRADE_PROXY_FIELDS,
+        HOT_PATH_OPEN_SIGNATURE => HOT_PATH_OPEN_FIELDS,
+        SAFE_MODE_SIGNATURE => SAFE_MODE_FIELDS,
+        _ => {
+            return Err(AltheaError::InvalidEventLogError(format!(
+                "{} is not a governance event",
+                signature
+            )))
+        }
+    };
+    Ok(fields)
+}
+
+impl GovernanceAction {
+    /// Builds the action from a log decoded with its `governance_fields` layout
+    pub fn from_decoded(log: &DecodedLog) -> Result<GovernanceAction, AltheaError> {
+        let action = match log.signature {
+            AUTHORITY_TRANSFER_SIGNATURE => GovernanceAction::AuthorityTransfer {
+                authority: log.address("authority")?,
+            },
+            SET_NEW_POOL_LIQ_SIGNATURE => GovernanceAction::SetNewPoolLiq {
+                liq: log.u128("liq")?,
+            },
+            SET_TAKE_RATE_SIGNATURE => GovernanceAction::SetTakeRate {
+                take_rate: log.u8("takeRate")?,
+            },
+            SET_RELAYER_TAKE_RATE_SIGNATURE => GovernanceAction::SetRelayerTakeRate {
+                take_rate: log.u8("takeRate")?,
+            },
+            RESYNC_TAKE_RATE_SIGNATURE => GovernanceAction::ResyncTakeRate {
+                base: log.address("base")?,
+                quote: log.address("quote")?,
+                pool_idx: log.uint256("poolIdx")?,
+                take_rate: log.u8("takeRate")?,
+            },
+            PRICE_IMPROVE_THRESH_SIGNATURE => GovernanceAction::PriceImproveThresh {
+                token: log.address("token")?,
+                unit_tick_collateral: log.u128("unitTickCollateral")?,
+                away_tick_tol: log.u16("awayTickTol")?,
+            },
+            TREASURY_SET_SIGNATURE => GovernanceAction::TreasurySet {
+                treasury: log.address("treasury")?,
+                start_time: log.u64("startTime")?,
+            },
+            PROTOCOL_DIVIDEND_SIGNATURE => GovernanceAction::ProtocolDividend {
+                token: log.address("token")?,
+                recv: log.address("recv")?,
+            },
+            UPGRADE_PROXY_SIGNATURE => GovernanceAction::UpgradeProxy {
+                proxy: log.address("proxy")?,
+                proxy_idx: log.u16("proxyIdx")?,
+            },
+            HOT_PATH_OPEN_SIGNATURE => GovernanceAction::HotPathOpen {
+                open: log.bool("open")?,
+            },
+            SAFE_MODE_SIGNATURE => GovernanceAction::SafeMode {
+                enabled: log.bool("enabled")?,
+            },
+            _ => {
+                return Err(AltheaError::InvalidEventLogError(format!(
+                    "{} is not a governance event",
+                    log.signature
+                )))
+            }
+        };
+        Ok(action)
+    }
+}
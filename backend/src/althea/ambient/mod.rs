@@ -1,36 +1,98 @@
-use std::{str::FromStr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
 
+use apr::lp_fees_after;
 use clarity::{Address, Uint256};
+use decoder::{validate_layout, EventDecoder};
 use events::{
-    BURN_AMBIENT_SIGNATURE, BURN_RANGED_SIGNATURE, INIT_POOL_SIGNATURE, MINT_AMBIENT_SIGNATURE,
-    MINT_RANGED_SIGNATURE,
+    BURN_AMBIENT_SIGNATURE, BURN_RANGED_SIGNATURE, DISABLE_POOL_TEMPLATE_SIGNATURE,
+    INIT_POOL_SIGNATURE, MINT_AMBIENT_SIGNATURE, MINT_RANGED_SIGNATURE,
+    SET_POOL_TEMPLATE_SIGNATURE, SWAP_SIGNATURE,
 };
-use futures::future::{join3, join4, join_all};
+use futures::future::{join, join4, join_all};
+use governance::{governance_fields, GovernanceEvent, GOVERNANCE_SIGNATURES};
 use log::{debug, info};
 use pools::InitPoolEvent;
-use positions::{BurnAmbientEvent, BurnRangedEvent, MintAmbientEvent, MintRangedEvent};
+use positions::{
+    BurnAmbientEvent, BurnRangedEvent, HarvestEvent, MintAmbientEvent, MintRangedEvent,
+};
+use stats::{record_swaps, VOLUME_WINDOW};
+use status::update_dex_status;
+use std::time::{SystemTime, UNIX_EPOCH};
+use swaps::SwapEvent;
+use templates::{DisablePoolTemplateEvent, PoolTemplate, SetPoolTemplateEvent, TemplateChange};
 use web30::client::Web3;
 
 use crate::althea::{
     database::{
+        governance::save_governance_event,
         pools::save_init_pool,
-        positions::{save_burn_ambient, save_burn_ranged, save_mint_ambient, save_mint_ranged},
+        positions::{
+            save_burn_ambient, save_burn_ranged, save_mint_ambient, save_mint_ranged,
+            update_active_ranged_positions,
+        },
+        stats::save_liquidity_provider,
+        swaps::{save_swap, swap_exists},
+        templates::{get_pool_template, save_pool_template},
     },
     CROC_SWAP_CTR,
 };
 
 use super::{
     database::curve::{
-        get_curve, get_liquidity, get_price, save_curve, save_liquidity, save_price,
+        get_curve, get_latest_price_snapshot, get_liquidity, get_pool_params, get_price,
+        save_curve, save_liquidity, save_pool_params, save_price, save_price_snapshot,
+        PriceSnapshot, PRICE_SNAPSHOT_INTERVAL,
     },
     error::AltheaError,
     CROC_QUERY_CTR,
 };
 
+pub mod apr;
 pub mod croc_query;
+pub mod curve_math;
+pub mod decoder;
 pub mod events;
+pub mod governance;
 pub mod pools;
 pub mod positions;
+pub mod pricing;
+pub mod reserves;
+pub mod stats;
+pub mod status;
+pub mod swaps;
+pub mod templates;
+
+/// Checks the declared layout of every event we index against its signature
+pub fn validate_event_layouts() -> Result<(), AltheaError> {
+    let layouts = [
+        (InitPoolEvent::SIGNATURE, InitPoolEvent::FIELDS),
+        (MintRangedEvent::SIGNATURE, MintRangedEvent::FIELDS),
+        (BurnRangedEvent::SIGNATURE, BurnRangedEvent::FIELDS),
+        (HarvestEvent::SIGNATURE, HarvestEvent::FIELDS),
+        (MintAmbientEvent::SIGNATURE, MintAmbientEvent::FIELDS),
+        (BurnAmbientEvent::SIGNATURE, BurnAmbientEvent::FIELDS),
+        (SwapEvent::SIGNATURE, SwapEvent::FIELDS),
+        (
+            SetPoolTemplateEvent::SIGNATURE,
+            SetPoolTemplateEvent::FIELDS,
+        ),
+        (
+            DisablePoolTemplateEvent::SIGNATURE,
+            DisablePoolTemplateEvent::FIELDS,
+        ),
+    ];
+    for (signature, fields) in layouts {
+        validate_layout(signature, fields)?;
+    }
+    for signature in GOVERNANCE_SIGNATURES {
+        validate_layout(signature, governance_fields(signature)?)?;
+    }
+    Ok(())
+}
 
 // Searches for InitPool events and saves them
 pub async fn search_for_pools(
@@ -62,6 +124,84 @@ pub async fn search_for_pools(
     Ok(())
 }
 
+// Searches for SetPoolTemplate and DisablePoolTemplate events, applying them in order to the stored pool templates
+pub async fn search_for_templates(
+    db: &Arc<rocksdb::DB>,
+    web3: &Web3,
+    start_block: Uint256,
+    end_block: Uint256,
+) -> Result<(), AltheaError> {
+    let ctr = Address::from_str(CROC_SWAP_CTR).unwrap();
+    info!("Searching for pool template events");
+    let set_events = web3.check_for_events(
+        start_block,
+        Some(end_block),
+        vec![ctr],
+        vec![SET_POOL_TEMPLATE_SIGNATURE],
+    );
+    let disable_events = web3.check_for_events(
+        start_block,
+        Some(end_block),
+        vec![ctr],
+        vec![DISABLE_POOL_TEMPLATE_SIGNATURE],
+    );
+    let (set_events, disable_events) = join(set_events, disable_events).await;
+    let set_events = SetPoolTemplateEvent::from_logs(&set_events?)?;
+    let disable_events = DisablePoolTemplateEvent::from_logs(&disable_events?)?;
+    debug!("Found {} events", set_events.len() + disable_events.len());
+
+    // A template may be set and disabled within the same range, so the events must be applied in chain order
+    let mut changes = set_events
+        .into_iter()
+        .map(TemplateChange::Set)
+        .chain(disable_events.into_iter().map(TemplateChange::Disable))
+        .collect::<Vec<_>>();
+    changes.sort_by_key(|c| c.order());
+
+    for change in changes {
+        let template = match change {
+            TemplateChange::Set(event) => PoolTemplate::from(event),
+            TemplateChange::Disable(event) => PoolTemplate {
+                disabled: true,
+                block_height: event.block_height,
+                ..get_pool_template(db, event.pool_idx).unwrap_or(PoolTemplate {
+                    pool_idx: event.pool_idx,
+                    ..Default::default()
+                })
+            },
+        };
+        info!("Writing {template:?} to database");
+        save_pool_template(db, template);
+    }
+    Ok(())
+}
+
+// Searches for protocol-level governance events (take rates, treasury, safe mode, etc) and records them in the governance log
+pub async fn search_for_governance_events(
+    db: &Arc<rocksdb::DB>,
+    web3: &Web3,
+    start_block: Uint256,
+    end_block: Uint256,
+) -> Result<(), AltheaError> {
+    let ctr = Address::from_str(CROC_SWAP_CTR).unwrap();
+    info!("Searching for governance events");
+    let searches = GOVERNANCE_SIGNATURES
+        .iter()
+        .map(|sig| web3.check_for_events(start_block, Some(end_block), vec![ctr], vec![*sig]));
+    let results = join_all(searches).await;
+    let mut new_events = vec![];
+    for (signature, logs) in GOVERNANCE_SIGNATURES.iter().zip(results) {
+        let events = GovernanceEvent::from_logs(signature, &logs?)?;
+        for event in events {
+            info!("Writing {event:?} to database");
+            save_governance_event(db, event.clone());
+            new_events.push(event);
+        }
+    }
+    update_dex_status(db, &new_events);
+    Ok(())
+}
+
 // Searches for any position events (minting or burning ranged or ambient positions), saving them if the events contain the given tokens and templates
 pub async fn search_for_positions(
     db: &Arc<rocksdb::DB>,
@@ -151,35 +291,133 @@ pub async fn search_for_positions(
         return Ok(());
     }
 
+    // The active ranged positions of every user and pool with new ranged events are recomputed once saved
+    let mut ranged_changes = HashSet::new();
     for event in mint_ranged_events {
         debug!("Writing {event:?} to database");
+        ranged_changes.insert((event.user, event.base, event.quote, event.pool_idx));
+        save_liquidity_provider(db, event.user);
         save_mint_ranged(db, event);
     }
     for event in mint_ambient_events {
         debug!("Writing {event:?} to database");
+        save_liquidity_provider(db, event.user);
         save_mint_ambient(db, event);
     }
     for event in burn_ranged_events {
         debug!("Writing {event:?} to database");
+        ranged_changes.insert((event.user, event.base, event.quote, event.pool_idx));
         save_burn_ranged(db, event);
     }
     for event in burn_ambient_events {
         debug!("Writing {event:?} to database");
         save_burn_ambient(db, event);
     }
+    for (user, base, quote, pool_idx) in ranged_changes {
+        update_active_ranged_positions(db, user, base, quote, pool_idx);
+    }
     Ok(())
 }
 
+// Searches for Swap events, saving them if the events contain the given tokens and templates. Newly found
+// swaps are recorded into the dex statistics, with their volume attributed to the hour of their block
+pub async fn search_for_swaps(
+    db: &Arc<rocksdb::DB>,
+    web3: &Web3,
+    tokens: &[Address],
+    templates: &[Uint256],
+    start_block: Uint256,
+    end_block: Uint256,
+) -> Result<(), AltheaError> {
+    let ctr = Address::from_str(CROC_SWAP_CTR).unwrap();
+    info!("Searching for swap events");
+    let events = web3
+        .check_for_events(
+            start_block,
+            Some(end_block),
+            vec![ctr],
+            vec![SWAP_SIGNATURE],
+        )
+        .await?;
+    debug!("Found {} events", events.len());
+    let swap_events = SwapEvent::from_logs(&events)?
+        .into_iter()
+        .filter(|v| {
+            templates.contains(&v.pool_idx) && (tokens.contains(&v.buy) || tokens.contains(&v.sell))
+        })
+        .collect::<Vec<_>>();
+    if swap_events.is_empty() {
+        debug!("No events found");
+        return Ok(());
+    }
+
+    let new_swaps = swap_events
+        .iter()
+        .filter(|v| !swap_exists(db, v))
+        .cloned()
+        .collect::<Vec<_>>();
+    let block_times = get_recent_block_times(web3, &new_swaps, end_block).await?;
+    for event in swap_events {
+        debug!("Writing {event:?} to database");
+        save_swap(db, event);
+    }
+    record_swaps(db, &new_swaps, &block_times);
+    Ok(())
+}
+
+// Gets the timestamps of the blocks containing `swaps`, skipping the queries entirely when even `end_block`
+// is older than the volume window, as it is for most of the chain's history while catching up
+async fn get_recent_block_times(
+    web3: &Web3,
+    swaps: &[SwapEvent],
+    end_block: Uint256,
+) -> Result<HashMap<Uint256, u64>, AltheaError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    if swaps.is_empty()
+        || get_block_time(web3, end_block).await? < now.saturating_sub(VOLUME_WINDOW)
+    {
+        return Ok(HashMap::new());
+    }
+    let blocks: HashSet<Uint256> = swaps.iter().map(|s| s.block_height).collect();
+    let times = join_all(blocks.iter().map(|block| get_block_time(web3, *block))).await;
+    let mut block_times = HashMap::new();
+    for (block, time) in blocks.into_iter().zip(times) {
+        block_times.insert(block, time?);
+    }
+    Ok(block_times)
+}
+
+async fn get_block_time(web3: &Web3, block: Uint256) -> Result<u64, AltheaError> {
+    let timestamp = web3.eth_get_concise_block_by_number(block).await?.timestamp;
+    timestamp
+        .to_string()
+        .parse()
+        .map_err(|_| AltheaError::InvalidEventLogError(format!("Invalid block time {}", timestamp)))
+}
+
+// Queries the latest state of each pool, when `snapshot_block` is provided the indexer has caught up
+// to the chain and the queried prices are also recorded into each pool's price history at that block
 pub async fn query_latest(
     db: &Arc<rocksdb::DB>,
     web30: &Web3,
     pools: &[(Address, Address, Uint256)],
+    snapshot_block: Option<Uint256>,
 ) -> Result<(), AltheaError> {
     info!("Querying latest pool data");
 
     let mut futures = vec![];
     for pool in pools {
-        futures.push(query_pool(db, web30, pool.0, pool.1, pool.2));
+        futures.push(query_pool(
+            db,
+            web30,
+            pool.0,
+            pool.1,
+            pool.2,
+            snapshot_block,
+        ));
     }
 
     let results = join_all(futures).await;
@@ -196,13 +434,15 @@ pub async fn query_pool(
     base: Address,
     quote: Address,
     pool_idx: Uint256,
+    snapshot_block: Option<Uint256>,
 ) -> Result<(), AltheaError> {
     let croc_query = Address::from_str(CROC_QUERY_CTR).unwrap();
     let curve = croc_query::get_curve(web30, croc_query, base, quote, pool_idx);
     let price = croc_query::get_price(web30, croc_query, base, quote, pool_idx);
     let liq = croc_query::get_liquidity(web30, croc_query, base, quote, pool_idx);
+    let params = croc_query::get_pool_params(web30, croc_query, base, quote, pool_idx);
 
-    let (curve, price, liq) = join3(curve, price, liq).await;
+    let (curve, price, liq, params) = join4(curve, price, liq, params).await;
 
     // Only save items if the value is nonzero (empty) or if the key is already in the database
     if let Ok(curve) = curve {
@@ -216,6 +456,9 @@ pub async fn query_pool(
             info!("Writing price to database for pool {base} {quote} {pool_idx}");
             save_price(db, price, base, quote, pool_idx);
         }
+        if let (Some(block_height), true) = (snapshot_block, price != 0) {
+            record_price_snapshot(db, base, quote, pool_idx, block_height, price);
+        }
     }
     if let Ok(liq) = liq {
         if liq != 0 || get_liquidity(db, base, quote, pool_idx).is_some() {
@@ -223,6 +466,46 @@ pub async fn query_pool(
             save_liquidity(db, liq, base, quote, pool_idx);
         }
     }
+    if let Ok(params) = params {
+        if params.fee_rate != 0 || get_pool_params(db, base, quote, pool_idx).is_some() {
+            info!("Writing pool params to database for pool {base} {quote} {pool_idx}");
+            save_pool_params(db, params, base, quote, pool_idx);
+        }
+    }
 
     Ok(())
 }
+
+// Records a price snapshot unless one was recorded within the last PRICE_SNAPSHOT_INTERVAL, along with the
+// liquidity provider fees paid since the previous snapshot so that APR estimates need not read every swap
+fn record_price_snapshot(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    block_height: Uint256,
+    price: u128,
+) {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let latest = get_latest_price_snapshot(db, base, quote, pool_idx);
+    if let Some(latest) = &latest {
+        if time < latest.time + PRICE_SNAPSHOT_INTERVAL {
+            return;
+        }
+    }
+    // The first snapshot starts the pool's history, so earlier swaps are not attributed to it
+    let lp_fees = match latest {
+        Some(latest) => lp_fees_after(db, base, quote, pool_idx, latest.block_height, price),
+        None => 0.0,
+    };
+    let snapshot = PriceSnapshot {
+        block_height,
+        time,
+        price,
+        lp_fees,
+    };
+    save_price_snapshot(db, snapshot, base, quote, pool_idx);
+}
@@ -0,0 +1,329 @@
+// This file values tokens in USD by routing them through indexed pools to a stablecoin
+// Each token is priced along the route whose shallowest pool holds the most value, found by a widest path
+// search outward from the stablecoins, which are assumed to be worth exactly one dollar.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clarity::{Address, Uint256};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use super::curve_math::{active_liquidity, ambient_reserves, sqrt_price_from_q64};
+use crate::althea::database::{
+    curve::{get_curve, get_price},
+    pools::get_init_pools,
+    prices::save_token_prices,
+};
+use crate::althea::token_mappings::get_token_registry;
+
+/// A pool a price was routed through
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RouteHop {
+    pub base: Address,
+    pub quote: Address,
+    pub pool_idx: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenPrice {
+    pub token: Address,
+    pub symbol: Option<String>,
+    /// The USD value of one whole token, scaled by the token's decimals
+    pub usd_price: f64,
+    /// The pools the price was routed through, starting from the token and ending at a stablecoin.
+    /// Empty for the stablecoins themselves
+    pub route: Vec<RouteHop>,
+    /// The USD value of the token side of the shallowest pool on the route, None for stablecoins
+    pub route_liquidity_usd: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenPrices {
+    pub prices: Vec<TokenPrice>,
+    pub last_updated: u64,
+}
+
+impl TokenPrices {
+    pub fn usd_price(&self, token: Address) -> Option<f64> {
+        self.prices
+            .iter()
+            .find(|p| p.token == token)
+            .map(|p| p.usd_price)
+    }
+}
+
+// A pool along with the values needed to trade across it
+struct PoolEdge {
+    hop: RouteHop,
+    /// base raw units per quote raw unit
+    price: f64,
+    /// The virtual reserves of the active liquidity in raw units
+    base_reserve: f64,
+    quote_reserve: f64,
+}
+
+// A token's best known route while the search is running
+struct Candidate {
+    usd_price: f64,
+    depth: f64,
+    route: Vec<RouteHop>,
+}
+
+/// Prices every token reachable from `stablecoins` through indexed pools and saves the result. Tokens
+/// without decimals in the token registry cannot be priced and are skipped
+pub fn update_token_prices(db: &rocksdb::DB, stablecoins: &[Address]) {
+    let tokens = get_token_registry(db).map(|r| r.tokens).unwrap_or_default();
+    let decimals: HashMap<Address, i32> = tokens
+        .iter()
+        .map(|t| (t.address, t.decimals as i32))
+        .collect();
+    let mut edges = Vec::new();
+    for pool in get_init_pools(db) {
+        let (Some(price_root), Some(curve)) = (
+            get_price(db, pool.base, pool.quote, pool.pool_idx),
+            get_curve(db, pool.base, pool.quote, pool.pool_idx),
+        ) else {
+            continue;
+        };
+        if price_root == 0 {
+            continue;
+        }
+        let sqrt_price = sqrt_price_from_q64(price_root);
+        let (base_reserve, quote_reserve) = ambient_reserves(active_liquidity(&curve), sqrt_price);
+        edges.push(PoolEdge {
+            hop: RouteHop {
+                base: pool.base,
+                quote: pool.quote,
+                pool_idx: pool.pool_idx,
+            },
+            price: sqrt_price * sqrt_price,
+            base_reserve,
+            quote_reserve,
+        });
+    }
+
+    let mut prices: Vec<TokenPrice> = find_routes(&edges, &decimals, stablecoins)
+        .into_iter()
+        .map(|(token, c)| TokenPrice {
+            token,
+            symbol: tokens
+                .iter()
+                .find(|t| t.address == token)
+                .map(|t| t.symbol.clone()),
+            usd_price: c.usd_price,
+            route_liquidity_usd: if c.route.is_empty() {
+                None
+            } else {
+                Some(c.depth)
+            },
+            route: c.route,
+        })
+        .collect();
+    prices.sort_by(|a, b| a.token.cmp(&b.token));
+    debug!("Token prices {:?}", prices);
+    info!("Priced {} tokens", prices.len());
+
+    save_token_prices(
+        db,
+        &TokenPrices {
+            prices,
+            last_updated: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        },
+    );
+}
+
+// Finds the route from each token reachable from `stablecoins` whose shallowest pool holds the most value,
+// tokens missing from `decimals` are not routed through
+fn find_routes(
+    edges: &[PoolEdge],
+    decimals: &HashMap<Address, i32>,
+    stablecoins: &[Address],
+) -> HashMap<Address, Candidate> {
+    let mut candidates: HashMap<Address, Candidate> = HashMap::new();
+    for stable in stablecoins {
+        if decimals.contains_key(stable) {
+            candidates.insert(
+                *stable,
+                Candidate {
+                    usd_price: 1.0,
+                    depth: f64::INFINITY,
+                    route: vec![],
+                },
+            );
+        }
+    }
+
+    // Widest path search, the unsettled token with the deepest route is final once it is chosen
+    let mut settled: HashMap<Address, Candidate> = HashMap::new();
+    loop {
+        let next = candidates
+            .iter()
+            .max_by(|a, b| a.1.depth.total_cmp(&b.1.depth))
+            .map(|(token, _)| *token);
+        let Some(token) = next else {
+            break;
+        };
+        let current = candidates.remove(&token).unwrap();
+
+        for edge in edges.iter() {
+            let (other, reserve) = if edge.hop.base == token {
+                (edge.hop.quote, edge.base_reserve)
+            } else if edge.hop.quote == token {
+                (edge.hop.base, edge.quote_reserve)
+            } else {
+                continue;
+            };
+            if settled.contains_key(&other) || other == token {
+                continue;
+            }
+            let (Some(token_decimals), Some(other_decimals)) =
+                (decimals.get(&token), decimals.get(&other))
+            else {
+                continue;
+            };
+            let edge_depth = reserve / 10f64.powi(*token_decimals) * current.usd_price;
+            let depth = current.depth.min(edge_depth);
+            if candidates.get(&other).is_some_and(|c| c.depth >= depth) {
+                continue;
+            }
+            let (base_decimals, quote_decimals) = if edge.hop.base == token {
+                (*token_decimals, *other_decimals)
+            } else {
+                (*other_decimals, *token_decimals)
+            };
+            let display_price = display_price(edge.price, base_decimals, quote_decimals);
+            let usd_price = if edge.hop.base == token {
+                current.usd_price * display_price
+            } else {
+                current.usd_price / display_price
+            };
+            if !usd_price.is_finite() {
+                continue;
+            }
+            let mut route = vec![edge.hop.clone()];
+            route.extend(current.route.iter().cloned());
+            candidates.insert(
+                other,
+                Candidate {
+                    usd_price,
+                    depth,
+                    route,
+                },
+            );
+        }
+        settled.insert(token, current);
+    }
+    settled
+}
+
+// The price of one whole quote token in whole base tokens, from a price in base raw units per quote raw unit
+fn display_price(price: f64, base_decimals: i32, quote_decimals: i32) -> f64 {
+    price * 10f64.powi(quote_decimals - base_decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn usdc() -> Address {
+        Address::from_str("0x0000000000000000000000000000000000000001").unwrap()
+    }
+    fn weth() -> Address {
+        Address::from_str("0x0000000000000000000000000000000000000002").unwrap()
+    }
+    fn token() -> Address {
+        Address::from_str("0x0000000000000000000000000000000000000003").unwrap()
+    }
+
+    // USDC has 6 decimals, WETH and the other token 18
+    fn decimals() -> HashMap<Address, i32> {
+        HashMap::from([(usdc(), 6), (weth(), 18), (token(), 18)])
+    }
+
+    fn edge(
+        base: Address,
+        quote: Address,
+        pool_idx: u64,
+        price: f64,
+        base_reserve: f64,
+        quote_reserve: f64,
+    ) -> PoolEdge {
+        PoolEdge {
+            hop: RouteHop {
+                base,
+                quote,
+                pool_idx: pool_idx.into(),
+            },
+            price,
+            base_reserve,
+            quote_reserve,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_display_price() {
+        // 2000 USDC (6 decimals) per WETH (18 decimals) is 2000e6 raw base per 1e18 raw quote
+        assert_close(display_price(2e-9, 6, 18), 2000.0);
+        // 0.5 WETH per USDC is 0.5e18 raw base per 1e6 raw quote
+        assert_close(display_price(0.5e12, 18, 6), 0.5);
+        assert_close(display_price(3.0, 18, 18), 3.0);
+    }
+
+    #[test]
+    fn test_multi_hop_route() {
+        let edges = vec![
+            // 1 WETH = 2000 USDC, holding 1,000,000 USDC
+            edge(usdc(), weth(), 1, 2e-9, 1e12, 500e18),
+            // 1 token = 0.0001 WETH, holding 100 WETH
+            edge(weth(), token(), 1, 1e-4, 100e18, 1e24),
+        ];
+        let routes = find_routes(&edges, &decimals(), &[usdc()]);
+
+        let stable = &routes[&usdc()];
+        assert_eq!(stable.usd_price, 1.0);
+        assert!(stable.route.is_empty());
+        assert_close(routes[&weth()].usd_price, 2000.0);
+        assert_close(routes[&weth()].depth, 1e6);
+
+        let priced = &routes[&token()];
+        assert_close(priced.usd_price, 0.2);
+        // The shallowest pool on the route is the WETH pool, whose 100 WETH are worth $200,000
+        assert_close(priced.depth, 200_000.0);
+        assert_eq!(
+            priced.route,
+            vec![edges[1].hop.clone(), edges[0].hop.clone()]
+        );
+    }
+
+    #[test]
+    fn test_deepest_route() {
+        let edges = vec![
+            // 1 token = 1 USDC, holding 1,000 USDC
+            edge(usdc(), token(), 1, 1e-12, 1e9, 1000e18),
+            // 1 token = 2 USDC with the token as the base, holding 1,000,000 USDC
+            edge(token(), usdc(), 2, 0.5e12, 500_000e18, 1e12),
+        ];
+        let routes = find_routes(&edges, &decimals(), &[usdc()]);
+
+        let priced = &routes[&token()];
+        assert_close(priced.usd_price, 2.0);
+        assert_close(priced.depth, 1e6);
+        assert_eq!(priced.route, vec![edges[1].hop.clone()]);
+        // WETH has no pool and is not priced
+        assert!(!routes.contains_key(&weth()));
+    }
+}
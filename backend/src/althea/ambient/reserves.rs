@@ -0,0 +1,211 @@
+// This file derives the token reserves and total value locked of each pool from its indexed curve state.
+// A pool's reserves are the ambient liquidity's full range reserves plus the reserves of every active
+// concentrated position, each valued at the curve's current price.
+
+use clarity::{Address, Uint256};
+use serde::{Deserialize, Serialize};
+
+use super::croc_query::CurveState;
+use super::curve_math::{
+    active_liquidity_exact, ambient_liquidity_exact, ambient_reserves_exact, ranged_reserves_exact,
+    sqrt_price_from_q64, tick_from_sqrt_price, value_in_base_exact,
+};
+use super::pricing::TokenPrices;
+use crate::althea::amount::Amount;
+use crate::althea::database::{
+    curve::{get_curve, get_price},
+    positions::{get_pool_ranged_positions, RangedPosition},
+};
+use crate::althea::token_mappings::get_token_info;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PoolReserves {
+    /// The current price in base tokens per quote token, in raw units
+    pub price: f64,
+    pub tick: i32,
+    /// The ambient seeds inflated by the seed deflator
+    pub ambient_liquidity: Amount,
+    /// The concentrated liquidity whose range contains the current price
+    pub concentrated_liquidity: Amount,
+    /// The reserves implied by the active liquidity at the current price, which determine swap depth
+    pub virtual_base_reserve: Amount,
+    pub virtual_quote_reserve: Amount,
+    /// The tokens actually held by the pool's ambient liquidity and indexed concentrated positions,
+    /// in raw units
+    pub base_reserve: Amount,
+    pub quote_reserve: Amount,
+    /// The value of both reserves in raw base token units
+    pub tvl_base: Amount,
+    /// The value of both reserves in USD, None if neither token has a price
+    pub tvl_usd: Option<f64>,
+}
+
+/// Computes the reserves of a pool from its stored curve, price and active ranged positions. Returns None
+/// if the pool's curve has not been queried
+pub fn get_pool_reserves(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    prices: Option<&TokenPrices>,
+) -> Option<PoolReserves> {
+    let price_root = get_price(db, base, quote, pool_idx)?;
+    let curve = get_curve(db, base, quote, pool_idx)?;
+    let ranged = get_pool_ranged_positions(db, base, quote, pool_idx);
+    let mut reserves = compute_reserves(&curve, price_root, &ranged)?;
+    reserves.tvl_usd = prices.and_then(|p| {
+        let sqrt_price = sqrt_price_from_q64(price_root);
+        tvl_usd(db, p, base, quote, reserves.tvl_base.to_f64(), sqrt_price)
+    });
+    Some(reserves)
+}
+
+/// Computes the reserves of a pool with `curve` at the Q64.64 square root price `price_root`, where
+/// `ranged` holds the pool's active ranged positions. `tvl_usd` is left unset, returns None if the
+/// price is zero
+fn compute_reserves(
+    curve: &CurveState,
+    price_root: u128,
+    ranged: &[RangedPosition],
+) -> Option<PoolReserves> {
+    if price_root == 0 {
+        return None;
+    }
+    let sqrt_price = sqrt_price_from_q64(price_root);
+    let root = Uint256::from(price_root);
+
+    let ambient = ambient_liquidity_exact(curve);
+    let (virtual_base, virtual_quote) = ambient_reserves_exact(active_liquidity_exact(curve), root);
+    let (mut base_reserve, mut quote_reserve) = ambient_reserves_exact(ambient, root);
+    for position in ranged {
+        let (b, q) = ranged_reserves_exact(
+            position.liq.into(),
+            root,
+            position.bid_tick,
+            position.ask_tick,
+        );
+        base_reserve += b;
+        quote_reserve += q;
+    }
+    let tvl_base = value_in_base_exact((base_reserve, quote_reserve), root);
+
+    Some(PoolReserves {
+        price: sqrt_price * sqrt_price,
+        tick: tick_from_sqrt_price(sqrt_price),
+        ambient_liquidity: Amount::new(ambient, 0),
+        concentrated_liquidity: Amount::from(curve.conc_liq),
+        virtual_base_reserve: Amount::new(virtual_base, 0),
+        virtual_quote_reserve: Amount::new(virtual_quote, 0),
+        base_reserve: Amount::new(base_reserve, 0),
+        quote_reserve: Amount::new(quote_reserve, 0),
+        tvl_base: Amount::new(tvl_base, 0),
+        tvl_usd: None,
+    })
+}
+
+// Values the pool in USD through whichever of its tokens has a price, preferring the base token
+fn tvl_usd(
+    db: &rocksdb::DB,
+    prices: &TokenPrices,
+    base: Address,
+    quote: Address,
+    tvl_base: f64,
+    sqrt_price: f64,
+) -> Option<f64> {
+    let whole = |token: Address, raw: f64| {
+        get_token_info(db, token).map(|t| raw / 10f64.powi(t.decimals as i32))
+    };
+    if let Some(price) = prices.usd_price(base) {
+        return Some(whole(base, tvl_base)? * price);
+    }
+    let price = prices.usd_price(quote)?;
+    Some(whole(quote, tvl_base / (sqrt_price * sqrt_price))? * price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const Q64: u128 = 1 << 64;
+
+    fn position(liq: u128, bid_tick: i32, ask_tick: i32) -> RangedPosition {
+        RangedPosition {
+            start_block: 0u8.into(),
+            user: Address::default(),
+            base: Address::default(),
+            quote: Address::default(),
+            pool_idx: 36000u64.into(),
+            bid_tick,
+            ask_tick,
+            liq,
+            base_amount: 0,
+            quote_amount: 0,
+        }
+    }
+
+    fn amount(s: &str) -> Amount {
+        Amount::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_compute_reserves() {
+        // A price of 4, with 10^18 ambient seeds inflated by half and 5 * 10^17 in range concentrated
+        // liquidity
+        let price_root = 2 * Q64;
+        let curve = CurveState {
+            price_root,
+            ambient_seeds: 1_000_000_000_000_000_000,
+            conc_liq: 500_000_000_000_000_000,
+            seed_deflator: 1 << 47,
+            conc_growth: 0,
+        };
+        let ranged = [
+            // In range, L * (2 - 1) base and L * (1/2 - 1/sqrt(1.0001^27726)) quote
+            position(500_000_000_000_000_000, 0, 27726),
+            // Above the price, entirely quote tokens
+            position(200_000_000_000_000_000, 20000, 30000),
+        ];
+        let reserves = compute_reserves(&curve, price_root, &ranged).unwrap();
+
+        assert_eq!(reserves.price, 4.0);
+        assert_eq!(reserves.tick, 13863);
+        assert_eq!(reserves.ambient_liquidity, amount("1500000000000000000"));
+        assert_eq!(
+            reserves.concentrated_liquidity,
+            amount("500000000000000000")
+        );
+        // The active liquidity of 2 * 10^18 backs L * 2 base and L / 2 quote
+        assert_eq!(reserves.virtual_base_reserve, amount("4000000000000000000"));
+        assert_eq!(
+            reserves.virtual_quote_reserve,
+            amount("1000000000000000000")
+        );
+        // 3 * 10^18 ambient base plus 5 * 10^17 from the in range position
+        assert_eq!(reserves.base_reserve, amount("3500000000000000000"));
+        // 7.5 * 10^17 ambient quote plus 124992040809211239 and 28950187990952857 from the positions
+        assert_eq!(reserves.quote_reserve, amount("903942228800164096"));
+        // The base reserve plus four times the quote reserve
+        assert_eq!(reserves.tvl_base, amount("7115768915200656384"));
+        assert_eq!(reserves.tvl_usd, None);
+    }
+
+    #[test]
+    fn test_compute_reserves_without_positions() {
+        let curve = CurveState {
+            price_root: Q64,
+            ambient_seeds: 1_000_000,
+            conc_liq: 0,
+            seed_deflator: 0,
+            conc_growth: 0,
+        };
+        // At a price of 1 the ambient liquidity is held equally in both tokens
+        let reserves = compute_reserves(&curve, Q64, &[]).unwrap();
+        assert_eq!(reserves.tick, 0);
+        assert_eq!(reserves.base_reserve, amount("1000000"));
+        assert_eq!(reserves.quote_reserve, amount("1000000"));
+        assert_eq!(reserves.virtual_base_reserve, reserves.base_reserve);
+        assert_eq!(reserves.tvl_base, amount("2000000"));
+
+        assert!(compute_reserves(&curve, 0, &[]).is_none());
+    }
+}
@@ -0,0 +1,216 @@
+// This file maintains protocol-wide DEX statistics for the dashboard. Swap volume is accumulated into
+// hourly buckets per pool by the time of each swap's block as swaps are indexed, and participants are
+// recorded into sets as they are seen, so that the summary rebuilt after each indexer pass only reads a
+// week of buckets per pool.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clarity::{Address, Uint256};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use super::pricing::TokenPrices;
+use super::reserves::get_pool_reserves;
+use super::swaps::SwapEvent;
+use crate::althea::database::{
+    curve::get_pool_params,
+    pools::get_init_pools,
+    positions::{get_all_mint_ambient, get_all_mint_ranged},
+    prices::get_token_prices,
+    stats::{
+        get_liquidity_provider_count, get_trader_count, get_volume_bucket, get_volume_buckets,
+        remove_volume_buckets, save_dex_stats, save_liquidity_provider, save_trader,
+        save_volume_bucket,
+    },
+    swaps::get_all_swaps,
+};
+use crate::althea::token_mappings::get_token_info;
+
+const HOUR: u64 = 3600;
+const DAY_HOURS: u64 = 24;
+const WEEK_HOURS: u64 = 7 * DAY_HOURS;
+/// The seconds of swap history reflected in the statistics
+pub const VOLUME_WINDOW: u64 = WEEK_HOURS * HOUR;
+/// The number of pools listed under each top pools metric
+pub const TOP_POOLS: usize = 10;
+/// Set once the participants of databases indexed before participants were recorded have been counted
+const PARTICIPANTS_BACKFILLED_KEY: &[u8] = b"dex-participants-backfilled";
+
+/// The swaps recorded in a single pool during one hour
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct VolumeBucket {
+    pub volume_usd: f64,
+    pub fees_usd: f64,
+    pub swap_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PoolStats {
+    pub base: Address,
+    pub quote: Address,
+    pub pool_idx: Uint256,
+    pub tvl_usd: Option<f64>,
+    pub volume_24h_usd: f64,
+    pub volume_7d_usd: f64,
+    pub fees_24h_usd: f64,
+    pub fees_7d_usd: f64,
+    pub swaps_24h: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DexStats {
+    pub pool_count: u64,
+    /// The sum of every pool whose TVL could be priced
+    pub tvl_usd: f64,
+    pub volume_24h_usd: f64,
+    pub volume_7d_usd: f64,
+    pub fees_24h_usd: f64,
+    pub fees_7d_usd: f64,
+    pub swaps_24h: u64,
+    /// Every address that has ever minted a position
+    pub unique_lps: u64,
+    /// Every address that has ever swapped
+    pub unique_traders: u64,
+    pub top_pools_by_tvl: Vec<PoolStats>,
+    pub top_pools_by_volume: Vec<PoolStats>,
+    pub top_pools_by_fees: Vec<PoolStats>,
+    pub last_updated: u64,
+}
+
+/// Records newly indexed swaps. Every swapper is counted as a trader, and swaps add to the volume of the
+/// hour of their block given by `block_times`. Swaps without a block time or from outside the volume
+/// window only count as traders. Volume is valued at the current token prices
+pub fn record_swaps(db: &rocksdb::DB, swaps: &[SwapEvent], block_times: &HashMap<Uint256, u64>) {
+    for swap in swaps {
+        save_trader(db, swap.user);
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let prices = get_token_prices(db);
+    for swap in swaps {
+        let Some(time) = block_times.get(&swap.block_height) else {
+            continue;
+        };
+        if *time < now.saturating_sub(VOLUME_WINDOW) {
+            continue;
+        }
+        let hour = time / HOUR;
+        let (base, quote) = swap.base_quote();
+        let mut bucket =
+            get_volume_bucket(db, base, quote, swap.pool_idx, hour).unwrap_or_default();
+        let volume = prices
+            .as_ref()
+            .and_then(|p| swap_value_usd(db, p, swap))
+            .unwrap_or_default();
+        // Ambient charges the pool's fee rate, in units of 0.0001%, on each swap
+        let fee_rate = get_pool_params(db, base, quote, swap.pool_idx)
+            .map(|p| p.fee_rate as f64 / 1_000_000.0)
+            .unwrap_or_default();
+        bucket.volume_usd += volume;
+        bucket.fees_usd += volume * fee_rate;
+        bucket.swap_count += 1;
+        save_volume_bucket(db, base, quote, swap.pool_idx, hour, &bucket);
+    }
+}
+
+/// Records the liquidity providers and traders of every stored mint and swap, for databases indexed before
+/// participants were recorded as they were seen. This only scans the events once
+pub fn backfill_participants(db: &rocksdb::DB) {
+    if db.get(PARTICIPANTS_BACKFILLED_KEY).unwrap().is_some() {
+        return;
+    }
+    info!("Backfilling dex liquidity providers and traders");
+    for mint in get_all_mint_ranged(db, None) {
+        save_liquidity_provider(db, mint.user);
+    }
+    for mint in get_all_mint_ambient(db, None) {
+        save_liquidity_provider(db, mint.user);
+    }
+    for swap in get_all_swaps(db, None) {
+        save_trader(db, swap.user);
+    }
+    db.put(PARTICIPANTS_BACKFILLED_KEY, [1u8]).unwrap();
+}
+
+// Values a swap by whichever side has a price, preferring the token sold into the pool
+fn swap_value_usd(db: &rocksdb::DB, prices: &TokenPrices, swap: &SwapEvent) -> Option<f64> {
+    let value = |token: Address, qty: u128| {
+        let price = prices.usd_price(token)?;
+        let decimals = get_token_info(db, token)?.decimals;
+        Some(qty as f64 / 10f64.powi(decimals as i32) * price)
+    };
+    value(swap.sell, swap.sell_qty).or_else(|| value(swap.buy, swap.buy_qty))
+}
+
+/// Rebuilds the dashboard statistics from the stored volume buckets, participant counts and current
+/// pool reserves, and removes buckets that have aged out of the weekly window
+pub fn update_dex_stats(db: &rocksdb::DB) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let current_hour = now / HOUR;
+    let day_start = current_hour.saturating_sub(DAY_HOURS - 1);
+    let week_start = current_hour.saturating_sub(WEEK_HOURS - 1);
+
+    let pools = get_init_pools(db);
+    let prices = get_token_prices(db);
+    let mut pool_stats = Vec::with_capacity(pools.len());
+    for pool in pools.iter() {
+        remove_volume_buckets(db, pool.base, pool.quote, pool.pool_idx, week_start);
+        let mut stats = PoolStats {
+            base: pool.base,
+            quote: pool.quote,
+            pool_idx: pool.pool_idx,
+            tvl_usd: get_pool_reserves(db, pool.base, pool.quote, pool.pool_idx, prices.as_ref())
+                .and_then(|r| r.tvl_usd),
+            volume_24h_usd: 0.0,
+            volume_7d_usd: 0.0,
+            fees_24h_usd: 0.0,
+            fees_7d_usd: 0.0,
+            swaps_24h: 0,
+        };
+        for (hour, bucket) in
+            get_volume_buckets(db, pool.base, pool.quote, pool.pool_idx, week_start)
+        {
+            stats.volume_7d_usd += bucket.volume_usd;
+            stats.fees_7d_usd += bucket.fees_usd;
+            if hour >= day_start {
+                stats.volume_24h_usd += bucket.volume_usd;
+                stats.fees_24h_usd += bucket.fees_usd;
+                stats.swaps_24h += bucket.swap_count;
+            }
+        }
+        pool_stats.push(stats);
+    }
+
+    let top_by = |key: fn(&PoolStats) -> f64| {
+        let mut sorted = pool_stats.clone();
+        sorted.sort_by(|a, b| key(b).total_cmp(&key(a)));
+        sorted.truncate(TOP_POOLS);
+        sorted
+    };
+    let stats = DexStats {
+        pool_count: pools.len() as u64,
+        tvl_usd: pool_stats.iter().filter_map(|p| p.tvl_usd).sum(),
+        volume_24h_usd: pool_stats.iter().map(|p| p.volume_24h_usd).sum(),
+        volume_7d_usd: pool_stats.iter().map(|p| p.volume_7d_usd).sum(),
+        fees_24h_usd: pool_stats.iter().map(|p| p.fees_24h_usd).sum(),
+        fees_7d_usd: pool_stats.iter().map(|p| p.fees_7d_usd).sum(),
+        swaps_24h: pool_stats.iter().map(|p| p.swaps_24h).sum(),
+        unique_lps: get_liquidity_provider_count(db),
+        unique_traders: get_trader_count(db),
+        top_pools_by_tvl: top_by(|p| p.tvl_usd.unwrap_or_default()),
+        top_pools_by_volume: top_by(|p| p.volume_24h_usd),
+        top_pools_by_fees: top_by(|p| p.fees_24h_usd),
+        last_updated: now,
+    };
+    info!(
+        "Dex stats: {} pools, ${:.2} TVL, ${:.2} 24h volume",
+        stats.pool_count, stats.tvl_usd, stats.volume_24h_usd
+    );
+    save_dex_stats(db, &stats);
+}
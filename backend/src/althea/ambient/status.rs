@@ -0,0 +1,374 @@
+// This file concerns the protocol-wide state of CrocSwapDex, reconstructed from the governance log
+
+use std::str::FromStr;
+
+use clarity::{abi::encode_call, Address, Uint256};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use web30::{client::Web3, types::TransactionRequest};
+
+use super::governance::{GovernanceAction, GovernanceEvent};
+use crate::althea::{
+    database::{
+        governance::get_governance_log,
+        status::{get_dex_status, save_dex_status},
+    },
+    error::AltheaError,
+    CROC_SWAP_CTR, DEFAULT_QUERIER,
+};
+
+/// The current protocol-wide configuration of CrocSwapDex
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct DexStatus {
+    /// True if emergency safe mode is on, restricting all operations except governance
+    pub safe_mode: bool,
+    /// True if users may call swap() on CrocSwapDex directly, otherwise swaps must go through the proxy
+    pub hot_path_open: bool,
+    /// The protocol's share of swap fees in units of 1/256
+    pub protocol_take_rate: u8,
+    /// The protocol's share of relayer tips in units of 1/256
+    pub relayer_take_rate: u8,
+    /// The vault that collects protocol fees, if one has been set
+    pub treasury: Option<Address>,
+    /// The earliest unix time at which the treasury may collect protocol fees
+    pub treasury_start_time: Option<u64>,
+    /// The current governance authority, if a transfer has been indexed
+    pub authority: Option<Address>,
+    /// The (block, log index) of the last governance event applied to this status
+    pub block_height: Uint256,
+    pub log_index: Uint256,
+}
+
+impl Default for DexStatus {
+    // CrocSwapDex opens the hot path on deployment and starts outside of safe mode
+    fn default() -> Self {
+        DexStatus {
+            safe_mode: false,
+            hot_path_open: true,
+            protocol_take_rate: 0,
+            relayer_take_rate: 0,
+            treasury: None,
+            treasury_start_time: None,
+            authority: None,
+            block_height: 0u8.into(),
+            log_index: 0u8.into(),
+        }
+    }
+}
+
+impl DexStatus {
+    /// Applies a governance event to the status, events at or before the last applied event are ignored
+    /// so that re-indexing a block range cannot roll the status back
+    pub fn apply(&mut self, event: &GovernanceEvent) {
+        let last_applied = (self.block_height, self.log_index);
+        if last_applied != Default::default()
+            && (event.block_height, event.log_index) <= last_applied
+        {
+            return;
+        }
+        match event.action {
+            GovernanceAction::SafeMode { enabled } => self.safe_mode = enabled,
+            GovernanceAction::HotPathOpen { open } => self.hot_path_open = open,
+            GovernanceAction::SetTakeRate { take_rate } => self.protocol_take_rate = take_rate,
+            GovernanceAction::SetRelayerTakeRate { take_rate } => {
+                self.relayer_take_rate = take_rate
+            }
+            GovernanceAction::TreasurySet {
+                treasury,
+                start_time,
+            } => {
+                self.treasury = Some(treasury);
+                self.treasury_start_time = Some(start_time);
+            }
+            GovernanceAction::AuthorityTransfer { authority } => self.authority = Some(authority),
+            _ => {}
+        }
+        self.block_height = event.block_height;
+        self.log_index = event.log_index;
+    }
+}
+
+/// Rebuilds the status from every event in the governance log
+pub fn rebuild_dex_status(db: &rocksdb::DB) -> DexStatus {
+    let mut status = DexStatus::default();
+    for event in get_governance_log(db) {
+        status.apply(&event);
+    }
+    status
+}
+
+/// Returns the cached status, rebuilding it from the governance log if it has not been cached yet
+pub fn current_dex_status(db: &rocksdb::DB) -> DexStatus {
+    match get_dex_status(db) {
+        Some(status) => status,
+        None => {
+            let status = rebuild_dex_status(db);
+            save_dex_status(db, &status);
+            status
+        }
+    }
+}
+
+/// Applies newly indexed governance events to the cached status
+pub fn update_dex_status(db: &rocksdb::DB, events: &[GovernanceEvent]) {
+    if events.is_empty() {
+        return;
+    }
+    let mut events = events.to_vec();
+    events.sort_by(|a, b| {
+        a.block_height
+            .cmp(&b.block_height)
+            .then(a.log_index.cmp(&b.log_index))
+    });
+    let mut status = current_dex_status(db);
+    for event in events.iter() {
+        status.apply(event);
+    }
+    save_dex_status(db, &status);
+}
+
+// function readSlot (uint256 slot) public view returns (uint256 data)
+pub const READ_SLOT_SIG: &str = "readSlot(uint256)";
+
+// StorageLayout packs the following into slot 0, starting from the low order bytes:
+//     address internal lockHolder_;    (bytes 0-19)
+//     bool internal sudoMode_;         (byte 20)
+//     bool internal msgValSpent_;      (byte 21)
+//     bool internal hotPathOpen_;      (byte 22)
+//     bool internal inSafeMode_;       (byte 23)
+//     uint8 internal relayerTakeRate_; (byte 24)
+// The lock holder, sudo mode and msg.value flags are reset at the end of every call, so they are always
+// zero when read from outside a call
+const FLAGS_SLOT: u64 = 0;
+const CALL_STATE_BYTES: usize = 22;
+const HOT_PATH_OPEN_BYTE: usize = 22;
+const SAFE_MODE_BYTE: usize = 23;
+const RELAYER_TAKE_RATE_BYTE: usize = 24;
+// Slot 0 is followed by proxyPaths_ (slots 1-65536), authority_ and the mappings numbered in CrocSlots. After
+// pools_ (CrocSlots.POOL_PARAM_SLOT, 65545) and improves_ the next slot packs:
+//     uint128 internal newPoolLiq_;     (bytes 0-15)
+//     uint8 internal protocolTakeRate_; (byte 16)
+const PROTOCOL_TAKE_RATE_SLOT: u64 = 65547;
+const PROTOCOL_TAKE_RATE_BYTE: usize = 16;
+// Following userBals_ (CrocSlots.BAL_MAP_SLOT, 65552) the next slot packs:
+//     address treasury_;          (bytes 0-19)
+//     uint64 treasuryStartTime_;  (bytes 20-27)
+const TREASURY_SLOT: u64 = 65553;
+const TREASURY_START_TIME_BYTE: usize = 20;
+const TREASURY_BYTES: usize = 28;
+
+/// The safe mode, hot path and relayer take rate values currently stored by CrocSwapDex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DexFlags {
+    pub safe_mode: bool,
+    pub hot_path_open: bool,
+    pub relayer_take_rate: u8,
+}
+
+impl DexFlags {
+    /// Decodes the flags from the 32 byte big endian word returned by readSlot(0). Nonzero call state
+    /// means the word does not match the expected layout, so it is rejected rather than misread
+    pub fn from_slot(word: &[u8]) -> Result<DexFlags, AltheaError> {
+        let word = slot_word(word, FLAGS_SLOT)?;
+        if (0..CALL_STATE_BYTES).any(|n| byte_at(word, n) != 0) {
+            return Err(unexpected_slot(FLAGS_SLOT, word));
+        }
+        Ok(DexFlags {
+            hot_path_open: byte_at(word, HOT_PATH_OPEN_BYTE) != 0,
+            safe_mode: byte_at(word, SAFE_MODE_BYTE) != 0,
+            relayer_take_rate: byte_at(word, RELAYER_TAKE_RATE_BYTE),
+        })
+    }
+}
+
+/// Decodes protocolTakeRate_ from the word returned by readSlot(PROTOCOL_TAKE_RATE_SLOT)
+pub fn protocol_take_rate_from_slot(word: &[u8]) -> Result<u8, AltheaError> {
+    let word = slot_word(word, PROTOCOL_TAKE_RATE_SLOT)?;
+    if (PROTOCOL_TAKE_RATE_BYTE + 1..32).any(|n| byte_at(word, n) != 0) {
+        return Err(unexpected_slot(PROTOCOL_TAKE_RATE_SLOT, word));
+    }
+    Ok(byte_at(word, PROTOCOL_TAKE_RATE_BYTE))
+}
+
+/// Decodes the treasury and its start time from the word returned by readSlot(TREASURY_SLOT), both are
+/// None while no treasury has been set
+pub fn treasury_from_slot(word: &[u8]) -> Result<(Option<Address>, Option<u64>), AltheaError> {
+    let word = slot_word(word, TREASURY_SLOT)?;
+    if (TREASURY_BYTES..32).any(|n| byte_at(word, n) != 0) {
+        return Err(unexpected_slot(TREASURY_SLOT, word));
+    }
+    let treasury = Address::from_slice(&word[12..32])?;
+    if treasury == Address::default() {
+        return Ok((None, None));
+    }
+    let start = 32 - TREASURY_BYTES;
+    let end = 32 - TREASURY_START_TIME_BYTE;
+    let start_time = u64::from_be_bytes(word[start..end].try_into().unwrap());
+    Ok((Some(treasury), Some(start_time)))
+}
+
+fn slot_word(word: &[u8], slot: u64) -> Result<&[u8], AltheaError> {
+    if word.len() < 32 {
+        return Err(AltheaError::InvalidEventLogError(format!(
+            "too short for readSlot({}) response",
+            slot
+        )));
+    }
+    Ok(&word[..32])
+}
+
+// The word is big endian, so the nth low order byte is at index 31 - n
+fn byte_at(word: &[u8], n: usize) -> u8 {
+    word[31 - n]
+}
+
+fn unexpected_slot(slot: u64, word: &[u8]) -> AltheaError {
+    AltheaError::InvalidEventLogError(format!(
+        "unexpected value in slot {}: 0x{}",
+        slot,
+        word.iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    ))
+}
+
+async fn read_slot(web30: &Web3, slot: u64) -> Result<Vec<u8>, AltheaError> {
+    Ok(web30
+        .simulate_transaction(
+            TransactionRequest::quick_tx(
+                Address::from_str(DEFAULT_QUERIER).unwrap(),
+                Address::from_str(CROC_SWAP_CTR).unwrap(),
+                encode_call(READ_SLOT_SIG, &[Uint256::from(slot).into()])?,
+            ),
+            None,
+        )
+        .await?)
+}
+
+pub async fn get_dex_flags(web30: &Web3) -> Result<DexFlags, AltheaError> {
+    DexFlags::from_slot(&read_slot(web30, FLAGS_SLOT).await?)
+}
+
+/// Checks the status reconstructed from indexed events against the values stored by the contract,
+/// the contract is authoritative so any disagreement is logged and corrected in the cache. Events indexed
+/// after the check are applied on top of the corrected status, so it is checked again once caught up
+pub async fn verify_dex_status(db: &rocksdb::DB, web30: &Web3) -> Result<DexStatus, AltheaError> {
+    let flags = get_dex_flags(web30).await?;
+    let protocol_take_rate =
+        protocol_take_rate_from_slot(&read_slot(web30, PROTOCOL_TAKE_RATE_SLOT).await?)?;
+    let (treasury, treasury_start_time) =
+        treasury_from_slot(&read_slot(web30, TREASURY_SLOT).await?)?;
+
+    let mut status = current_dex_status(db);
+    let stored = DexStatus {
+        safe_mode: flags.safe_mode,
+        hot_path_open: flags.hot_path_open,
+        protocol_take_rate,
+        relayer_take_rate: flags.relayer_take_rate,
+        treasury,
+        treasury_start_time,
+        ..status.clone()
+    };
+    if status != stored {
+        warn!(
+            "Indexed dex status {:?} disagrees with contract state {:?}, using contract state",
+            status, stored
+        );
+        status = stored;
+        save_dex_status(db, &status);
+    } else {
+        info!("Indexed dex status matches contract state");
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // readSlot(0) outside of a call with the hot path open, safe mode off and a relayer take rate of 128
+    const SLOT_WORD: &str = "0000000000000080000100000000000000000000000000000000000000000000";
+
+    fn word(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_flags() {
+        let flags = DexFlags::from_slot(&word(SLOT_WORD)).unwrap();
+        assert_eq!(
+            flags,
+            DexFlags {
+                safe_mode: false,
+                hot_path_open: true,
+                relayer_take_rate: 128,
+            }
+        );
+
+        // Safe mode on, hot path closed and no relayer take
+        let flags = DexFlags::from_slot(&word(
+            "0000000000000000010000000000000000000000000000000000000000000000",
+        ))
+        .unwrap();
+        assert!(flags.safe_mode);
+        assert!(!flags.hot_path_open);
+        assert_eq!(flags.relayer_take_rate, 0);
+    }
+
+    #[test]
+    fn test_reject_call_state() {
+        // A held lock
+        assert!(DexFlags::from_slot(&word(
+            "0000000000000000000100000000000000000000000000000000000000000001",
+        ))
+        .is_err());
+        // msgValSpent_ set, which is where a layout without it would store hotPathOpen_
+        assert!(DexFlags::from_slot(&word(
+            "0000000000000000000001000000000000000000000000000000000000000000",
+        ))
+        .is_err());
+        assert!(DexFlags::from_slot(&[0u8; 31]).is_err());
+    }
+
+    #[test]
+    fn test_decode_protocol_take_rate() {
+        // A take rate of 32 above 2^64 of new pool liquidity
+        assert_eq!(
+            protocol_take_rate_from_slot(&word(
+                "0000000000000000000000000000002000000000000000010000000000000000",
+            ))
+            .unwrap(),
+            32
+        );
+        assert_eq!(protocol_take_rate_from_slot(&[0u8; 32]).unwrap(), 0);
+        // A value above protocolTakeRate_ means the slot holds something else
+        assert!(protocol_take_rate_from_slot(&word(
+            "0000000000000000000000000000012000000000000000000000000000000000",
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_decode_treasury() {
+        // Treasury 0x0412C7c846bb6b7DC462CF6B453f76D8440b2609 with a start time of 1700000000
+        let (treasury, start_time) = treasury_from_slot(&word(
+            "00000000000000006553f100\
+             0412c7c846bb6b7dc462cf6b453f76d8440b2609",
+        ))
+        .unwrap();
+        assert_eq!(
+            treasury,
+            Some(Address::from_str("0x0412C7c846bb6b7DC462CF6B453f76D8440b2609").unwrap())
+        );
+        assert_eq!(start_time, Some(1700000000));
+        // No treasury has been set
+        assert_eq!(treasury_from_slot(&[0u8; 32]).unwrap(), (None, None));
+        assert!(treasury_from_slot(&word(
+            "0100000000000000000000000000000000000000000000000000000000000000",
+        ))
+        .is_err());
+    }
+}
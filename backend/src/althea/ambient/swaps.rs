@@ -0,0 +1,59 @@
+use clarity::{Address, Uint256};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decoder::{DecodedLog, EventDecoder, EventField, SolType},
+    events::SWAP_SIGNATURE,
+};
+use crate::althea::error::AltheaError;
+
+/// Swap is an event emitted when a user has exchanged one token for another in an Ambient pool
+/// Note: This event was added to our fork to avoid the need to analyze ethereum traces to find function calls
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct SwapEvent {
+    pub block_height: Uint256,
+    pub log_index: Uint256,
+    pub user: Address,
+    pub buy: Address,
+    pub sell: Address,
+    pub pool_idx: Uint256,
+    pub buy_qty: u128,
+    pub sell_qty: u128,
+}
+
+impl EventDecoder for SwapEvent {
+    const SIGNATURE: &'static str = SWAP_SIGNATURE;
+    const FIELDS: &'static [EventField] = &[
+        EventField::indexed("user", SolType::Address),
+        EventField::indexed("buy", SolType::Address),
+        EventField::indexed("sell", SolType::Address),
+        EventField::data("poolIdx", SolType::Uint(256)),
+        EventField::data("buyQty", SolType::Uint(128)),
+        EventField::data("sellQty", SolType::Uint(128)),
+    ];
+
+    fn from_decoded(log: &DecodedLog) -> Result<Self, AltheaError> {
+        Ok(SwapEvent {
+            block_height: log.block_height,
+            log_index: log.log_index,
+            user: log.address("user")?,
+            buy: log.address("buy")?,
+            sell: log.address("sell")?,
+            pool_idx: log.uint256("poolIdx")?,
+            buy_qty: log.u128("buyQty")?,
+            sell_qty: log.u128("sellQty")?,
+        })
+    }
+}
+
+impl SwapEvent {
+    /// Ambient pools always order their tokens so that the base token has the lower address,
+    /// returns the (base, quote) pair of the pool this swap was performed in
+    pub fn base_quote(&self) -> (Address, Address) {
+        if self.buy.as_bytes() < self.sell.as_bytes() {
+            (self.buy, self.sell)
+        } else {
+            (self.sell, self.buy)
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use clarity::Uint256;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    decoder::{DecodedLog, EventDecoder, EventField, SolType},
+    events::{DISABLE_POOL_TEMPLATE_SIGNATURE, SET_POOL_TEMPLATE_SIGNATURE},
+};
+use crate::althea::error::AltheaError;
+
+/// SetPoolTemplate is an event emitted when governance writes or overwrites a pool template,
+/// any user may then create pools with the template's poolIdx
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct SetPoolTemplateEvent {
+    pub block_height: Uint256,
+    pub log_index: Uint256,
+    pub pool_idx: Uint256,
+    pub fee_rate: u16,
+    pub tick_size: u16,
+    pub jit_thresh: u8,
+    pub knockout: u8,
+    pub oracle_flags: u8,
+}
+
+impl EventDecoder for SetPoolTemplateEvent {
+    const SIGNATURE: &'static str = SET_POOL_TEMPLATE_SIGNATURE;
+    const FIELDS: &'static [EventField] = &[
+        EventField::indexed("poolIdx", SolType::Uint(256)),
+        EventField::data("feeRate", SolType::Uint(16)),
+        EventField::data("tickSize", SolType::Uint(16)),
+        EventField::data("jitThresh", SolType::Uint(8)),
+        EventField::data("knockout", SolType::Uint(8)),
+        EventField::data("oracleFlags", SolType::Uint(8)),
+    ];
+
+    fn from_decoded(log: &DecodedLog) -> Result<Self, AltheaError> {
+        Ok(SetPoolTemplateEvent {
+            block_height: log.block_height,
+            log_index: log.log_index,
+            pool_idx: log.uint256("poolIdx")?,
+            fee_rate: log.u16("feeRate")?,
+            tick_size: log.u16("tickSize")?,
+            jit_thresh: log.u8("jitThresh")?,
+            knockout: log.u8("knockout")?,
+            oracle_flags: log.u8("oracleFlags")?,
+        })
+    }
+}
+
+/// DisablePoolTemplate is an event emitted when governance disables a pool template, halting the
+/// creation of new pools with that poolIdx. Existing pools using the template are unaffected.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct DisablePoolTemplateEvent {
+    pub block_height: Uint256,
+    pub log_index: Uint256,
+    pub pool_idx: Uint256,
+}
+
+impl EventDecoder for DisablePoolTemplateEvent {
+    const SIGNATURE: &'static str = DISABLE_POOL_TEMPLATE_SIGNATURE;
+    const FIELDS: &'static [EventField] = &[EventField::indexed("poolIdx", SolType::Uint(256))];
+
+    fn from_decoded(log: &DecodedLog) -> Result<Self, AltheaError> {
+        Ok(DisablePoolTemplateEvent {
+            block_height: log.block_height,
+            log_index: log.log_index,
+            pool_idx: log.uint256("poolIdx")?,
+        })
+    }
+}
+
+/// The current configuration of a pool template, built by applying its SetPoolTemplate and
+/// DisablePoolTemplate events in order
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct PoolTemplate {
+    pub pool_idx: Uint256,
+    /// The swap fee rate in units of 0.0001%
+    pub fee_rate: u16,
+    /// The minimum tick size for range orders
+    pub tick_size: u16,
+    /// The JIT liquidity TTL in units of 10 seconds
+    pub jit_thresh: u8,
+    /// The knockout liquidity parameter bits (see Ambient's KnockoutLiq library)
+    pub knockout: u8,
+    /// The permissioned pool oracle flags, zero for permissionless pools
+    pub oracle_flags: u8,
+    /// True if governance has disabled the creation of new pools from this template
+    pub disabled: bool,
+    /// The block of the most recent event affecting this template
+    pub block_height: Uint256,
+}
+
+impl From<SetPoolTemplateEvent> for PoolTemplate {
+    fn from(e: SetPoolTemplateEvent) -> Self {
+        PoolTemplate {
+            pool_idx: e.pool_idx,
+            fee_rate: e.fee_rate,
+            tick_size: e.tick_size,
+            jit_thresh: e.jit_thresh,
+            knockout: e.knockout,
+            oracle_flags: e.oracle_flags,
+            disabled: false,
+            block_height: e.block_height,
+        }
+    }
+}
+
+/// A single template event, used to apply Set and Disable events in the order they occurred
+pub enum TemplateChange {
+    Set(SetPoolTemplateEvent),
+    Disable(DisablePoolTemplateEvent),
+}
+
+impl TemplateChange {
+    /// The (block, log index) position of the event on chain
+    pub fn order(&self) -> (Uint256, Uint256) {
+        match self {
+            TemplateChange::Set(e) => (e.block_height, e.log_index),
+            TemplateChange::Disable(e) => (e.block_height, e.log_index),
+        }
+    }
+}
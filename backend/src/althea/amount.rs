@@ -0,0 +1,413 @@
+// A lossless fixed point decimal used for every token amount reported by the API. Chain and EVM amounts
+// routinely exceed the 2^53 that an f64 can represent exactly, so amounts are never converted through
+// floating point except to compute ratios.
+
+use clarity::Uint256;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::althea::error::AltheaError;
+use crate::althea::token_mappings::denom_exponent;
+
+/// The number of decimal places carried by a Cosmos SDK Dec
+pub const SDK_DEC_PRECISION: u32 = 18;
+
+/// A non-negative decimal number equal to `units / 10^scale`
+#[derive(Debug, Clone)]
+pub struct Amount {
+    units: Uint256,
+    scale: u32,
+}
+
+impl Amount {
+    pub fn new(units: impl Into<Uint256>, scale: u32) -> Self {
+        Amount {
+            units: units.into(),
+            scale,
+        }
+    }
+
+    /// Parses a plain decimal string such as `123` or `0.045000`, keeping every given decimal place
+    pub fn parse(input: &str) -> Result<Self, AltheaError> {
+        let invalid = || AltheaError::InvalidAmountError(input.to_string());
+        let (integer, fraction) = match input.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (input, ""),
+        };
+        if integer.is_empty()
+            || !integer
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let units =
+            Uint256::from_str(&format!("{}{}", integer, fraction)).map_err(|_| invalid())?;
+        Ok(Amount {
+            units,
+            scale: fraction.len() as u32,
+        })
+    }
+
+    /// Parses an sdk.Dec. Over gRPC a Dec is the text of its underlying integer, which carries 18 decimal
+    /// places, while JSON endpoints return the decimal form. Either way the result has 18 decimal places
+    pub fn from_sdk_dec(input: &str) -> Result<Self, AltheaError> {
+        if input.contains('.') {
+            Ok(Self::parse(input)?.with_scale(SDK_DEC_PRECISION))
+        } else {
+            Ok(Self::parse(input)?.shift(SDK_DEC_PRECISION))
+        }
+    }
+
+    /// Converts an amount of `denom` in base units (e.g. aalthea) to display units (e.g. ALTHEA), keeping
+    /// exactly as many decimal places as the denom's exponent. Precision below one base unit is truncated
+    pub fn to_display(&self, denom: &str) -> Amount {
+        let exponent = denom_exponent(denom);
+        self.clone().shift(exponent).with_scale(exponent)
+    }
+
+    /// Divides the amount by `10^exponent` without losing precision
+    pub fn shift(self, exponent: u32) -> Amount {
+        Amount {
+            units: self.units,
+            scale: self.scale + exponent,
+        }
+    }
+
+    /// Returns the same value with exactly `scale` decimal places, truncating toward zero if `scale` is
+    /// smaller than the current scale
+    pub fn with_scale(&self, scale: u32) -> Amount {
+        let digits = self.units.to_string();
+        let digits = match scale.cmp(&self.scale) {
+            Ordering::Equal => return self.clone(),
+            Ordering::Greater => format!("{}{}", digits, "0".repeat((scale - self.scale) as usize)),
+            Ordering::Less => {
+                let drop = (self.scale - scale) as usize;
+                if drop >= digits.len() {
+                    "0".to_string()
+                } else {
+                    digits[..digits.len() - drop].to_string()
+                }
+            }
+        };
+        Amount {
+            // Only fails if padding overflows 256 bits, far beyond any real amount
+            units: Uint256::from_str(&digits).unwrap_or_else(|_| 0u8.into()),
+            scale,
+        }
+    }
+
+    /// The absolute difference between two amounts, with the larger of their scales
+    pub fn abs_diff(&self, other: &Amount) -> Amount {
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.with_scale(scale).units, other.with_scale(scale).units);
+        let units = if a >= b { a - b } else { b - a };
+        Amount { units, scale }
+    }
+
+    pub fn units(&self) -> &Uint256 {
+        &self.units
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.units == 0u8.into()
+    }
+
+    /// An approximation of the amount, only for computing ratios and percentages
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or_default()
+    }
+}
+
+impl From<u128> for Amount {
+    fn from(units: u128) -> Self {
+        Amount::new(units, 0)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = AltheaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Amount::parse(s)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.units.to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}", digits);
+        }
+        // Left pad so there is at least one integer digit
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}.{}", integer, fraction)
+    }
+}
+
+impl PartialEq for Amount {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Amount {}
+
+impl PartialOrd for Amount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Amounts are compared as digit strings, since rescaling the units of amounts close to 2^256 overflows
+impl Ord for Amount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        let digits = |a: &Amount| {
+            let units = a.units.to_string();
+            let units = units.trim_start_matches('0');
+            if units.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", units, "0".repeat((scale - a.scale) as usize))
+            }
+        };
+        let (a, b) = (digits(self), digits(other));
+        a.len().cmp(&b.len()).then_with(|| a.cmp(&b))
+    }
+}
+
+// Amounts are serialized as their exact decimal string, which preserves the scale through trailing zeros
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Amount::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2^256 - 1, the largest amount representable
+    const MAX_UNITS: &str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+    // A deterministic xorshift generator, so that failures are reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        // A decimal string of up to 77 integer digits, which always fits in 256 bits, without leading zeros
+        fn decimal(&mut self) -> String {
+            let integer_digits = 1 + self.below(77) as usize;
+            let mut s = (1 + self.below(9)).to_string();
+            for _ in 1..integer_digits {
+                s.push_str(&self.below(10).to_string());
+            }
+            if self.below(4) == 0 {
+                s = "0".to_string();
+            }
+            let fraction_digits = self.below(20) as usize;
+            if fraction_digits > 0 && integer_digits + fraction_digits <= 77 {
+                s.push('.');
+                for _ in 0..fraction_digits {
+                    s.push_str(&self.below(10).to_string());
+                }
+            }
+            s
+        }
+    }
+
+    fn amount(s: &str) -> Amount {
+        Amount::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_display_round_trip() {
+        for s in [
+            "0",
+            "0.000",
+            "123.450",
+            "0.000000000000000001",
+            // 2^53 + 1, the first integer an f64 cannot represent
+            "9007199254740993",
+            "340282366920938463463374607431768211455",
+            MAX_UNITS,
+            "11579208923731619542357098500868790785326998466564056403945758400791312963993.5",
+        ] {
+            assert_eq!(amount(s).to_string(), s);
+        }
+
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+        for _ in 0..1000 {
+            let s = rng.decimal();
+            let parsed = amount(&s);
+            assert_eq!(parsed.to_string(), s);
+            assert_eq!(amount(&parsed.to_string()), parsed);
+            assert_eq!(
+                serde_json::from_str::<Amount>(&serde_json::to_string(&parsed).unwrap()).unwrap(),
+                parsed
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        for s in ["", ".5", "-1", "1e5", "1.2.3", " 1", "0x10", "1,000"] {
+            assert!(Amount::parse(s).is_err(), "{} should not parse", s);
+        }
+        // 2^256 does not fit
+        assert!(Amount::parse(
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_with_scale_truncates() {
+        assert_eq!(amount("123.456").with_scale(1).to_string(), "123.4");
+        assert_eq!(amount("123.456").with_scale(0).to_string(), "123");
+        assert_eq!(amount("123.456").with_scale(5).to_string(), "123.45600");
+        // Dropping exactly as many digits as the units have
+        assert_eq!(amount("0.123").with_scale(0).to_string(), "0");
+        // Dropping more digits than the units have
+        assert_eq!(amount("0.000123").with_scale(2).to_string(), "0.00");
+        assert_eq!(amount("0.000").with_scale(1).to_string(), "0.0");
+        assert_eq!(
+            amount(MAX_UNITS).shift(3).with_scale(0).to_string(),
+            &MAX_UNITS[..75]
+        );
+
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        for _ in 0..1000 {
+            let a = amount(&rng.decimal());
+            let scale = rng.below(20) as u32;
+            let truncated = a.with_scale(scale);
+            assert_eq!(truncated.scale(), scale);
+            if scale >= a.scale() {
+                // Padding past 2^256 is not representable
+                if a.units().to_string().len() + (scale - a.scale()) as usize <= 77 {
+                    assert_eq!(truncated, a);
+                }
+            } else {
+                // Truncation is toward zero and loses less than one unit of the new scale
+                assert!(truncated <= a);
+                assert!(a.abs_diff(&truncated) < Amount::new(1u8, scale));
+                assert!(a.to_string().starts_with(&truncated.to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_sdk_dec() {
+        // The integer form carries 18 implied decimal places
+        assert_eq!(
+            Amount::from_sdk_dec("1500000000000000000")
+                .unwrap()
+                .to_string(),
+            "1.500000000000000000"
+        );
+        assert_eq!(
+            Amount::from_sdk_dec("5").unwrap().to_string(),
+            "0.000000000000000005"
+        );
+        assert_eq!(
+            Amount::from_sdk_dec("1.5").unwrap().to_string(),
+            "1.500000000000000000"
+        );
+        assert_eq!(
+            Amount::from_sdk_dec("0.050000000000000000")
+                .unwrap()
+                .to_string(),
+            "0.050000000000000000"
+        );
+        assert!(Amount::from_sdk_dec("abc").is_err());
+
+        // Both forms of the same Dec parse to the same amount
+        let mut rng = Rng(0xdeadbeefcafef00d);
+        for _ in 0..1000 {
+            let units = (rng.next() as u128) * (rng.next() as u128);
+            let integer_form = units.to_string();
+            let decimal_form = Amount::new(units, SDK_DEC_PRECISION).to_string();
+            let a = Amount::from_sdk_dec(&integer_form).unwrap();
+            let b = Amount::from_sdk_dec(&decimal_form).unwrap();
+            assert_eq!(a.scale(), SDK_DEC_PRECISION);
+            assert_eq!(a.to_string(), b.to_string());
+        }
+    }
+
+    #[test]
+    fn test_ord_across_scales() {
+        assert_eq!(amount("1.50"), amount("1.5"));
+        assert!(amount("1.5") < amount("1.51"));
+        assert!(amount("10") > amount("9.999"));
+        assert_eq!(amount("0.000"), amount("0"));
+        assert!(amount(MAX_UNITS) > amount(MAX_UNITS).shift(1));
+
+        // Compared against exact integer arithmetic, values below 10^18 and scales below 18 keep the
+        // rescaled values below 10^36 so they fit in a u128
+        let mut rng = Rng(0x0123456789abcdef);
+        for _ in 0..1000 {
+            let (a, b) = (rng.below(10u64.pow(18)), rng.below(10u64.pow(18)));
+            let (sa, sb) = (rng.below(18) as u32, rng.below(18) as u32);
+            let scale = sa.max(sb);
+            let expected =
+                (a as u128 * 10u128.pow(scale - sa)).cmp(&(b as u128 * 10u128.pow(scale - sb)));
+            assert_eq!(
+                Amount::new(a as u128, sa).cmp(&Amount::new(b as u128, sb)),
+                expected
+            );
+            assert_eq!(
+                Amount::new(a as u128, sa) == Amount::new(b as u128, sb),
+                expected == Ordering::Equal
+            );
+        }
+    }
+
+    #[test]
+    fn test_large_values_are_exact() {
+        // An f64 would round 2^53 + 1 down to 2^53
+        let a = amount("9007199254740993");
+        assert_eq!(a.abs_diff(&amount("9007199254740992")).to_string(), "1");
+        assert_eq!(
+            Amount::from(u128::MAX).to_string(),
+            "340282366920938463463374607431768211455"
+        );
+        let max = amount(MAX_UNITS);
+        assert_eq!(
+            max.abs_diff(&amount(&format!("{}4", &MAX_UNITS[..MAX_UNITS.len() - 1])))
+                .to_string(),
+            "1"
+        );
+        assert_eq!(max.to_display("aalthea").to_string(), {
+            let (integer, fraction) = MAX_UNITS.split_at(MAX_UNITS.len() - 18);
+            format!("{}.{}", integer, fraction)
+        });
+    }
+}
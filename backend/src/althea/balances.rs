@@ -0,0 +1,309 @@
+// Bank module balances annotated with what each denom is: IBC vouchers are resolved to their origin
+// through the transfer module's denom traces, bank metadata supplies display units, and the erc20
+// module supplies the ERC20 each coin converts to
+
+use clarity::Address as EthAddress;
+use cosmos_sdk_proto_althea::cosmos::bank::v1beta1::{
+    query_client::QueryClient as BankQueryClient, Metadata, QueryDenomsMetadataRequest,
+};
+use cosmos_sdk_proto_althea::cosmos::base::query::v1beta1::PageRequest;
+use deep_space::{Address as CosmosAddress, Contact};
+use futures::future::join_all;
+use log::warn;
+use prost::Message;
+use rocksdb::DB;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, Endpoint};
+
+use crate::althea::amount::Amount;
+use crate::althea::token_mappings::{denom_exponent, fetch_token_pairs};
+use crate::althea::{get_cached, start_eviction_task, Cached, CACHE_DURATION};
+
+const BALANCES_KEY_PREFIX: &str = "balances_";
+const DENOM_METADATA_KEY: &[u8] = b"denom_metadata";
+const DENOM_TRACE_KEY_PREFIX: &str = "denom_trace_";
+const IBC_DENOM_PREFIX: &str = "ibc/";
+
+/// ibc-go's transfer module is not included in the cosmos protos, these mirror its DenomTrace query
+const DENOM_TRACE_PATH: &str = "/ibc.applications.transfer.v1.Query/DenomTrace";
+
+#[derive(Clone, PartialEq, Message)]
+struct QueryDenomTraceRequest {
+    #[prost(string, tag = "1")]
+    hash: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct QueryDenomTraceResponse {
+    #[prost(message, optional, tag = "1")]
+    denom_trace: Option<ProtoDenomTrace>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ProtoDenomTrace {
+    #[prost(string, tag = "1")]
+    path: String,
+    #[prost(string, tag = "2")]
+    base_denom: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountBalances {
+    pub address: String,
+    pub balances: Vec<BankBalance>,
+    pub last_updated: u64,
+}
+
+impl Cached for AccountBalances {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BankBalance {
+    pub denom: String,
+    /// The amount in the denom's base units
+    pub amount: Amount,
+    /// The display unit from the denom's bank metadata, e.g. althea for aalthea
+    pub display_denom: Option<String>,
+    /// The number of decimal places between the base unit and the display unit, None if unknown
+    pub exponent: Option<u32>,
+    /// The amount in display units, None if the exponent is unknown
+    pub display_amount: Option<Amount>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    /// The origin of an ibc/... voucher
+    pub ibc_trace: Option<DenomTrace>,
+    /// The ERC20 this coin converts to and from through the erc20 module
+    pub erc20: Option<Erc20Representation>,
+}
+
+/// The channels an IBC voucher travelled through, e.g. transfer/channel-0, and its denom on the origin chain
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DenomTrace {
+    pub path: String,
+    pub base_denom: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Erc20Representation {
+    pub address: EthAddress,
+    /// Whether conversion is currently enabled
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct DenomMetadata {
+    base: String,
+    display: String,
+    exponent: u32,
+    name: String,
+    symbol: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct DenomMetadataList {
+    metadata: Vec<DenomMetadata>,
+    last_updated: u64,
+}
+
+impl Cached for DenomMetadataList {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+impl From<Metadata> for DenomMetadata {
+    fn from(m: Metadata) -> Self {
+        // The display unit is one of the denom units, whose exponent is relative to the base unit
+        let exponent = m
+            .denom_units
+            .iter()
+            .find(|u| u.denom == m.display)
+            .map(|u| u.exponent)
+            .unwrap_or_default();
+        DenomMetadata {
+            base: m.base,
+            display: m.display,
+            exponent,
+            name: m.name,
+            symbol: m.symbol,
+        }
+    }
+}
+
+fn get_cached_balances(db: &rocksdb::DB, address: &CosmosAddress) -> Option<AccountBalances> {
+    let key = format!("{}{}", BALANCES_KEY_PREFIX, address);
+    get_cached(db, key.as_bytes(), CACHE_DURATION)
+}
+
+fn cache_balances(db: &rocksdb::DB, address: &CosmosAddress, balances: &AccountBalances) {
+    let key = format!("{}{}", BALANCES_KEY_PREFIX, address);
+    let encoded = bincode::serialize(balances).unwrap();
+    db.put(key.as_bytes(), encoded).unwrap();
+}
+
+/// Periodically deletes expired balances from the cache. Balances are only cached when requested and
+/// are not refreshed, so the balances of an address that is not requested again would otherwise remain
+pub fn start_balances_eviction_task(db: Arc<DB>) {
+    start_eviction_task::<AccountBalances>(db, BALANCES_KEY_PREFIX, CACHE_DURATION, "balances");
+}
+
+/// Fetches every bank balance of `address` along with its denom's metadata, IBC origin and ERC20
+pub async fn fetch_balances(
+    db: &rocksdb::DB,
+    contact: &Contact,
+    address: CosmosAddress,
+) -> Result<AccountBalances, Box<dyn std::error::Error>> {
+    if let Some(cached) = get_cached_balances(db, &address) {
+        return Ok(cached);
+    }
+
+    let coins = contact.get_balances(address).await?;
+    let metadata = fetch_denom_metadata(db, contact).await?;
+    // Token pairs only annotate the balances, so a failure to query them is logged rather than returned
+    let pairs = fetch_token_pairs(contact).await.unwrap_or_else(|e| {
+        warn!("Failed to fetch erc20 token pairs: {}", e);
+        vec![]
+    });
+
+    // Every ibc/ denom is resolved at once over a single channel, which only connects if a trace is not
+    // already stored
+    let channel = Endpoint::from_shared(contact.get_url())?.connect_lazy();
+    let traces = join_all(coins.iter().map(|coin| {
+        let channel = channel.clone();
+        async move {
+            let hash = coin.denom.strip_prefix(IBC_DENOM_PREFIX)?;
+            match fetch_denom_trace(db, channel, hash).await {
+                Ok(trace) => trace,
+                Err(e) => {
+                    warn!("Failed to fetch denom trace of {}: {}", coin.denom, e);
+                    None
+                }
+            }
+        }
+    }))
+    .await;
+
+    let mut balances = Vec::new();
+    for (coin, ibc_trace) in coins.into_iter().zip(traces) {
+        let meta = metadata.iter().find(|m| m.base == coin.denom);
+        let exponent = match meta {
+            Some(m) => Some(m.exponent),
+            None => Some(denom_exponent(&coin.denom)).filter(|e| *e > 0),
+        };
+        let amount = Amount::new(coin.amount, 0);
+        balances.push(BankBalance {
+            display_amount: exponent.map(|e| amount.clone().shift(e).with_scale(e)),
+            amount,
+            display_denom: meta.map(|m| m.display.clone()),
+            exponent,
+            name: meta.map(|m| m.name.clone()).filter(|n| !n.is_empty()),
+            symbol: meta.map(|m| m.symbol.clone()).filter(|s| !s.is_empty()),
+            ibc_trace,
+            erc20: pairs.iter().find(|(_, denom, _)| *denom == coin.denom).map(
+                |(address, _, enabled)| Erc20Representation {
+                    address: *address,
+                    enabled: *enabled,
+                },
+            ),
+            denom: coin.denom,
+        });
+    }
+
+    let response = AccountBalances {
+        address: address.to_string(),
+        balances,
+        last_updated: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    cache_balances(db, &address, &response);
+    Ok(response)
+}
+
+// Denom metadata is shared by every address, so it is cached once for all of them
+async fn fetch_denom_metadata(
+    db: &rocksdb::DB,
+    contact: &Contact,
+) -> Result<Vec<DenomMetadata>, Box<dyn std::error::Error>> {
+    if let Some(list) = get_cached::<DenomMetadataList>(db, DENOM_METADATA_KEY, CACHE_DURATION) {
+        return Ok(list.metadata);
+    }
+
+    let mut client = BankQueryClient::connect(contact.get_url()).await?;
+    let metadata: Vec<DenomMetadata> = client
+        .denoms_metadata(QueryDenomsMetadataRequest {
+            pagination: Some(PageRequest {
+                key: Vec::new(),
+                offset: 0,
+                limit: 1000,
+                count_total: false,
+                reverse: false,
+            }),
+        })
+        .await?
+        .into_inner()
+        .metadatas
+        .into_iter()
+        .map(DenomMetadata::from)
+        .collect();
+    let list = DenomMetadataList {
+        metadata,
+        last_updated: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    db.put(DENOM_METADATA_KEY, bincode::serialize(&list).unwrap())
+        .unwrap();
+    Ok(list.metadata)
+}
+
+// An ibc/ denom is the hash of its trace, so a resolved trace never changes and is stored without expiry
+async fn fetch_denom_trace(
+    db: &rocksdb::DB,
+    channel: Channel,
+    hash: &str,
+) -> Result<Option<DenomTrace>, Box<dyn std::error::Error>> {
+    let key = format!("{}{}", DENOM_TRACE_KEY_PREFIX, hash);
+    if let Some(data) = db.get(key.as_bytes()).unwrap() {
+        if let Ok(trace) = bincode::deserialize::<DenomTrace>(&data) {
+            return Ok(Some(trace));
+        }
+    }
+
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready().await?;
+    let codec =
+        tonic::codec::ProstCodec::<QueryDenomTraceRequest, QueryDenomTraceResponse>::default();
+    let response = match grpc
+        .unary(
+            tonic::Request::new(QueryDenomTraceRequest {
+                hash: hash.to_string(),
+            }),
+            PathAndQuery::from_static(DENOM_TRACE_PATH),
+            codec,
+        )
+        .await
+    {
+        Ok(response) => response.into_inner(),
+        Err(status) if status.code() == tonic::Code::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let Some(trace) = response.denom_trace else {
+        return Ok(None);
+    };
+    let trace = DenomTrace {
+        path: trace.path,
+        base_denom: trace.base_denom,
+    };
+    db.put(key.as_bytes(), bincode::serialize(&trace).unwrap())
+        .unwrap();
+    Ok(Some(trace))
+}
@@ -1,8 +1,10 @@
 use clarity::Address;
 use clarity::Uint256;
 use log::debug;
+use log::error;
+use serde::{Deserialize, Serialize};
 
-use crate::althea::ambient::croc_query::CurveState;
+use crate::althea::ambient::croc_query::{CurveState, PoolParams};
 
 /// CrocQuery queryCurve()
 pub const LATEST_CURVE_KEY: &str = "curve";
@@ -101,3 +103,137 @@ pub fn save_liquidity(
     let v = liquidity.to_be_bytes();
     db.put(k.as_bytes(), v).unwrap();
 }
+
+/// CrocQuery queryPoolParams()
+pub const LATEST_POOL_PARAMS_KEY: &str = "pool-params";
+pub fn pool_params_key(base: Address, quote: Address, pool_idx: Uint256) -> String {
+    format!("{}{}_{}_{}", LATEST_POOL_PARAMS_KEY, base, quote, pool_idx)
+}
+pub fn get_pool_params(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+) -> Option<PoolParams> {
+    let k = pool_params_key(base, quote, pool_idx);
+    let v = db.get(k.as_bytes()).unwrap();
+    #[allow(clippy::question_mark)]
+    if v.is_none() {
+        debug!("No pool params at key {}", k);
+        return None;
+    }
+    let decoded: PoolParams = bincode::deserialize(&v.unwrap()).unwrap();
+    Some(decoded)
+}
+pub fn save_pool_params(
+    db: &rocksdb::DB,
+    params: PoolParams,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+) {
+    debug!("Saving pool params {:?}", params);
+    let k = pool_params_key(base, quote, pool_idx);
+    let v = bincode::serialize(&params).unwrap();
+    db.put(k.as_bytes(), v).unwrap();
+}
+
+/// A CrocQuery queryPrice() result recorded at a known block and time, used to reconstruct
+/// which ticks a pool has traded at over time
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct PriceSnapshot {
+    pub block_height: Uint256,
+    /// Unix timestamp in seconds
+    pub time: u64,
+    /// Q64.64 square root price
+    pub price: u128,
+    /// The fees paid to liquidity providers by swaps since the previous snapshot, in raw base token units
+    pub lp_fees: f64,
+}
+
+/// Price snapshots are recorded at most this often per pool
+pub const PRICE_SNAPSHOT_INTERVAL: u64 = 600;
+/// Price snapshots older than this are deleted when a new snapshot is recorded
+pub const PRICE_SNAPSHOT_RETENTION: u64 = 30 * 86400;
+
+pub const PRICE_HISTORY_PREFIX: &str = "price-history_";
+fn price_history_pool_prefix(base: Address, quote: Address, pool_idx: Uint256) -> String {
+    format!("{}{}_{}_{}_", PRICE_HISTORY_PREFIX, base, quote, pool_idx)
+}
+// Times are zero padded so that snapshots sort by time
+fn price_history_key(base: Address, quote: Address, pool_idx: Uint256, time: u64) -> String {
+    format!(
+        "{}{:020}",
+        price_history_pool_prefix(base, quote, pool_idx),
+        time
+    )
+}
+
+// Gets the price snapshots of a pool recorded at or after `since`, oldest first
+pub fn get_price_history(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    since: u64,
+) -> Vec<PriceSnapshot> {
+    let prefix = price_history_pool_prefix(base, quote, pool_idx);
+    let start = price_history_key(base, quote, pool_idx, since);
+    let mut snapshots = vec![];
+    let iter = db.iterator(rocksdb::IteratorMode::From(
+        start.as_bytes(),
+        rocksdb::Direction::Forward,
+    ));
+    for entry in iter {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                match bincode::deserialize::<PriceSnapshot>(&v) {
+                    Ok(snapshot) => snapshots.push(snapshot),
+                    Err(_) => error!("Invalid price snapshot at key {:?}", k),
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    snapshots
+}
+// Gets the most recent price snapshot of a pool
+pub fn get_latest_price_snapshot(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+) -> Option<PriceSnapshot> {
+    let prefix = price_history_pool_prefix(base, quote, pool_idx);
+    let end = price_history_key(base, quote, pool_idx, u64::MAX);
+    let mut iter = db.iterator(rocksdb::IteratorMode::From(
+        end.as_bytes(),
+        rocksdb::Direction::Reverse,
+    ));
+    match iter.next() {
+        Some(Ok((k, v))) if k.starts_with(prefix.as_bytes()) => bincode::deserialize(&v).ok(),
+        _ => None,
+    }
+}
+// Saves `snapshot` and removes the pool's snapshots past their retention period
+pub fn save_price_snapshot(
+    db: &rocksdb::DB,
+    snapshot: PriceSnapshot,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+) {
+    debug!("Saving price snapshot {:?}", snapshot);
+    let k = price_history_key(base, quote, pool_idx, snapshot.time);
+    let cutoff = snapshot.time.saturating_sub(PRICE_SNAPSHOT_RETENTION);
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.put(k.as_bytes(), bincode::serialize(&snapshot).unwrap());
+    batch.delete_range(
+        price_history_key(base, quote, pool_idx, 0).as_bytes(),
+        price_history_key(base, quote, pool_idx, cutoff).as_bytes(),
+    );
+    db.write(batch).unwrap();
+}
@@ -0,0 +1,42 @@
+use clarity::Uint256;
+use log::debug;
+
+use super::super::ambient::governance::GovernanceEvent;
+
+pub const GOVERNANCE_LOG_PREFIX: &str = "governance-log_";
+fn governance_log_key(block: Uint256, log_index: Uint256) -> String {
+    format!("{}{}_{}", GOVERNANCE_LOG_PREFIX, block, log_index)
+}
+
+// Gets every recorded governance event from the database, sorted in the order they occurred on chain
+pub fn get_governance_log(db: &rocksdb::DB) -> Vec<GovernanceEvent> {
+    let prefix = GOVERNANCE_LOG_PREFIX.as_bytes();
+    let mut events = vec![];
+    let iter = db.prefix_iterator(prefix);
+    for entry in iter {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix) {
+                    break;
+                }
+                let event: GovernanceEvent = bincode::deserialize(&v).unwrap();
+                events.push(event);
+            }
+            Err(_) => break,
+        }
+    }
+    events.sort_by(|a, b| {
+        a.block_height
+            .cmp(&b.block_height)
+            .then(a.log_index.cmp(&b.log_index))
+    });
+    events
+}
+
+pub fn save_governance_event(db: &rocksdb::DB, event: GovernanceEvent) {
+    let k = governance_log_key(event.block_height, event.log_index);
+    debug!("Saving GovernanceEvent to key {}", k);
+    let v = bincode::serialize(&event).unwrap();
+
+    db.put(k.as_bytes(), v).unwrap();
+}
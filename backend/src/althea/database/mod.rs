@@ -2,8 +2,14 @@ use clarity::Uint256;
 use log::debug;
 
 pub mod curve;
+pub mod governance;
 pub mod pools;
 pub mod positions;
+pub mod prices;
+pub mod stats;
+pub mod status;
+pub mod swaps;
+pub mod templates;
 
 use super::InitPoolEvent;
 
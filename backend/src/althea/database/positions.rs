@@ -3,6 +3,8 @@ use clarity::Uint256;
 use log::debug;
 use log::error;
 use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use super::super::ambient::positions::{
     BurnAmbientEvent, BurnRangedEvent, MintAmbientEvent, MintRangedEvent,
@@ -364,7 +366,7 @@ pub enum Position {
     Ambient(AmbientPosition),
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RangedPosition {
     pub start_block: Uint256,
     pub user: Address,
@@ -448,6 +450,136 @@ pub fn get_active_user_pool_positions(
     positions
 }
 
+pub const ACTIVE_RANGED_PREFIX: &str = "active-ranged_";
+/// Set once the active ranged positions of databases indexed before they were stored have been built
+const ACTIVE_RANGED_BUILT_KEY: &[u8] = b"active_ranged_built";
+// The pool comes first so that a pool's positions can be read with a single prefix scan
+fn active_ranged_pool_prefix(base: Address, quote: Address, pool_idx: Uint256) -> String {
+    format!("{}{}_{}_{}_", ACTIVE_RANGED_PREFIX, base, quote, pool_idx)
+}
+fn active_ranged_key(user: Address, base: Address, quote: Address, pool_idx: Uint256) -> String {
+    format!(
+        "{}{}",
+        active_ranged_pool_prefix(base, quote, pool_idx),
+        user
+    )
+}
+
+/// Gets the active ranged positions of every user in a pool, as stored by `update_active_ranged_positions`
+pub fn get_pool_ranged_positions(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+) -> Vec<RangedPosition> {
+    let prefix = active_ranged_pool_prefix(base, quote, pool_idx);
+    let prefix = prefix.as_bytes();
+    let mut positions = vec![];
+    for entry in db.prefix_iterator(prefix) {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix) {
+                    break;
+                }
+                match bincode::deserialize::<Vec<RangedPosition>>(&v) {
+                    Ok(user_positions) => positions.extend(user_positions),
+                    Err(_) => error!("Invalid active ranged positions at key {:?}", k),
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    positions
+}
+
+/// Recomputes the active ranged positions of `user` in a pool from their MintRanged and BurnRanged events,
+/// called whenever new events are indexed for them
+pub fn update_active_ranged_positions(
+    db: &rocksdb::DB,
+    user: Address,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+) {
+    let mut mint_ranged = get_all_mint_ranged(
+        db,
+        Some(mint_ranged_user_pool_prefix(user, base, quote, pool_idx).as_bytes()),
+    );
+    mint_ranged.sort_by(|a, b| a.block_height.cmp(&b.block_height));
+    let mut burn_ranged = get_all_burn_ranged(
+        db,
+        Some(burn_ranged_user_pool_prefix(user, base, quote, pool_idx).as_bytes()),
+    );
+    burn_ranged.sort_by(|a, b| a.block_height.cmp(&b.block_height));
+    let positions = combine_and_filter_ranged_positions(mint_ranged, burn_ranged);
+    save_active_ranged_positions(db, user, base, quote, pool_idx, &positions);
+}
+
+fn save_active_ranged_positions(
+    db: &rocksdb::DB,
+    user: Address,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    positions: &[RangedPosition],
+) {
+    let k = active_ranged_key(user, base, quote, pool_idx);
+    if positions.is_empty() {
+        db.delete(k.as_bytes()).unwrap();
+    } else {
+        db.put(k.as_bytes(), bincode::serialize(positions).unwrap())
+            .unwrap();
+    }
+}
+
+/// Stores the active ranged positions of databases indexed before they were maintained by the indexer,
+/// this scans every ranged position event once
+pub fn build_active_ranged_positions(db: &rocksdb::DB) {
+    if db.get(ACTIVE_RANGED_BUILT_KEY).unwrap().is_some() {
+        return;
+    }
+    info!("Building active ranged positions");
+    let mut by_pool_user: HashMap<(Address, Address, Uint256, Address), Vec<RangedPosition>> =
+        HashMap::new();
+    for position in get_all_active_ranged_positions(db) {
+        by_pool_user
+            .entry((
+                position.base,
+                position.quote,
+                position.pool_idx,
+                position.user,
+            ))
+            .or_default()
+            .push(position);
+    }
+    for ((base, quote, pool_idx, user), positions) in by_pool_user {
+        save_active_ranged_positions(db, user, base, quote, pool_idx, &positions);
+    }
+    db.put(ACTIVE_RANGED_BUILT_KEY, [1u8]).unwrap();
+}
+
+/// Gets every active ranged position in every pool, across all users
+fn get_all_active_ranged_positions(db: &rocksdb::DB) -> Vec<RangedPosition> {
+    let mut mint_ranged = get_all_mint_ranged(db, None);
+    mint_ranged.sort_by(|a, b| a.block_height.cmp(&b.block_height));
+    let mut burn_ranged = get_all_burn_ranged(db, None);
+    burn_ranged.sort_by(|a, b| a.block_height.cmp(&b.block_height));
+
+    // Events are only combined with others from the same user
+    let mut by_user: HashMap<Address, (Vec<MintRangedEvent>, Vec<BurnRangedEvent>)> =
+        HashMap::new();
+    for mr in mint_ranged {
+        by_user.entry(mr.user).or_default().0.push(mr);
+    }
+    for br in burn_ranged {
+        by_user.entry(br.user).or_default().1.push(br);
+    }
+    by_user
+        .into_values()
+        .flat_map(|(mint, burn)| combine_and_filter_ranged_positions(mint, burn))
+        .collect()
+}
+
 // Combines together any corresponding mint_ranged entries, and filters them by any corresponding burn_ranged entries
 fn combine_and_filter_ranged_positions(
     mint_ranged: Vec<MintRangedEvent>,
@@ -0,0 +1,20 @@
+use log::debug;
+
+use super::super::ambient::pricing::TokenPrices;
+
+/// The USD price of every routable token, recomputed by the indexer after each pass
+pub const TOKEN_PRICES_KEY: &str = "token-prices";
+pub fn get_token_prices(db: &rocksdb::DB) -> Option<TokenPrices> {
+    let v = db.get(TOKEN_PRICES_KEY.as_bytes()).unwrap();
+    #[allow(clippy::question_mark)]
+    if v.is_none() {
+        debug!("No token prices");
+        return None;
+    }
+    bincode::deserialize(&v.unwrap()).ok()
+}
+pub fn save_token_prices(db: &rocksdb::DB, prices: &TokenPrices) {
+    debug!("Saving {} token prices", prices.prices.len());
+    let v = bincode::serialize(prices).unwrap();
+    db.put(TOKEN_PRICES_KEY.as_bytes(), v).unwrap();
+}
@@ -0,0 +1,156 @@
+use clarity::Address;
+use clarity::Uint256;
+use log::debug;
+use log::error;
+
+use super::super::ambient::stats::{DexStats, VolumeBucket};
+
+/// Protocol-wide dashboard statistics, recomputed by the indexer after each pass
+pub const DEX_STATS_KEY: &str = "dex-stats";
+pub fn get_dex_stats(db: &rocksdb::DB) -> Option<DexStats> {
+    let v = db.get(DEX_STATS_KEY.as_bytes()).unwrap();
+    #[allow(clippy::question_mark)]
+    if v.is_none() {
+        debug!("No dex stats");
+        return None;
+    }
+    bincode::deserialize(&v.unwrap()).ok()
+}
+pub fn save_dex_stats(db: &rocksdb::DB, stats: &DexStats) {
+    debug!("Saving dex stats for {} pools", stats.pool_count);
+    let v = bincode::serialize(stats).unwrap();
+    db.put(DEX_STATS_KEY.as_bytes(), v).unwrap();
+}
+
+/// Hourly swap volume per pool, keyed by the start of the hour
+pub const VOLUME_BUCKET_PREFIX: &str = "dex-volume_";
+fn volume_pool_prefix(base: Address, quote: Address, pool_idx: Uint256) -> String {
+    format!("{}{}_{}_{}_", VOLUME_BUCKET_PREFIX, base, quote, pool_idx)
+}
+// Hours are zero padded so that buckets sort by time
+fn volume_bucket_key(base: Address, quote: Address, pool_idx: Uint256, hour: u64) -> String {
+    format!("{}{:020}", volume_pool_prefix(base, quote, pool_idx), hour)
+}
+pub fn get_volume_bucket(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    hour: u64,
+) -> Option<VolumeBucket> {
+    let v = db
+        .get(volume_bucket_key(base, quote, pool_idx, hour).as_bytes())
+        .unwrap();
+    #[allow(clippy::question_mark)]
+    if v.is_none() {
+        return None;
+    }
+    bincode::deserialize(&v.unwrap()).ok()
+}
+pub fn save_volume_bucket(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    hour: u64,
+    bucket: &VolumeBucket,
+) {
+    let k = volume_bucket_key(base, quote, pool_idx, hour);
+    debug!("Saving volume bucket {:?} to key {}", bucket, k);
+    let v = bincode::serialize(bucket).unwrap();
+    db.put(k.as_bytes(), v).unwrap();
+}
+// Gets the (hour, bucket) pairs of a pool at or after `since_hour`, oldest first
+pub fn get_volume_buckets(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    since_hour: u64,
+) -> Vec<(u64, VolumeBucket)> {
+    let prefix = volume_pool_prefix(base, quote, pool_idx);
+    let start = volume_bucket_key(base, quote, pool_idx, since_hour);
+    let mut buckets = vec![];
+    let iter = db.iterator(rocksdb::IteratorMode::From(
+        start.as_bytes(),
+        rocksdb::Direction::Forward,
+    ));
+    for entry in iter {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                let hour = std::str::from_utf8(&k[prefix.len()..])
+                    .ok()
+                    .and_then(|h| h.parse().ok());
+                match (hour, bincode::deserialize::<VolumeBucket>(&v)) {
+                    (Some(hour), Ok(bucket)) => buckets.push((hour, bucket)),
+                    _ => error!("Invalid volume bucket at key {:?}", k),
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    buckets
+}
+// Removes the buckets of a pool from before `before_hour`
+pub fn remove_volume_buckets(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    before_hour: u64,
+) {
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.delete_range(
+        volume_bucket_key(base, quote, pool_idx, 0).as_bytes(),
+        volume_bucket_key(base, quote, pool_idx, before_hour).as_bytes(),
+    );
+    if let Err(e) = db.write(batch) {
+        error!("Failed to remove expired volume buckets: {}", e);
+    }
+}
+
+/// Every address that has ever provided liquidity or swapped, along with a running count of each so that
+/// the number of unique participants does not require a scan
+pub const LIQUIDITY_PROVIDER_PREFIX: &str = "dex-lp_";
+pub const LIQUIDITY_PROVIDER_COUNT_KEY: &str = "dex-lp-count";
+pub const TRADER_PREFIX: &str = "dex-trader_";
+pub const TRADER_COUNT_KEY: &str = "dex-trader-count";
+
+// Adds `user` to the set at `prefix`, incrementing the count at `count_key` if it was not already present
+fn insert_participant(db: &rocksdb::DB, prefix: &str, count_key: &str, user: Address) {
+    let k = format!("{}{}", prefix, user);
+    if db.get(k.as_bytes()).unwrap().is_some() {
+        return;
+    }
+    let count = get_count(db, count_key) + 1;
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.put(k.as_bytes(), b"");
+    batch.put(count_key.as_bytes(), count.to_be_bytes());
+    db.write(batch).unwrap();
+}
+fn get_count(db: &rocksdb::DB, count_key: &str) -> u64 {
+    match db.get(count_key.as_bytes()).unwrap() {
+        Some(v) => u64::from_be_bytes(v.try_into().unwrap_or_default()),
+        None => 0,
+    }
+}
+pub fn save_liquidity_provider(db: &rocksdb::DB, user: Address) {
+    insert_participant(
+        db,
+        LIQUIDITY_PROVIDER_PREFIX,
+        LIQUIDITY_PROVIDER_COUNT_KEY,
+        user,
+    )
+}
+pub fn get_liquidity_provider_count(db: &rocksdb::DB) -> u64 {
+    get_count(db, LIQUIDITY_PROVIDER_COUNT_KEY)
+}
+pub fn save_trader(db: &rocksdb::DB, user: Address) {
+    insert_participant(db, TRADER_PREFIX, TRADER_COUNT_KEY, user)
+}
+pub fn get_trader_count(db: &rocksdb::DB) -> u64 {
+    get_count(db, TRADER_COUNT_KEY)
+}
@@ -0,0 +1,21 @@
+use log::debug;
+
+use super::super::ambient::status::DexStatus;
+
+/// The protocol-wide CrocSwapDex status, reconstructed from the governance log
+pub const DEX_STATUS_KEY: &str = "dex-status";
+pub fn get_dex_status(db: &rocksdb::DB) -> Option<DexStatus> {
+    let v = db.get(DEX_STATUS_KEY.as_bytes()).unwrap();
+    #[allow(clippy::question_mark)]
+    if v.is_none() {
+        debug!("No dex status");
+        return None;
+    }
+    let decoded: DexStatus = bincode::deserialize(&v.unwrap()).unwrap();
+    Some(decoded)
+}
+pub fn save_dex_status(db: &rocksdb::DB, status: &DexStatus) {
+    debug!("Saving dex status {:?}", status);
+    let v = bincode::serialize(status).unwrap();
+    db.put(DEX_STATUS_KEY.as_bytes(), v).unwrap();
+}
@@ -0,0 +1,109 @@
+use clarity::Address;
+use clarity::Uint256;
+use log::debug;
+
+use super::super::ambient::swaps::SwapEvent;
+
+pub const SWAP_PREFIX: &str = "swap_";
+fn swap_pool_prefix(base: Address, quote: Address, pool_idx: Uint256) -> String {
+    format!("{}{}_{}_{}_", SWAP_PREFIX, base, quote, pool_idx)
+}
+// Blocks and log indices are zero padded so that swaps sort in chain order
+fn swap_block_prefix(base: Address, quote: Address, pool_idx: Uint256, block: Uint256) -> String {
+    format!(
+        "{}{:0>20}_",
+        swap_pool_prefix(base, quote, pool_idx),
+        block.to_string()
+    )
+}
+fn swap_key(
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    block: Uint256,
+    log_index: Uint256,
+) -> String {
+    format!(
+        "{}{:0>10}",
+        swap_block_prefix(base, quote, pool_idx, block),
+        log_index.to_string()
+    )
+}
+
+// Gets all known Swap events from the database, optionally restricted to a single pool
+pub fn get_all_swaps(db: &rocksdb::DB, prefix: Option<&[u8]>) -> Vec<SwapEvent> {
+    let prefix = prefix.unwrap_or_else(|| SWAP_PREFIX.as_bytes());
+    let mut events = vec![];
+    let iter = db.prefix_iterator(prefix);
+    for entry in iter {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix) {
+                    break;
+                }
+                let event: SwapEvent = bincode::deserialize(&v).unwrap();
+                events.push(event);
+            }
+            Err(_) => break,
+        }
+    }
+    events
+}
+
+// Gets the Swap events of a pool after `after_block`, sorted by block height
+pub fn get_pool_swaps_after(
+    db: &rocksdb::DB,
+    base: Address,
+    quote: Address,
+    pool_idx: Uint256,
+    after_block: Uint256,
+) -> Vec<SwapEvent> {
+    let prefix = swap_pool_prefix(base, quote, pool_idx);
+    let start = swap_block_prefix(base, quote, pool_idx, after_block + 1u8.into());
+    let mut swaps = vec![];
+    let iter = db.iterator(rocksdb::IteratorMode::From(
+        start.as_bytes(),
+        rocksdb::Direction::Forward,
+    ));
+    for entry in iter {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                let event: SwapEvent = bincode::deserialize(&v).unwrap();
+                swaps.push(event);
+            }
+            Err(_) => break,
+        }
+    }
+    swaps
+}
+
+// Returns true if `swap` has already been saved, the boundary block of each search range is searched twice
+pub fn swap_exists(db: &rocksdb::DB, swap: &SwapEvent) -> bool {
+    let (base, quote) = swap.base_quote();
+    let k = swap_key(
+        base,
+        quote,
+        swap.pool_idx,
+        swap.block_height,
+        swap.log_index,
+    );
+    db.get(k.as_bytes()).unwrap().is_some()
+}
+
+pub fn save_swap(db: &rocksdb::DB, swap: SwapEvent) {
+    let (base, quote) = swap.base_quote();
+    let k = swap_key(
+        base,
+        quote,
+        swap.pool_idx,
+        swap.block_height,
+        swap.log_index,
+    );
+    debug!("Saving SwapEvent to key {}", k);
+    let v = bincode::serialize(&swap).unwrap();
+
+    db.put(k.as_bytes(), v).unwrap();
+}
@@ -0,0 +1,48 @@
+use clarity::Uint256;
+use log::debug;
+
+use super::super::ambient::templates::PoolTemplate;
+
+pub const POOL_TEMPLATE_PREFIX: &str = "pool-template_";
+fn pool_template_key(pool_idx: Uint256) -> String {
+    format!("{}{}", POOL_TEMPLATE_PREFIX, pool_idx)
+}
+
+// Gets all known pool templates from the database, sorted by poolIdx
+pub fn get_pool_templates(db: &rocksdb::DB) -> Vec<PoolTemplate> {
+    let prefix = POOL_TEMPLATE_PREFIX.as_bytes();
+    let mut templates = vec![];
+    let iter = db.prefix_iterator(prefix);
+    for entry in iter {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix) {
+                    break;
+                }
+                let template: PoolTemplate = bincode::deserialize(&v).unwrap();
+                templates.push(template);
+            }
+            Err(_) => break,
+        }
+    }
+    templates.sort_by(|a, b| a.pool_idx.cmp(&b.pool_idx));
+    templates
+}
+
+// Gets a single pool template from the database by its poolIdx, returns none if it does not exist
+pub fn get_pool_template(db: &rocksdb::DB, pool_idx: Uint256) -> Option<PoolTemplate> {
+    let v = db.get(pool_template_key(pool_idx).as_bytes()).unwrap();
+    #[allow(clippy::question_mark)]
+    if v.is_none() {
+        return None;
+    }
+    Some(bincode::deserialize(&v.unwrap()).unwrap())
+}
+
+pub fn save_pool_template(db: &rocksdb::DB, template: PoolTemplate) {
+    let k = pool_template_key(template.pool_idx);
+    debug!("Saving pool template to key {}", k);
+    let v = bincode::serialize(&template).unwrap();
+
+    db.put(k.as_bytes(), v).unwrap();
+}
@@ -1,5 +1,14 @@
 use bincode;
+use cosmos_sdk_proto_althea::cosmos::base::query::v1beta1::PageRequest;
+use cosmos_sdk_proto_althea::cosmos::distribution::v1beta1::{
+    query_client::QueryClient as DistributionQueryClient, QueryDelegatorWithdrawAddressRequest,
+};
+use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::{
+    query_client::QueryClient as StakingQueryClient, QueryDelegatorUnbondingDelegationsRequest,
+    QueryRedelegationsRequest,
+};
 use deep_space::{Address as CosmosAddress, Contact};
+use futures::stream::{self, StreamExt};
 use log::{error, info};
 use rocksdb::DB;
 use serde::{Deserialize, Serialize};
@@ -7,8 +16,9 @@ use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::vec::Vec;
 
-use crate::althea::abi_util::format_u128_to_decimal_18;
-use crate::althea::CACHE_DURATION;
+use crate::althea::amount::Amount;
+use crate::althea::error::AltheaError;
+use crate::althea::{format_timestamp, get_cached, Cached, CACHE_DURATION};
 use tokio;
 
 const DELEGATIONS_KEY_PREFIX: &str = "delegations_";
@@ -16,8 +26,40 @@ const DELEGATIONS_KEY_PREFIX: &str = "delegations_";
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DelegatorResponse {
     pub delegations: Vec<DelegationResponse>,
-    pub unbonding_delegations: Option<Vec<String>>,
+    pub unbonding_delegations: Vec<UnbondingEntry>,
+    pub redelegations: Vec<RedelegationEntry>,
     pub rewards: RewardsResponse,
+    /// The address rewards are paid to when withdrawn, the delegator itself unless it has set another
+    pub withdraw_address: String,
+    /// The unix time the response was queried, which also applies to addresses without any delegations
+    pub last_updated: u64,
+}
+
+impl Cached for DelegatorResponse {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+/// A single pending unbonding, the balance becomes liquid at completion_time
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnbondingEntry {
+    pub validator_address: String,
+    pub creation_height: i64,
+    pub completion_time: Option<String>,
+    pub initial_balance: Balance,
+    pub balance: Balance,
+}
+
+/// A single pending redelegation, the balance may not be redelegated again until completion_time
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RedelegationEntry {
+    pub validator_src_address: String,
+    pub validator_dst_address: String,
+    pub creation_height: i64,
+    pub completion_time: Option<String>,
+    pub initial_balance: Balance,
+    pub balance: Balance,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -30,14 +72,39 @@ pub struct DelegationResponse {
 pub struct DelegationInfo {
     pub delegator_address: String,
     pub validator_address: String,
-    pub shares: String,
+    /// Delegation shares are a Dec, reported with 18 decimal places
+    pub shares: Amount,
     pub last_updated: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Balance {
     pub denom: String,
-    pub amount: String,
+    /// The amount in the denom's base units, rewards may include a fraction of a base unit
+    pub amount: Amount,
+    /// The amount in display units according to the denom's exponent, e.g. althea rather than aalthea
+    pub display_amount: Amount,
+}
+
+impl Balance {
+    pub fn new(denom: impl Into<String>, amount: Amount) -> Self {
+        let denom = denom.into();
+        Balance {
+            display_amount: amount.to_display(&denom),
+            amount,
+            denom,
+        }
+    }
+
+    /// Builds a balance from an integer amount of base units as reported by the chain
+    pub fn from_base_units(denom: impl Into<String>, amount: &str) -> Result<Self, AltheaError> {
+        Ok(Balance::new(denom, Amount::parse(amount)?))
+    }
+
+    /// Builds a balance from a DecCoin, whose amount is a Dec of base units
+    pub fn from_dec_coin(denom: impl Into<String>, amount: &str) -> Result<Self, AltheaError> {
+        Ok(Balance::new(denom, Amount::from_sdk_dec(amount)?))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -57,25 +124,7 @@ fn get_cached_delegations(
     delegator: &CosmosAddress,
 ) -> Option<DelegatorResponse> {
     let key = format!("{}{}", DELEGATIONS_KEY_PREFIX, delegator.to_string());
-    match db.get(key.as_bytes()).unwrap() {
-        Some(data) => {
-            let delegations: DelegatorResponse = bincode::deserialize(&data).unwrap();
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-
-            // Check if any delegations exist and if cache is still valid
-            if !delegations.delegations.is_empty()
-                && now - delegations.delegations[0].delegation.last_updated < CACHE_DURATION
-            {
-                Some(delegations)
-            } else {
-                None
-            }
-        }
-        None => None,
-    }
+    get_cached(db, key.as_bytes(), CACHE_DURATION)
 }
 
 fn cache_delegations(db: &rocksdb::DB, delegator: &CosmosAddress, response: &DelegatorResponse) {
@@ -84,20 +133,35 @@ fn cache_delegations(db: &rocksdb::DB, delegator: &CosmosAddress, response: &Del
     db.put(key.as_bytes(), encoded).unwrap();
 }
 
+/// Returns the delegations of `delegator_address`, from the cache if it is fresh. Each call counts as an
+/// access, which keeps the address in the cache refreshed by start_delegation_cache_refresh_task
 pub async fn fetch_delegations(
     db: &rocksdb::DB,
     contact: &Contact,
     delegator_address: CosmosAddress,
 ) -> Result<DelegatorResponse, Box<dyn std::error::Error>> {
+    record_delegation_access(db, &delegator_address);
     // Check cache first
     if let Some(cached) = get_cached_delegations(db, &delegator_address) {
         return Ok(cached);
     }
+    refresh_delegations(db, contact, delegator_address).await
+}
 
+// Queries the delegations of `delegator_address` and caches them
+async fn refresh_delegations(
+    db: &rocksdb::DB,
+    contact: &Contact,
+    delegator_address: CosmosAddress,
+) -> Result<DelegatorResponse, Box<dyn std::error::Error>> {
     let validators = contact
         .query_delegator_validators(delegator_address)
         .await?;
 
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
     let mut delegation_responses = Vec::new();
     for validator_addr in &validators {
         let validator_address = CosmosAddress::from_bech32(validator_addr.to_string())?;
@@ -111,15 +175,12 @@ pub async fn fetch_delegations(
                     delegation: DelegationInfo {
                         delegator_address: delegator_address.to_string(),
                         validator_address: validator_addr.clone(),
-                        shares: format!("{}.000000000000000000", del_response.shares),
-                        last_updated: SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs(),
+                        shares: Amount::from_sdk_dec(&del_response.shares)?,
+                        last_updated: now,
                     },
-                    balance: Balance {
-                        denom: "aalthea".to_string(),
-                        amount: delegation.balance.map(|b| b.amount).unwrap_or_default(),
+                    balance: match delegation.balance {
+                        Some(b) => Balance::from_base_units(b.denom, &b.amount)?,
+                        None => Balance::new("aalthea", Amount::from(0u128)),
                     },
                 });
             }
@@ -131,76 +192,306 @@ pub async fn fetch_delegations(
         .query_all_delegation_rewards(delegator_address)
         .await?;
 
-    let rewards = validators
-        .iter()
-        .map(|validator_addr| ValidatorReward {
+    // Every reward coin is reported, validators without pending rewards have an empty reward
+    let mut rewards = Vec::new();
+    for validator_addr in validators.iter() {
+        let reward = match rewards_response
+            .rewards
+            .iter()
+            .find(|r| r.validator_address == *validator_addr)
+        {
+            Some(r) => r
+                .reward
+                .iter()
+                .map(|c| Balance::from_dec_coin(c.denom.clone(), &c.amount))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![],
+        };
+        rewards.push(ValidatorReward {
             validator_address: validator_addr.clone(),
-            reward: vec![Balance {
-                denom: "aalthea".to_string(),
-                amount: rewards_response
-                    .rewards
-                    .iter()
-                    .find(|r| r.validator_address == *validator_addr)
-                    .and_then(|r| r.reward.first())
-                    .map(|r| {
-                        let amount = r.amount.parse::<u128>().unwrap_or_default();
-                        format_u128_to_decimal_18(amount, 100_000_000_000_000)
-                    })
-                    .unwrap_or_else(|| "0.000000000000000000".to_string()),
-            }],
-        })
-        .collect();
-
-    let total = vec![Balance {
-        denom: "aalthea".to_string(),
-        amount: rewards_response
-            .total
-            .first()
-            .map(|t| {
-                let amount = t.amount.parse::<u128>().unwrap_or_default();
-                format_u128_to_decimal_18(amount, 100_000_000_000_000)
-            })
-            .unwrap_or_else(|| "0.000000000000000000".to_string()),
-    }];
+            reward,
+        });
+    }
+    let total = rewards_response
+        .total
+        .iter()
+        .map(|c| Balance::from_dec_coin(c.denom.clone(), &c.amount))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let unbonding_delegations = fetch_unbonding_delegations(contact, delegator_address).await?;
+    let redelegations = fetch_redelegations(contact, delegator_address).await?;
+    let withdraw_address = fetch_withdraw_address(contact, delegator_address).await?;
 
     // Cache the response before returning
     let response = DelegatorResponse {
         delegations: delegation_responses,
-        unbonding_delegations: None,
+        unbonding_delegations,
+        redelegations,
         rewards: RewardsResponse { rewards, total },
+        withdraw_address,
+        last_updated: now,
     };
 
     cache_delegations(db, &delegator_address, &response);
     Ok(response)
 }
 
+// Requests the page starting at `key`, which is empty for the first page
+fn page_request(key: Vec<u8>) -> Option<PageRequest> {
+    Some(PageRequest {
+        key,
+        offset: 0,
+        limit: 1000,
+        count_total: false,
+        reverse: false,
+    })
+}
+
+/// Queries the distribution module for the address that `delegator_address`'s rewards are paid to
+pub async fn fetch_withdraw_address(
+    contact: &Contact,
+    delegator_address: CosmosAddress,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut client = DistributionQueryClient::connect(contact.get_url()).await?;
+    let response = client
+        .delegator_withdraw_address(QueryDelegatorWithdrawAddressRequest {
+            delegator_address: delegator_address.to_string(),
+        })
+        .await?
+        .into_inner();
+    Ok(response.withdraw_address)
+}
+
+/// Queries the staking module for every pending unbonding of `delegator_address`, one entry per unbonding
+async fn fetch_unbonding_delegations(
+    contact: &Contact,
+    delegator_address: CosmosAddress,
+) -> Result<Vec<UnbondingEntry>, Box<dyn std::error::Error>> {
+    let mut client = StakingQueryClient::connect(contact.get_url()).await?;
+    let mut unbondings = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let response = client
+            .delegator_unbonding_delegations(QueryDelegatorUnbondingDelegationsRequest {
+                delegator_addr: delegator_address.to_string(),
+                pagination: page_request(next_key),
+            })
+            .await?
+            .into_inner();
+        unbondings.extend(response.unbonding_responses);
+        match response.pagination {
+            Some(p) if !p.next_key.is_empty() => next_key = p.next_key,
+            _ => break,
+        }
+    }
+
+    let mut entries = Vec::new();
+    for unbonding in unbondings {
+        for entry in unbonding.entries {
+            entries.push(UnbondingEntry {
+                validator_address: unbonding.validator_address.clone(),
+                creation_height: entry.creation_height,
+                completion_time: entry.completion_time.and_then(format_timestamp),
+                initial_balance: Balance::from_base_units("aalthea", &entry.initial_balance)?,
+                balance: Balance::from_base_units("aalthea", &entry.balance)?,
+            });
+        }
+    }
+    entries.sort_by(|a, b| a.completion_time.cmp(&b.completion_time));
+    Ok(entries)
+}
+
+/// Queries the staking module for every pending redelegation of `delegator_address`, one entry per redelegation
+async fn fetch_redelegations(
+    contact: &Contact,
+    delegator_address: CosmosAddress,
+) -> Result<Vec<RedelegationEntry>, Box<dyn std::error::Error>> {
+    let mut client = StakingQueryClient::connect(contact.get_url()).await?;
+    let mut redelegations = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let response = client
+            .redelegations(QueryRedelegationsRequest {
+                delegator_addr: delegator_address.to_string(),
+                src_validator_addr: String::new(),
+                dst_validator_addr: String::new(),
+                pagination: page_request(next_key),
+            })
+            .await?
+            .into_inner();
+        redelegations.extend(response.redelegation_responses);
+        match response.pagination {
+            Some(p) if !p.next_key.is_empty() => next_key = p.next_key,
+            _ => break,
+        }
+    }
+
+    let mut entries = Vec::new();
+    for redelegation in redelegations {
+        let Some(info) = redelegation.redelegation else {
+            continue;
+        };
+        // The response pairs each redelegation entry with its current balance
+        for entry in redelegation.entries {
+            let Some(details) = entry.redelegation_entry else {
+                continue;
+            };
+            entries.push(RedelegationEntry {
+                validator_src_address: info.validator_src_address.clone(),
+                validator_dst_address: info.validator_dst_address.clone(),
+                creation_height: details.creation_height,
+                completion_time: details.completion_time.and_then(format_timestamp),
+                initial_balance: Balance::from_base_units("aalthea", &details.initial_balance)?,
+                balance: Balance::from_base_units("aalthea", &entry.balance)?,
+            });
+        }
+    }
+    entries.sort_by(|a, b| a.completion_time.cmp(&b.completion_time));
+    Ok(entries)
+}
+
+/// The time each cached address was last requested, kept apart from DELEGATIONS_KEY_PREFIX so that
+/// a scan of the cache does not also return these
+const DELEGATION_ACCESS_KEY_PREFIX: &str = "delegation-access_";
+const DELEGATION_CACHE_METRICS_KEY: &[u8] = b"delegation_cache_metrics";
+/// Cached addresses that have not been requested for this long are evicted instead of refreshed
+pub const DELEGATION_EVICTION_AGE: u64 = 86400;
+/// The maximum number of addresses refreshed at once
+pub const DELEGATION_REFRESH_CONCURRENCY: usize = 8;
+
+/// Counters describing the delegation cache, updated after each refresh cycle
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DelegationCacheMetrics {
+    /// The number of addresses in the cache after the last cycle
+    pub cached_addresses: u64,
+    /// The number of addresses evicted in the last cycle
+    pub evicted_addresses: u64,
+    pub total_evicted_addresses: u64,
+    /// The number of addresses that failed to refresh in the last cycle
+    pub refresh_failures: u64,
+    pub total_refresh_failures: u64,
+    pub last_refresh: u64,
+}
+
+fn record_delegation_access(db: &rocksdb::DB, delegator: &CosmosAddress) {
+    let key = format!("{}{}", DELEGATION_ACCESS_KEY_PREFIX, delegator);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    db.put(key.as_bytes(), now.to_be_bytes()).unwrap();
+}
+
+fn get_delegation_access(db: &rocksdb::DB, delegator: &str) -> Option<u64> {
+    let key = format!("{}{}", DELEGATION_ACCESS_KEY_PREFIX, delegator);
+    let v = db.get(key.as_bytes()).unwrap()?;
+    Some(u64::from_be_bytes(v.try_into().ok()?))
+}
+
+// Returns the addresses of every cached delegator
+fn get_cached_delegators(db: &rocksdb::DB) -> Vec<String> {
+    let prefix = DELEGATIONS_KEY_PREFIX.as_bytes();
+    let mut delegators = vec![];
+    for entry in db.prefix_iterator(prefix) {
+        match entry {
+            Ok((k, _)) => {
+                if !k.starts_with(prefix) {
+                    break;
+                }
+                delegators.push(String::from_utf8_lossy(&k[prefix.len()..]).to_string());
+            }
+            Err(_) => break,
+        }
+    }
+    delegators
+}
+
+fn evict_delegator(db: &rocksdb::DB, delegator: &str) {
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.delete(format!("{}{}", DELEGATIONS_KEY_PREFIX, delegator).as_bytes());
+    batch.delete(format!("{}{}", DELEGATION_ACCESS_KEY_PREFIX, delegator).as_bytes());
+    if let Err(e) = db.write(batch) {
+        error!("Failed to evict delegations of {}: {}", delegator, e);
+    }
+}
+
+pub fn get_delegation_cache_metrics(db: &rocksdb::DB) -> Option<DelegationCacheMetrics> {
+    match db.get(DELEGATION_CACHE_METRICS_KEY).unwrap() {
+        Some(data) => bincode::deserialize(&data).ok(),
+        None => None,
+    }
+}
+
+fn save_delegation_cache_metrics(db: &rocksdb::DB, metrics: &DelegationCacheMetrics) {
+    db.put(
+        DELEGATION_CACHE_METRICS_KEY,
+        bincode::serialize(metrics).unwrap(),
+    )
+    .unwrap();
+}
+
+/// Every CACHE_DURATION, evicts cached addresses that have not been requested within
+/// DELEGATION_EVICTION_AGE and refreshes the rest, DELEGATION_REFRESH_CONCURRENCY at a time
 pub fn start_delegation_cache_refresh_task(db: Arc<DB>, contact: Contact) {
     tokio::spawn(async move {
         loop {
             tokio::time::sleep(tokio::time::Duration::from_secs(CACHE_DURATION)).await;
 
-            let iter = db.iterator(rocksdb::IteratorMode::Start);
-            for item in iter {
-                if let Ok((key_bytes, _)) = item {
-                    let key_str = String::from_utf8_lossy(&key_bytes);
-                    if key_str.starts_with(DELEGATIONS_KEY_PREFIX) {
-                        let delegator_addr = key_str.trim_start_matches(DELEGATIONS_KEY_PREFIX);
-                        if let Ok(cosmos_addr) =
-                            CosmosAddress::from_bech32(delegator_addr.to_string())
-                        {
-                            match fetch_delegations(&db, &contact, cosmos_addr).await {
-                                Ok(_) => {
-                                    info!("Refreshed delegations cache for {}", delegator_addr)
-                                }
-                                Err(e) => error!(
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let mut active = vec![];
+            let mut evicted = 0;
+            for delegator in get_cached_delegators(&db) {
+                // Addresses cached before access times were recorded have none, and are evicted
+                let accessed = get_delegation_access(&db, &delegator).unwrap_or_default();
+                match CosmosAddress::from_bech32(delegator.clone()) {
+                    Ok(address) if now.saturating_sub(accessed) < DELEGATION_EVICTION_AGE => {
+                        active.push(address)
+                    }
+                    _ => {
+                        evict_delegator(&db, &delegator);
+                        evicted += 1;
+                    }
+                }
+            }
+
+            let results: Vec<bool> = stream::iter(active.clone())
+                .map(|address| {
+                    let (db, contact) = (db.clone(), contact.clone());
+                    async move {
+                        match refresh_delegations(&db, &contact, address).await {
+                            Ok(_) => true,
+                            Err(e) => {
+                                error!(
                                     "Failed to refresh delegations cache for {}: {}",
-                                    delegator_addr, e
-                                ),
+                                    address, e
+                                );
+                                false
                             }
                         }
                     }
-                }
-            }
+                })
+                .buffer_unordered(DELEGATION_REFRESH_CONCURRENCY)
+                .collect()
+                .await;
+            let failures = results.iter().filter(|ok| !**ok).count() as u64;
+
+            let previous = get_delegation_cache_metrics(&db).unwrap_or_default();
+            let metrics = DelegationCacheMetrics {
+                cached_addresses: active.len() as u64,
+                evicted_addresses: evicted,
+                total_evicted_addresses: previous.total_evicted_addresses + evicted,
+                refresh_failures: failures,
+                total_refresh_failures: previous.total_refresh_failures + failures,
+                last_refresh: now,
+            };
+            info!(
+                "Refreshed delegations of {} addresses with {} failures, evicted {}",
+                metrics.cached_addresses, failures, evicted
+            );
+            save_delegation_cache_metrics(&db, &metrics);
         }
     });
 }
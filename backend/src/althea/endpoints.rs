@@ -1,13 +1,28 @@
-use super::delegations::fetch_delegations;
+use super::delegations::{fetch_delegations, get_delegation_cache_metrics};
 use crate::althea::{
+    account::{fetch_account, AccountAddress},
+    ambient::{
+        governance::GovernanceEvent,
+        pools::InitPoolEvent,
+        reserves::{get_pool_reserves, PoolReserves},
+        status::current_dex_status,
+    },
+    balances::fetch_balances,
     database::{
+        governance::get_governance_log,
         pools::{get_init_pool, get_init_pools},
         positions::{
             get_active_user_pool_positions, get_active_user_positions, get_all_burn_ambient,
             get_all_burn_ranged, get_all_mint_ambient, get_all_mint_ranged,
         },
+        prices::get_token_prices,
+        stats::get_dex_stats,
+        templates::get_pool_templates,
     },
-    ALTHEA_MAINNET_EVM_CHAIN_ID,
+    get_althea_web3,
+    token_mappings::get_token_registry,
+    transactions::{fetch_account_txs, DEFAULT_TXS_LIMIT, MAX_TXS_DEPTH, MAX_TXS_LIMIT},
+    ALTHEA_MAINNET_EVM_CHAIN_ID, TIMEOUT,
 };
 use actix_web::{
     get, post,
@@ -22,7 +37,8 @@ use rocksdb::DB;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use super::database::positions::Position::{Ambient, Ranged};
+use super::ambient::apr::{estimate_ambient_apr, estimate_ranged_apr};
+use super::database::positions::Position::{self, Ambient, Ranged};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct PoolRequest {
@@ -31,6 +47,15 @@ pub struct PoolRequest {
     pub pool_idx: Uint256,
 }
 
+/// A pool along with its current reserves
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PoolInfo {
+    #[serde(flatten)]
+    pub pool: InitPoolEvent,
+    /// None until the indexer has queried the pool's curve
+    pub reserves: Option<PoolReserves>,
+}
+
 /// Retrieves a pool by its base, quote, and pool index.
 ///
 /// # Query
@@ -43,14 +68,23 @@ pub struct PoolRequest {
 ///
 /// # Response
 ///
-/// The response body will be a JSON array of `PoolInitEvent` objects representing the moment of creation of the pool
+/// The response body will be a `PoolInfo` object: the `InitPoolEvent` representing the moment of creation
+/// of the pool, along with its `reserves`. The reserves include the current price and tick, the ambient
+/// and in range concentrated liquidity, the virtual reserves of that active liquidity, the base and quote
+/// tokens held by ambient liquidity and indexed concentrated positions, and the pool's TVL in base
+/// tokens and in USD
 #[post("/init_pool")]
 pub async fn query_pool(req: Json<PoolRequest>, db: web::Data<Arc<DB>>) -> impl Responder {
     let req = req.into_inner();
     info!("Querying pool {:?}", req);
     let pool = get_init_pool(&db, req.base, req.quote, req.pool_idx);
     match pool {
-        Some(pool) => HttpResponse::Ok().json(pool),
+        Some(pool) => {
+            let prices = get_token_prices(&db);
+            let reserves =
+                get_pool_reserves(&db, pool.base, pool.quote, pool.pool_idx, prices.as_ref());
+            HttpResponse::Ok().json(PoolInfo { pool, reserves })
+        }
         None => HttpResponse::NotFound().body("No pool found for base quote poolIdx triple"),
     }
 }
@@ -63,15 +97,132 @@ pub async fn query_pool(req: Json<PoolRequest>, db: web::Data<Arc<DB>>) -> impl
 ///
 /// # Response
 ///
-/// The response body will be a JSON array of `InitPoolEvent` objects representing the moment of creation of the pools
+/// The response body will be a JSON array of `PoolInfo` objects, each an `InitPoolEvent` representing
+/// the moment of creation of the pool along with its current `reserves`
 #[get("/init_pools")]
 pub async fn query_all_init_pools(db: web::Data<Arc<DB>>) -> impl Responder {
     info!("Querying all InitPools");
     let pools = get_init_pools(&db);
     if pools.is_empty() {
-        HttpResponse::NotFound().body("No pools found, try again later")
+        return HttpResponse::NotFound().body("No pools found, try again later");
+    }
+    let prices = get_token_prices(&db);
+    let pools: Vec<PoolInfo> = pools
+        .into_iter()
+        .map(|pool| PoolInfo {
+            reserves: get_pool_reserves(&db, pool.base, pool.quote, pool.pool_idx, prices.as_ref()),
+            pool,
+        })
+        .collect();
+    HttpResponse::Ok().json(pools)
+}
+
+/// Retrieves all known pool templates, as announced by SetPoolTemplate and DisablePoolTemplate events
+///
+/// # Query
+///
+/// A simple HTTP GET request
+///
+/// # Response
+///
+/// The response body will be a JSON array of `PoolTemplate` objects, each containing the template's
+/// `fee_rate` (in units of 0.0001%), `tick_size`, `jit_thresh`, `knockout` bits, `oracle_flags`
+/// and whether new pools can no longer be created with it (`disabled`)
+#[get("/pool_templates")]
+pub async fn query_pool_templates(db: web::Data<Arc<DB>>) -> impl Responder {
+    info!("Querying all pool templates");
+    let templates = get_pool_templates(&db);
+    if templates.is_empty() {
+        HttpResponse::NotFound().body("No pool templates found, try again later")
     } else {
-        HttpResponse::Ok().json(pools)
+        HttpResponse::Ok().json(templates)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GovernanceLogResponse {
+    /// True if the most recent SafeMode event turned emergency safe mode on
+    pub safe_mode: bool,
+    /// False if the most recent HotPathOpen event closed direct swap() calls
+    pub hot_path_open: bool,
+    pub events: Vec<GovernanceEvent>,
+}
+
+/// Retrieves the audit log of protocol-level governance changes made to the Ambient DEX
+///
+/// # Query
+///
+/// A simple HTTP GET request
+///
+/// # Response
+///
+/// The response body will be a JSON object containing the current `safe_mode` and `hot_path_open` flags
+/// so that clients can warn users, and an `events` array of `GovernanceEvent` objects, newest first.
+/// Each event carries the `block_height`, `tx_hash` and the decoded `action` (e.g. `SetTakeRate`, `SafeMode`)
+#[get("/governance_log")]
+pub async fn query_governance_log(db: web::Data<Arc<DB>>) -> impl Responder {
+    info!("Querying governance log");
+    let mut events = get_governance_log(&db);
+    events.reverse();
+    let status = current_dex_status(&db);
+    HttpResponse::Ok().json(GovernanceLogResponse {
+        safe_mode: status.safe_mode,
+        hot_path_open: status.hot_path_open,
+        events,
+    })
+}
+
+/// Retrieves the current protocol-wide status of the Ambient DEX
+///
+/// # Query
+///
+/// A simple HTTP GET request
+///
+/// # Response
+///
+/// The response body will be a JSON `DexStatus` object with the following fields:
+///
+/// - `safe_mode`: True if CrocSwap is in emergency safe mode and only governance actions are allowed
+/// - `hot_path_open`: True if users may call `swap()` directly, otherwise swaps must use the proxy
+/// - `protocol_take_rate` and `relayer_take_rate`: The protocol's fee shares in units of 1/256
+/// - `treasury` and `treasury_start_time`: The protocol fee vault and when it may begin collecting
+/// - `authority`: The current governance authority, if known
+/// - `block_height` and `log_index`: The position of the last governance event reflected in the status
+#[get("/status")]
+pub async fn query_dex_status(db: web::Data<Arc<DB>>) -> impl Responder {
+    info!("Querying dex status");
+    let status = current_dex_status(&db);
+    HttpResponse::Ok().json(status)
+}
+
+/// Retrieves protocol-wide statistics for the DEX dashboard (graphcache-go chain_stats)
+///
+/// # Query
+///
+/// A simple HTTP GET request
+///
+/// # Response
+///
+/// The response body will be a JSON `DexStats` object with the following fields:
+///
+/// - `pool_count`: The number of indexed pools
+/// - `tvl_usd`: The total value locked across every pool that can be priced
+/// - `volume_24h_usd`, `volume_7d_usd`, `fees_24h_usd` and `fees_7d_usd`: Swap volume and LP fees in USD
+/// - `swaps_24h`: The number of swaps in the last 24 hours
+/// - `unique_lps` and `unique_traders`: The number of addresses that have ever minted a position or swapped
+/// - `top_pools_by_tvl`, `top_pools_by_volume` and `top_pools_by_fees`: The leading pools by TVL, 24h
+///   volume and 24h fees, each with its TVL, volume, fees and swap count
+/// - `last_updated`: The unix time the statistics were computed
+///
+/// Statistics are updated by the indexer after each pass. Swaps count towards the hour of their block and
+/// are valued at the token prices when they were indexed. Returns a 404 Not Found response if the
+/// statistics have not been computed yet.
+#[get("/stats")]
+pub async fn query_dex_stats(db: web::Data<Arc<DB>>) -> impl Responder {
+    info!("Querying dex stats");
+    match get_dex_stats(&db) {
+        Some(stats) => HttpResponse::Ok().json(stats),
+        None => HttpResponse::NotFound().body("Dex stats not yet available"),
     }
 }
 
@@ -169,6 +320,7 @@ pub struct UserPoolPositionsRequest {
 /// Many of these fields are not used by the frontend, so the default values are used instead
 /// of trying to populate them with real data
 #[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct UserPosition {
     // USED
     pub chain_id: Uint256,
@@ -181,6 +333,11 @@ pub struct UserPosition {
     pub is_bid: bool,
     pub ambient_liq: Uint256,
     pub conc_liq: Uint256,
+    // Fee APR estimate, see ambient::apr
+    pub apr_duration: f64,
+    pub apr_post_liq: f64,
+    pub apr_contributed_liq: f64,
+    pub apr_est: f64,
 
     // UNUSED
     pub time_first_mint: i32,
@@ -193,13 +350,57 @@ pub struct UserPosition {
     // This is a particularly strange field in the original code
     #[serde(rename = "-")]
     pub strange: StrangeStruct,
-    pub apr_duration: f64,
-    pub apr_post_liq: f64,
-    pub apr_contributed_liq: f64,
-    pub apr: f64,
     pub position_id: f64,
 }
 
+impl UserPosition {
+    /// Builds the report for an active position, estimating its fee APR from indexed pool data
+    pub fn from_position(db: &DB, position: Position) -> UserPosition {
+        match position {
+            Ranged(p) => {
+                let apr = estimate_ranged_apr(db, &p);
+                UserPosition {
+                    chain_id: ALTHEA_MAINNET_EVM_CHAIN_ID.into(),
+                    user: p.user,
+                    base: p.base,
+                    quote: p.quote,
+                    pool_idx: p.pool_idx,
+                    bid_tick: p.bid_tick,
+                    ask_tick: p.ask_tick,
+                    is_bid: p.base_amount > 0,
+                    ambient_liq: 0u8.into(),
+                    conc_liq: p.liq.into(),
+                    position_type: "concentrated".to_string(),
+                    apr_duration: apr.duration,
+                    apr_post_liq: apr.post_liq,
+                    apr_contributed_liq: apr.contributed_liq,
+                    apr_est: apr.apr,
+                    ..Default::default()
+                }
+            }
+            Ambient(p) => {
+                let apr = estimate_ambient_apr(db, &p);
+                UserPosition {
+                    chain_id: ALTHEA_MAINNET_EVM_CHAIN_ID.into(),
+                    user: p.user,
+                    base: p.base,
+                    quote: p.quote,
+                    pool_idx: p.pool_idx,
+                    is_bid: p.base_amount > 0,
+                    conc_liq: 0u8.into(),
+                    ambient_liq: p.liq.into(),
+                    position_type: "ambient".to_string(),
+                    apr_duration: apr.duration,
+                    apr_post_liq: apr.post_liq,
+                    apr_contributed_liq: apr.contributed_liq,
+                    apr_est: apr.apr,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+}
+
 /// This struct is used to populate the `strange` field in `UserPosition`, which becomes renamed to `-`
 /// It is unused, so this struct is just meant to populate expected JSON fields
 #[derive(Clone, Serialize, Deserialize, Debug, Default)]
@@ -225,35 +426,10 @@ pub async fn user_pool_positions(
     if positions.is_empty() {
         HttpResponse::NotFound().body("No pool positions found for user");
     }
-    let mut results = vec![];
-    for position in positions {
-        results.push(match position {
-            Ranged(p) => UserPosition {
-                chain_id: ALTHEA_MAINNET_EVM_CHAIN_ID.into(),
-                user: p.user,
-                base: p.base,
-                quote: p.quote,
-                pool_idx: p.pool_idx,
-                bid_tick: p.bid_tick,
-                ask_tick: p.ask_tick,
-                is_bid: p.base_amount > 0,
-                ambient_liq: 0u8.into(),
-                conc_liq: p.liq.into(),
-                ..Default::default()
-            },
-            Ambient(p) => UserPosition {
-                chain_id: ALTHEA_MAINNET_EVM_CHAIN_ID.into(),
-                user: p.user,
-                base: p.base,
-                quote: p.quote,
-                pool_idx: p.pool_idx,
-                is_bid: p.base_amount > 0,
-                conc_liq: 0u8.into(),
-                ambient_liq: p.liq.into(),
-                ..Default::default()
-            },
-        });
-    }
+    let results = positions
+        .into_iter()
+        .map(|p| UserPosition::from_position(&db, p))
+        .collect::<Vec<_>>();
     HttpResponse::Ok().json(results)
 }
 
@@ -273,35 +449,10 @@ pub async fn user_positions(
     if positions.is_empty() {
         HttpResponse::NotFound().body("No positions found for user");
     }
-    let mut results = vec![];
-    for position in positions {
-        results.push(match position {
-            Ranged(p) => UserPosition {
-                chain_id: ALTHEA_MAINNET_EVM_CHAIN_ID.into(),
-                user: p.user,
-                base: p.base,
-                quote: p.quote,
-                pool_idx: p.pool_idx,
-                bid_tick: p.bid_tick,
-                ask_tick: p.ask_tick,
-                is_bid: p.base_amount > 0,
-                ambient_liq: 0u8.into(),
-                conc_liq: p.liq.into(),
-                ..Default::default()
-            },
-            Ambient(p) => UserPosition {
-                chain_id: ALTHEA_MAINNET_EVM_CHAIN_ID.into(),
-                user: p.user,
-                base: p.base,
-                quote: p.quote,
-                pool_idx: p.pool_idx,
-                is_bid: p.base_amount > 0,
-                conc_liq: 0u8.into(),
-                ambient_liq: p.liq.into(),
-                ..Default::default()
-            },
-        });
-    }
+    let results = positions
+        .into_iter()
+        .map(|p| UserPosition::from_position(&db, p))
+        .collect::<Vec<_>>();
     HttpResponse::Ok().json(results)
 }
 
@@ -372,6 +523,195 @@ pub async fn get_validators(
     }
 }
 
+/// Retrieves details on a single validator
+///
+/// # Path Parameters
+///
+/// - `operator`: The validator's operator address (althea1valoper...)
+///
+/// # Response
+///
+/// Returns a JSON object with the validator's staking information and commission rates, its
+/// signing info (missed blocks, uptime, tombstoned, jailed until), slashing events, the amount
+/// the operator has self-delegated and the number of delegators. If no validator has the given
+/// operator address, returns a 404 Not Found response.
+///
+/// # Example
+///
+/// - `GET /validators/altheavaloper1...` - Returns the details of the given validator
+#[get("/validators/{operator}")]
+pub async fn get_validator_detail(
+    path: web::Path<String>,
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    let operator = path.into_inner();
+    info!("Querying validator detail for {}", operator);
+
+    match super::validators::fetch_validator_detail(&db, &contact, &operator).await {
+        Ok(Some(detail)) => HttpResponse::Ok().json(detail),
+        Ok(None) => HttpResponse::NotFound().body("Validator not found"),
+        Err(e) => {
+            error!("Error getting validator detail: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Retrieves a validator's accumulated commission and outstanding rewards
+///
+/// # Path Parameters
+///
+/// - `operator`: The validator's operator address (altheavaloper1...)
+///
+/// # Response
+///
+/// Returns the operator's account address and the address its commission is withdrawn to, the
+/// commission accumulated since the operator last withdrew it, and every reward the distribution
+/// module holds for the validator, including unclaimed delegator rewards. Amounts are given for each
+/// denom, with fractions of a base unit. If no validator has the given operator address, returns a
+/// 404 Not Found response.
+///
+/// # Example
+///
+/// - `GET /validators/altheavaloper1.../commission` - Returns the commission of the given validator
+#[get("/validators/{operator}/commission")]
+pub async fn get_validator_commission(
+    path: web::Path<String>,
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    let operator = path.into_inner();
+    info!("Querying validator commission for {}", operator);
+
+    match super::validators::fetch_validator_rewards(&db, &contact, &operator).await {
+        Ok(Some(rewards)) => HttpResponse::Ok().json(rewards),
+        Ok(None) => HttpResponse::NotFound().body("Validator not found"),
+        Err(e) => {
+            error!("Error getting validator commission: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ValidatorHistoryQuery {
+    since: Option<u64>,
+}
+
+/// Retrieves the recorded history of a single validator
+///
+/// Snapshots of the validator set are recorded at most hourly and kept for 90 days.
+///
+/// # Path Parameters
+///
+/// - `operator`: The validator's operator address (altheavaloper1...)
+///
+/// # Query Parameters
+///
+/// - `since` (optional): Only return snapshots recorded at or after this unix timestamp
+///
+/// # Response
+///
+/// Returns the validator's tokens, delegator shares, status, jailed flag, commission rate and rank
+/// at each snapshot, oldest first. Returns a 404 Not Found response if the validator does not appear
+/// in any snapshot.
+///
+/// # Examples
+///
+/// - `GET /validators/altheavaloper1.../history` - Returns every recorded snapshot of the validator
+/// - `GET /validators/altheavaloper1.../history?since=1735689600` - Returns snapshots since the given time
+#[get("/validators/{operator}/history")]
+pub async fn get_validator_history(
+    path: web::Path<String>,
+    query: web::Query<ValidatorHistoryQuery>,
+    db: web::Data<Arc<DB>>,
+) -> impl Responder {
+    let operator = path.into_inner();
+    info!("Querying validator history for {}", operator);
+
+    let history =
+        super::validator_history::get_validator_history(&db, &operator, query.since.unwrap_or(0));
+    if history.points.is_empty() {
+        HttpResponse::NotFound().body("No history found for validator")
+    } else {
+        HttpResponse::Ok().json(history)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RankChangeQuery {
+    period: Option<u64>,
+}
+
+/// Retrieves how each validator's rank and voting power have changed over a period
+///
+/// # Query Parameters
+///
+/// - `period` (optional): The number of seconds to look back, defaults to 86400 (one day)
+///
+/// # Response
+///
+/// Returns the time of the snapshot the current validator set was compared against and, for every
+/// current validator, its rank and tokens now and at that snapshot. `rank_change` is positive for
+/// validators that have moved up. Returns a 404 Not Found response if no snapshot has been recorded
+/// within the period.
+///
+/// # Examples
+///
+/// - `GET /validators/rank-changes` - Returns rank changes over the last day
+/// - `GET /validators/rank-changes?period=604800` - Returns rank changes over the last week
+#[get("/validators/rank-changes")]
+pub async fn get_validator_rank_changes(
+    query: web::Query<RankChangeQuery>,
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    let period = query
+        .period
+        .unwrap_or(super::validator_history::DEFAULT_RANK_CHANGE_PERIOD);
+    info!("Querying validator rank changes over {} seconds", period);
+
+    let validators = match super::validators::fetch_validators(&db, &contact).await {
+        Ok(validators) => validators,
+        Err(e) => {
+            error!("Error getting validators: {}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    match super::validator_history::get_rank_changes(&db, &validators, period) {
+        Some(changes) => HttpResponse::Ok().json(changes),
+        None => HttpResponse::NotFound().body("No validator snapshots found in period"),
+    }
+}
+
+/// Retrieves the network staking APR
+///
+/// # Response
+///
+/// Returns the APR earned by delegators before validator commission, along with the inflation rate,
+/// bonded ratio and community tax it was computed from. Percentages range from 0 to 100. The APR
+/// after each validator's commission is given by `estimated_apr` on the `/validators` response.
+///
+/// # Example
+///
+/// - `GET /staking/apr` - Returns the current staking APR
+#[get("/staking/apr")]
+pub async fn get_staking_apr(
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    info!("Querying staking APR");
+
+    match super::staking::fetch_staking_apr(&db, &contact).await {
+        Ok(apr) => HttpResponse::Ok().json(apr),
+        Err(e) => {
+            error!("Error getting staking APR: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 /// Retrieves proposals from the Althea chain
 ///
 /// # Query Parameters
@@ -393,8 +733,11 @@ pub async fn get_validators(
 ///
 /// # Response
 ///
-/// Returns a JSON array of proposal information. If no proposals are found matching
-/// the criteria, returns a 404 Not Found response.
+/// Returns a JSON array of proposal information, including the `proposer` that submitted each proposal
+/// where the node has the submitting transaction indexed. Proposals in their voting period include a
+/// `live_tally` with the current turnout and how it compares to the quorum, threshold and veto
+/// params, refreshed more frequently than the rest of the proposal. If no proposals are found
+/// matching the criteria, returns a 404 Not Found response.
 ///
 /// # Examples
 ///
@@ -450,6 +793,270 @@ pub async fn get_proposals(
     }
 }
 
+/// Retrieves every vote cast on a proposal
+///
+/// # Path Parameters
+///
+/// - `id`: The proposal id
+///
+/// # Response
+///
+/// Returns a JSON object containing the proposal id and its votes, each with the voter's address and
+/// weighted vote options (1 = Yes, 2 = Abstain, 3 = No, 4 = NoWithVeto). The chain removes votes once
+/// a proposal has been tallied, so only proposals in their voting period will have votes.
+///
+/// # Example
+///
+/// - `GET /proposals/12/votes` - Returns the votes cast on proposal 12
+#[get("/proposals/{id}/votes")]
+pub async fn get_proposal_votes(
+    path: web::Path<u64>,
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    let proposal_id = path.into_inner();
+    info!("Querying votes for proposal {}", proposal_id);
+
+    match super::governance::fetch_proposal_votes(&db, &contact, proposal_id).await {
+        Ok(votes) => HttpResponse::Ok().json(votes),
+        Err(e) => {
+            error!("Error getting proposal votes: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Retrieves the deposits made on a proposal
+///
+/// # Path Parameters
+///
+/// - `id`: The proposal id
+///
+/// # Response
+///
+/// Returns a JSON object listing each depositor and their deposit, the proposal's total deposit,
+/// the minimum deposit from the gov params and the amount still required before the proposal
+/// enters its voting period. If the proposal does not exist, returns a 404 Not Found response.
+///
+/// # Example
+///
+/// - `GET /proposals/12/deposits` - Returns the deposits made on proposal 12
+#[get("/proposals/{id}/deposits")]
+pub async fn get_proposal_deposits(
+    path: web::Path<u64>,
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    let proposal_id = path.into_inner();
+    info!("Querying deposits for proposal {}", proposal_id);
+
+    match super::governance::fetch_proposal_deposits(&db, &contact, proposal_id).await {
+        Ok(Some(deposits)) => HttpResponse::Ok().json(deposits),
+        Ok(None) => HttpResponse::NotFound().body("Proposal not found"),
+        Err(e) => {
+            error!("Error getting proposal deposits: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct VoterQuery {
+    address: String,
+}
+
+/// Retrieves an address's votes on every proposal in its voting period
+///
+/// # Query Parameters
+///
+/// - `address`: The voter's address
+///
+/// # Response
+///
+/// Returns a JSON array with one entry per active proposal. `voted` is true if the address voted
+/// directly, otherwise `inherited` lists the votes of the validators the address delegates to,
+/// which are applied to its delegated stake when the proposal is tallied.
+///
+/// # Example
+///
+/// - `GET /votes?address=althea1...` - Returns the address's votes on active proposals
+#[get("/votes")]
+pub async fn get_address_votes(
+    query: web::Query<VoterQuery>,
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    info!("Querying votes for address: {}", query.address);
+
+    let voter = match CosmosAddress::from_bech32(query.address.clone()) {
+        Ok(addr) => addr,
+        Err(e) => {
+            error!("Invalid address format: {}", e);
+            return HttpResponse::BadRequest().body("Invalid address format");
+        }
+    };
+
+    match super::governance::fetch_address_votes(&db, &contact, voter).await {
+        Ok(votes) => HttpResponse::Ok().json(votes),
+        Err(e) => {
+            error!("Error getting address votes: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Retrieves everything held by an account
+///
+/// # Path Parameters
+///
+/// - `address`: The account's address, either as althea1... or as the equivalent 0x... EVM address
+///
+/// # Response
+///
+/// Returns both forms of the address, the account's bank balances, its balances of tokens in
+/// indexed pools, its delegations, rewards, unbonding delegations and redelegations, and its
+/// open Ambient positions. Returns a 400 Bad Request response if the address is invalid.
+///
+/// # Examples
+///
+/// - `GET /account/althea1...` - Returns the account with the given bech32 address
+/// - `GET /account/0x...` - Returns the same account by its EVM address
+#[get("/account/{address}")]
+pub async fn get_account(
+    path: web::Path<String>,
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    info!("Querying account {}", path);
+
+    let address = match path.parse::<AccountAddress>() {
+        Ok(address) => address,
+        Err(e) => {
+            error!("Invalid address format: {}", e);
+            return HttpResponse::BadRequest().body("Invalid address format");
+        }
+    };
+
+    let web3 = get_althea_web3(TIMEOUT);
+    match fetch_account(&db, &contact, &web3, address).await {
+        Ok(account) => HttpResponse::Ok().json(account),
+        Err(e) => {
+            error!("Error fetching account: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AccountTxsQuery {
+    page: Option<u64>,
+    limit: Option<u64>,
+}
+
+/// Retrieves an account's transaction history as an activity feed
+///
+/// # Path Parameters
+///
+/// - `address`: The account's address, either as althea1... or as the equivalent 0x... EVM address
+///
+/// # Query Parameters
+///
+/// - `page`: The page to return, starting from 1 (default 1)
+/// - `limit`: The number of transactions per page (default 20, at most 100)
+///
+/// # Response
+///
+/// Returns the transactions signed by the account or transferring funds to it, newest first. Each
+/// transaction includes its hash, height, time, fee, memo and whether it succeeded, along with its
+/// messages. Delegations, undelegations, redelegations, reward withdrawals, votes, bank sends and IBC
+/// transfers are decoded, other messages only report their type. `has_more` is true if there are older
+/// transactions on the following pages. Pages may reach at most 2000 transactions into the account's
+/// history. Returns a 400 Bad Request response if the address or page is invalid.
+///
+/// # Examples
+///
+/// - `GET /account/althea1.../txs` - Returns the most recent transactions of the account
+/// - `GET /account/0x.../txs?page=2&limit=50` - Returns the second page of 50 transactions
+#[get("/account/{address}/txs")]
+pub async fn get_account_txs(
+    path: web::Path<String>,
+    query: web::Query<AccountTxsQuery>,
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    info!("Querying transactions of account {}", path);
+
+    let address = match path.parse::<AccountAddress>() {
+        Ok(address) => address,
+        Err(e) => {
+            error!("Invalid address format: {}", e);
+            return HttpResponse::BadRequest().body("Invalid address format");
+        }
+    };
+    let page = query.page.unwrap_or(1);
+    if page == 0 {
+        return HttpResponse::BadRequest().body("Pages start at 1");
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_TXS_LIMIT)
+        .clamp(1, MAX_TXS_LIMIT);
+    if page.saturating_mul(limit) > MAX_TXS_DEPTH {
+        return HttpResponse::BadRequest().body("Page is too far into the account's history");
+    }
+
+    match fetch_account_txs(&db, &contact, address.cosmos, page, limit).await {
+        Ok(txs) => HttpResponse::Ok().json(txs),
+        Err(e) => {
+            error!("Error fetching account transactions: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Retrieves the token registry
+///
+/// # Response
+///
+/// Returns the name, symbol and decimals of every token found in an indexed pool, the Cosmos denom
+/// each token converts to through the erc20 module if any, and any configured display name and logo.
+/// The native token appears with the zero address, as it does in Ambient pools. Returns a 404 Not Found
+/// response if the registry has not been built yet.
+///
+/// # Example
+///
+/// - `GET /tokens` - Returns every known token
+#[get("/tokens")]
+pub async fn get_tokens(db: web::Data<Arc<DB>>) -> impl Responder {
+    info!("Querying token registry");
+
+    match get_token_registry(&db) {
+        Some(registry) => HttpResponse::Ok().json(registry.tokens),
+        None => HttpResponse::NotFound().body("Token registry not yet available"),
+    }
+}
+
+/// Retrieves the USD price of every token that can be routed to a stablecoin through indexed pools
+///
+/// # Response
+///
+/// Returns each token's price per whole token, the pools the price was routed through from the token
+/// to a stablecoin, and the USD liquidity of the shallowest pool on that route. Routes are chosen to
+/// maximize that liquidity. Returns a 404 Not Found response if prices have not been computed yet.
+///
+/// # Example
+///
+/// - `GET /prices` - Returns the price of every routable token
+#[get("/prices")]
+pub async fn get_prices(db: web::Data<Arc<DB>>) -> impl Responder {
+    info!("Querying token prices");
+
+    match get_token_prices(&db) {
+        Some(prices) => HttpResponse::Ok().json(prices),
+        None => HttpResponse::NotFound().body("Token prices not yet available"),
+    }
+}
+
 #[derive(Deserialize)]
 pub struct DelegatorQuery {
     address: String,
@@ -459,16 +1066,21 @@ pub struct DelegatorQuery {
 ///
 /// # Query Parameters
 ///
-/// - `address`: The delegator's address to query delegations for
+/// - `address`: The delegator's address to query delegations for, either as althea1... or 0x...
 ///
 /// # Response
 ///
 /// Returns a JSON array of delegation information including validator addresses
-/// and delegation amounts. If no delegations are found, returns a 404 Not Found response.
+/// and delegation amounts, along with any pending unbonding delegations and redelegations
+/// and their completion times, the pending rewards from each validator in every denom, the
+/// address rewards are withdrawn to and the unix time the response was queried (`last_updated`).
+/// If the address has no delegations, unbonding delegations
+/// or redelegations, returns a 404 Not Found response.
 ///
 /// # Example
 ///
 /// - `GET /delegations?address=althea1...` - Returns all delegations for the specified address
+/// - `GET /delegations?address=0x...` - Returns all delegations for the account with the given EVM address
 #[get("/delegations")]
 pub async fn get_delegations(
     query: web::Query<DelegatorQuery>,
@@ -477,8 +1089,8 @@ pub async fn get_delegations(
 ) -> impl Responder {
     info!("Querying delegations for address: {}", query.address);
 
-    let delegator_address = match CosmosAddress::from_bech32(query.address.clone()) {
-        Ok(addr) => addr,
+    let delegator_address = match query.address.parse::<AccountAddress>() {
+        Ok(addr) => addr.cosmos,
         Err(e) => {
             error!("Invalid address format: {}", e);
             return HttpResponse::BadRequest().body("Invalid address format");
@@ -487,7 +1099,10 @@ pub async fn get_delegations(
 
     match fetch_delegations(&db, &contact, delegator_address).await {
         Ok(response) => {
-            if response.delegations.is_empty() {
+            if response.delegations.is_empty()
+                && response.unbonding_delegations.is_empty()
+                && response.redelegations.is_empty()
+            {
                 HttpResponse::NotFound().body("No delegations found")
             } else {
                 HttpResponse::Ok().json(response)
@@ -499,3 +1114,68 @@ pub async fn get_delegations(
         }
     }
 }
+
+/// Retrieves counters describing the delegation cache, which is refreshed every five minutes
+///
+/// # Response
+///
+/// Returns the number of addresses cached after the last refresh, the number of addresses evicted
+/// because nobody had requested them for a day and the number that failed to refresh, each for the
+/// last refresh and in total, along with the time of the last refresh. Returns a 404 Not Found
+/// response if the cache has not been refreshed yet.
+///
+/// # Example
+///
+/// - `GET /delegations/cache-metrics` - Returns the delegation cache counters
+#[get("/delegations/cache-metrics")]
+pub async fn get_delegation_cache_stats(db: web::Data<Arc<DB>>) -> impl Responder {
+    info!("Querying delegation cache metrics");
+
+    match get_delegation_cache_metrics(&db) {
+        Some(metrics) => HttpResponse::Ok().json(metrics),
+        None => HttpResponse::NotFound().body("Delegation cache has not been refreshed yet"),
+    }
+}
+
+/// Retrieves the bank balances of an address
+///
+/// # Query Parameters
+///
+/// - `address`: The account's address, either as althea1... or as the equivalent 0x... EVM address
+///
+/// # Response
+///
+/// Returns every coin held by the account in base units. Where the bank module has metadata for a
+/// denom, each balance also gives its display denom, exponent, display amount, name and symbol. IBC
+/// vouchers (ibc/...) include the path they were transferred through and their denom on the origin
+/// chain, and coins that the erc20 module maps to an ERC20 include its address and whether conversion
+/// is enabled. Returns a 400 Bad Request response if the address is invalid.
+///
+/// # Examples
+///
+/// - `GET /balances?address=althea1...` - Returns the balances of the given address
+/// - `GET /balances?address=0x...` - Returns the balances of the account with the given EVM address
+#[get("/balances")]
+pub async fn get_balances(
+    query: web::Query<DelegatorQuery>,
+    db: web::Data<Arc<DB>>,
+    contact: web::Data<Arc<Contact>>,
+) -> impl Responder {
+    info!("Querying balances for address: {}", query.address);
+
+    let address = match query.address.parse::<AccountAddress>() {
+        Ok(addr) => addr.cosmos,
+        Err(e) => {
+            error!("Invalid address format: {}", e);
+            return HttpResponse::BadRequest().body("Invalid address format");
+        }
+    };
+
+    match fetch_balances(&db, &contact, address).await {
+        Ok(balances) => HttpResponse::Ok().json(balances),
+        Err(e) => {
+            error!("Error fetching balances: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -3,10 +3,13 @@ use std::fmt;
 use clarity::error::Error as ClarityError;
 use web30::jsonrpc::error::Web3Error;
 
+#[derive(Debug)]
 pub enum AltheaError {
     EthereumRestError(Web3Error),
     ClarityError(ClarityError),
     InvalidEventLogError(String),
+    InvalidAmountError(String),
+    InvalidAddressError(String),
 }
 impl fmt::Display for AltheaError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -14,9 +17,12 @@ impl fmt::Display for AltheaError {
             AltheaError::EthereumRestError(val) => write!(f, "Web3 error: {}", val),
             AltheaError::InvalidEventLogError(val) => write!(f, "Invalid ethereum logs: {}", val),
             AltheaError::ClarityError(error) => write!(f, "Clarity error: {}", error),
+            AltheaError::InvalidAmountError(val) => write!(f, "Invalid amount: {}", val),
+            AltheaError::InvalidAddressError(val) => write!(f, "Invalid address: {}", val),
         }
     }
 }
+impl std::error::Error for AltheaError {}
 impl From<Web3Error> for AltheaError {
     fn from(error: Web3Error) -> Self {
         AltheaError::EthereumRestError(error)
@@ -0,0 +1,226 @@
+// Queries gov v1 proposals. Althea's SDK predates the title, summary and proposer fields that v0.47 added
+// to Proposal, so a v1 proposal's title and summary come from its legacy content or its metadata, and
+// its proposer is found from the transaction that submitted it (see governance.rs).
+
+use cosmos_sdk_proto_althea::cosmos::base::query::v1beta1::{PageRequest, PageResponse};
+use cosmos_sdk_proto_althea::cosmos::base::v1beta1::Coin as ProtoCoin;
+use deep_space::Contact;
+use prost::Message;
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::Endpoint;
+
+use crate::althea::format_timestamp;
+use crate::althea::governance::{Coin, ProposalInfo, TallyResult};
+use crate::althea::proposal_content::{ProposalContent, ProposalDetails, ProposalMessage};
+
+/// cosmos-sdk-proto-althea generates the gov v1 types but does not export them, these mirror the messages
+/// and the Proposals query this crate uses
+const PROPOSALS_PATH: &str = "/cosmos.gov.v1.Query/Proposals";
+
+#[derive(Clone, PartialEq, Message)]
+pub struct V1Proposal {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(message, repeated, tag = "2")]
+    pub messages: Vec<prost_types::Any>,
+    #[prost(int32, tag = "3")]
+    pub status: i32,
+    #[prost(message, optional, tag = "4")]
+    pub final_tally_result: Option<V1TallyResult>,
+    #[prost(message, optional, tag = "5")]
+    pub submit_time: Option<prost_types::Timestamp>,
+    #[prost(message, optional, tag = "6")]
+    pub deposit_end_time: Option<prost_types::Timestamp>,
+    #[prost(message, repeated, tag = "7")]
+    pub total_deposit: Vec<ProtoCoin>,
+    #[prost(message, optional, tag = "8")]
+    pub voting_start_time: Option<prost_types::Timestamp>,
+    #[prost(message, optional, tag = "9")]
+    pub voting_end_time: Option<prost_types::Timestamp>,
+    #[prost(string, tag = "10")]
+    pub metadata: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct V1TallyResult {
+    #[prost(string, tag = "1")]
+    pub yes_count: String,
+    #[prost(string, tag = "2")]
+    pub abstain_count: String,
+    #[prost(string, tag = "3")]
+    pub no_count: String,
+    #[prost(string, tag = "4")]
+    pub no_with_veto_count: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct QueryProposalsRequest {
+    #[prost(int32, tag = "1")]
+    proposal_status: i32,
+    #[prost(string, tag = "2")]
+    voter: String,
+    #[prost(string, tag = "3")]
+    depositor: String,
+    #[prost(message, optional, tag = "4")]
+    pagination: Option<PageRequest>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct QueryProposalsResponse {
+    #[prost(message, repeated, tag = "1")]
+    proposals: Vec<V1Proposal>,
+    #[prost(message, optional, tag = "2")]
+    pagination: Option<PageResponse>,
+}
+
+/// /cosmos.gov.v1.MsgSubmitProposal
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgSubmitProposal {
+    #[prost(message, repeated, tag = "1")]
+    pub messages: Vec<prost_types::Any>,
+    #[prost(message, repeated, tag = "2")]
+    pub initial_deposit: Vec<ProtoCoin>,
+    #[prost(string, tag = "3")]
+    pub proposer: String,
+    #[prost(string, tag = "4")]
+    pub metadata: String,
+}
+
+/// /cosmos.gov.v1.MsgExecLegacyContent, which wraps the content of a v1beta1 proposal
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgExecLegacyContent {
+    #[prost(message, optional, tag = "1")]
+    pub content: Option<prost_types::Any>,
+    #[prost(string, tag = "2")]
+    pub authority: String,
+}
+
+/// /cosmos.gov.v1.MsgVote
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgVote {
+    #[prost(uint64, tag = "1")]
+    pub proposal_id: u64,
+    #[prost(string, tag = "2")]
+    pub voter: String,
+    #[prost(int32, tag = "3")]
+    pub option: i32,
+    #[prost(string, tag = "4")]
+    pub metadata: String,
+}
+
+/// The off-chain metadata format recommended for gov v1 proposals
+#[derive(Deserialize)]
+struct ProposalMetadata {
+    title: Option<String>,
+    summary: Option<String>,
+    details: Option<String>,
+}
+
+/// Fetches every proposal through the gov v1 query service
+pub async fn fetch_v1_proposals(
+    contact: &Contact,
+) -> Result<Vec<ProposalInfo>, Box<dyn std::error::Error + Send + Sync>> {
+    let channel = Endpoint::from_shared(contact.get_url())?.connect().await?;
+    let mut grpc = tonic::client::Grpc::new(channel);
+    let mut proposals = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        grpc.ready().await?;
+        let codec =
+            tonic::codec::ProstCodec::<QueryProposalsRequest, QueryProposalsResponse>::default();
+        let request = QueryProposalsRequest {
+            proposal_status: 0,
+            voter: String::new(),
+            depositor: String::new(),
+            pagination: Some(PageRequest {
+                key: next_key,
+                offset: 0,
+                limit: 1000,
+                count_total: false,
+                reverse: false,
+            }),
+        };
+        let response = grpc
+            .unary(
+                tonic::Request::new(request),
+                PathAndQuery::from_static(PROPOSALS_PATH),
+                codec,
+            )
+            .await?
+            .into_inner();
+        proposals.extend(response.proposals.into_iter().map(ProposalInfo::from));
+        match response.pagination {
+            Some(p) if !p.next_key.is_empty() => next_key = p.next_key,
+            _ => break,
+        }
+    }
+    Ok(proposals)
+}
+
+impl From<V1Proposal> for ProposalInfo {
+    fn from(p: V1Proposal) -> Self {
+        let messages: Vec<ProposalMessage> = p
+            .messages
+            .into_iter()
+            .map(|m| ProposalMessage::decode(m.type_url, &m.value))
+            .collect();
+        let content = v1_content(&p.metadata, &messages);
+
+        ProposalInfo {
+            proposal_id: p.id,
+            content,
+            messages,
+            metadata: Some(p.metadata).filter(|m| !m.is_empty()),
+            proposer: None,
+            status: p.status,
+            final_tally_result: p.final_tally_result.map(|t| TallyResult {
+                yes: t.yes_count,
+                abstain: t.abstain_count,
+                no: t.no_count,
+                no_with_veto: t.no_with_veto_count,
+            }),
+            live_tally: None,
+            submit_time: p.submit_time.and_then(format_timestamp),
+            deposit_end_time: p.deposit_end_time.and_then(format_timestamp),
+            total_deposit: p.total_deposit.into_iter().map(Coin::from).collect(),
+            voting_start_time: p.voting_start_time.and_then(format_timestamp),
+            voting_end_time: p.voting_end_time.and_then(format_timestamp),
+            last_updated: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        }
+    }
+}
+
+// Builds the content shown for a v1 proposal. A proposal wrapping a legacy v1beta1 proposal uses that
+// proposal's content, otherwise the title and summary come from the proposal's metadata
+fn v1_content(metadata: &str, messages: &[ProposalMessage]) -> Option<ProposalContent> {
+    if let Some(content) = messages.iter().find_map(|m| m.legacy_content()) {
+        return Some(content.clone());
+    }
+
+    // Metadata is frequently a link to an off chain document rather than JSON, in which case it is left as is
+    let (title, description) = match serde_json::from_str::<ProposalMetadata>(metadata) {
+        Ok(m) => (
+            m.title.unwrap_or_default(),
+            m.summary.or(m.details).unwrap_or_default(),
+        ),
+        Err(_) => (String::new(), String::new()),
+    };
+    if title.is_empty() && description.is_empty() && messages.is_empty() {
+        return None;
+    }
+
+    Some(ProposalContent {
+        type_url: messages
+            .first()
+            .map(|m| m.type_url.clone())
+            .unwrap_or_default(),
+        title,
+        description,
+        details: ProposalDetails::Unknown,
+    })
+}
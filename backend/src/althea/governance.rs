@@ -1,38 +1,83 @@
 use bincode;
 use chrono;
 use cosmos_sdk_proto_althea::cosmos::base::query::v1beta1::PageRequest;
-use cosmos_sdk_proto_althea::cosmos::gov::v1beta1::{Proposal, QueryProposalsRequest};
-use deep_space::Contact;
-use log::{error, info};
+use cosmos_sdk_proto_althea::cosmos::base::v1beta1::Coin as ProtoCoin;
+use cosmos_sdk_proto_althea::cosmos::gov::v1beta1::{
+    query_client::QueryClient as GovQueryClient, MsgSubmitProposal, Proposal, QueryDepositsRequest,
+    QueryParamsRequest, QueryProposalsRequest, QueryTallyResultRequest, QueryVotesRequest,
+    TallyResult as ProtoTallyResult, Vote,
+};
+use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::{
+    query_client::QueryClient as StakingQueryClient, QueryPoolRequest,
+};
+use cosmos_sdk_proto_althea::cosmos::tx::v1beta1::{
+    service_client::ServiceClient as TxServiceClient, GetTxsEventRequest, OrderBy,
+};
+use deep_space::{Address as CosmosAddress, Contact};
+use futures::stream::{self, StreamExt};
+use log::{error, info, warn};
+use prost::Message;
 use rocksdb::DB;
 use serde::{Deserialize, Serialize};
 
-use crate::althea::CACHE_DURATION;
+use crate::althea::amount::Amount;
+use crate::althea::delegations::fetch_delegations;
+use crate::althea::gov_v1::{fetch_v1_proposals, MsgSubmitProposal as MsgSubmitProposalV1};
+use crate::althea::notifications::{notify_status_changes, Webhook};
+use crate::althea::proposal_content::{ProposalContent, ProposalMessage};
+use crate::althea::{get_cached, Cached, ALTHEA_PREFIX, CACHE_DURATION};
 
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::time::sleep;
 
+/// How long the live tally of a proposal in its voting period is cached, in seconds
+pub const LIVE_TALLY_CACHE_DURATION: u64 = 30;
+const PROPOSAL_PROPOSER_KEY_PREFIX: &str = "proposal_proposer_";
+const PROPOSER_QUERY_CONCURRENCY: usize = 4;
+const MSG_SUBMIT_PROPOSAL_TYPE_URL: &str = "/cosmos.gov.v1beta1.MsgSubmitProposal";
+const MSG_SUBMIT_PROPOSAL_V1_TYPE_URL: &str = "/cosmos.gov.v1.MsgSubmitProposal";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProposalInfo {
     pub proposal_id: u64,
     pub content: Option<ProposalContent>,
+    /// The messages executed if a gov v1 proposal passes, empty for proposals only served by v1beta1
+    pub messages: Vec<ProposalMessage>,
+    pub metadata: Option<String>,
+    /// The account that submitted the proposal, None if its submission could not be found
+    pub proposer: Option<String>,
     pub status: i32,
     pub final_tally_result: Option<TallyResult>,
+    /// The current tally of a proposal in its voting period, None for all other proposals
+    pub live_tally: Option<LiveTally>,
     pub submit_time: Option<String>,
     pub deposit_end_time: Option<String>,
-    pub total_deposit: Vec<String>,
+    pub total_deposit: Vec<Coin>,
     pub voting_start_time: Option<String>,
     pub voting_end_time: Option<String>,
     pub last_updated: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ProposalContent {
-    pub type_url: String,
-    pub title: String,
-    pub description: String,
-    pub value: Vec<u8>,
+impl Cached for ProposalInfo {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Coin {
+    pub denom: String,
+    pub amount: String,
+}
+
+impl From<ProtoCoin> for Coin {
+    fn from(c: ProtoCoin) -> Self {
+        Coin {
+            denom: c.denom,
+            amount: c.amount,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -43,18 +88,33 @@ pub struct TallyResult {
     pub no_with_veto: String,
 }
 
+/// The running tally of a proposal in its voting period, evaluated against the gov tally params.
+/// All percentages are in the range 0 to 100
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ParamChange {
-    pub subspace: String,
-    pub key: String,
-    pub value: String,
+pub struct LiveTally {
+    pub tally: TallyResult,
+    pub bonded_tokens: String,
+    /// The share of bonded tokens that have voted
+    pub turnout_percent: f64,
+    /// The share of non-abstaining votes that are Yes, compared against the threshold
+    pub yes_percent: f64,
+    /// The share of all votes that are NoWithVeto, compared against the veto threshold
+    pub no_with_veto_percent: f64,
+    pub quorum_percent: f64,
+    pub threshold_percent: f64,
+    pub veto_threshold_percent: f64,
+    pub quorum_reached: bool,
+    /// True if the proposal would pass if voting ended now
+    pub passing: bool,
+    pub last_updated: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct UpgradePlan {
-    pub name: String,
-    pub height: i64,
-    pub info: String,
+/// The gov module's tally params as fractions
+#[derive(Debug, Clone, Copy)]
+pub struct TallyParams {
+    pub quorum: f64,
+    pub threshold: f64,
+    pub veto_threshold: f64,
 }
 
 impl ProposalInfo {
@@ -69,10 +129,50 @@ pub async fn fetch_proposals(
 ) -> Result<Vec<ProposalInfo>, Box<dyn std::error::Error>> {
     info!("Fetching proposals");
     let cached = get_cached_proposals(db);
-    if let Some(proposals) = cached {
+    if let Some(mut proposals) = cached {
+        refresh_live_tallies(db, contact, &mut proposals).await;
         return Ok(proposals);
     }
 
+    // Nodes may serve either or both gov query versions, v1 includes proposals submitted with
+    // arbitrary messages while v1beta1 decodes legacy content more completely
+    let (legacy, v1) = futures::future::join(
+        fetch_v1beta1_proposals(contact),
+        fetch_v1_proposals(contact),
+    )
+    .await;
+    let mut all_proposals = match (legacy, v1) {
+        (Ok(legacy), Ok(v1)) => merge_proposals(legacy, v1),
+        (Ok(legacy), Err(e)) => {
+            warn!(
+                "Failed to query gov v1 proposals, using v1beta1 only: {}",
+                e
+            );
+            legacy
+        }
+        (Err(e), Ok(v1)) => {
+            warn!(
+                "Failed to query gov v1beta1 proposals, using v1 only: {}",
+                e
+            );
+            v1
+        }
+        (Err(e), Err(_)) => return Err(e),
+    };
+    fill_proposers(db, contact, &mut all_proposals).await;
+
+    cache_proposals(db, &all_proposals);
+    refresh_live_tallies(db, contact, &mut all_proposals).await;
+    info!(
+        "Successfully fetched and stored {} proposals",
+        all_proposals.len()
+    );
+    Ok(all_proposals)
+}
+
+async fn fetch_v1beta1_proposals(
+    contact: &Contact,
+) -> Result<Vec<ProposalInfo>, Box<dyn std::error::Error + Send + Sync>> {
     let request = QueryProposalsRequest {
         proposal_status: 0,
         voter: String::new(),
@@ -87,83 +187,212 @@ pub async fn fetch_proposals(
     };
 
     let proposals = contact.get_governance_proposals(request).await?;
-    let all_proposals: Vec<ProposalInfo> = proposals
+    Ok(proposals
         .proposals
         .into_iter()
         .map(ProposalInfo::from)
-        .collect();
+        .collect())
+}
 
-    cache_proposals(db, &all_proposals);
-    info!(
-        "Successfully fetched and stored {} proposals",
-        all_proposals.len()
-    );
-    Ok(all_proposals)
+// Combines the results of both query versions by proposal id, v1beta1 content is kept where present
+// and the v1 messages and metadata are added to it
+fn merge_proposals(legacy: Vec<ProposalInfo>, v1: Vec<ProposalInfo>) -> Vec<ProposalInfo> {
+    let mut merged = legacy;
+    for proposal in v1 {
+        match merged
+            .iter_mut()
+            .find(|p| p.proposal_id == proposal.proposal_id)
+        {
+            Some(existing) => {
+                if existing.content.is_none() {
+                    existing.content = proposal.content;
+                }
+                existing.messages = proposal.messages;
+                existing.metadata = proposal.metadata;
+            }
+            None => merged.push(proposal),
+        }
+    }
+    merged.sort_by_key(|p| p.proposal_id);
+    merged
 }
 
-fn get_cached_proposals(db: &rocksdb::DB) -> Option<Vec<ProposalInfo>> {
-    const PROPOSALS_CACHE_KEY: &[u8] = b"proposals";
-    match db.get(PROPOSALS_CACHE_KEY).unwrap() {
-        Some(data) => {
-            let proposals: Vec<ProposalInfo> = bincode::deserialize(&data).unwrap();
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+/// Sets the proposer of each proposal, which neither gov query version reports on Althea's SDK, from the
+/// MsgSubmitProposal of the transaction that submitted it. A proposal's proposer never changes, so the
+/// result of each search is stored and only new proposals are searched for
+async fn fill_proposers(db: &rocksdb::DB, contact: &Contact, proposals: &mut [ProposalInfo]) {
+    let mut unknown = Vec::new();
+    for proposal in proposals.iter_mut() {
+        match get_stored_proposer(db, proposal.proposal_id) {
+            Some(proposer) => proposal.proposer = proposer,
+            None => unknown.push(proposal.proposal_id),
+        }
+    }
+    if unknown.is_empty() {
+        return;
+    }
 
-            // Cache for 5 minutes
-            if now - proposals[0].last_updated < CACHE_DURATION {
-                Some(proposals)
-            } else {
-                None
+    let client = match TxServiceClient::connect(contact.get_url()).await {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(
+                "Failed to connect to the tx service to find proposers: {}",
+                e
+            );
+            return;
+        }
+    };
+    let found: Vec<(u64, Option<String>)> = stream::iter(unknown)
+        .map(|proposal_id| {
+            let mut client = client.clone();
+            async move {
+                match fetch_proposer(&mut client, proposal_id).await {
+                    Ok(proposer) => {
+                        store_proposer(db, proposal_id, &proposer);
+                        Some((proposal_id, proposer))
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to find the proposer of proposal {}: {}",
+                            proposal_id, e
+                        );
+                        None
+                    }
+                }
             }
+        })
+        .buffer_unordered(PROPOSER_QUERY_CONCURRENCY)
+        .filter_map(|found| async move { found })
+        .collect()
+        .await;
+    for (proposal_id, proposer) in found {
+        if let Some(p) = proposals.iter_mut().find(|p| p.proposal_id == proposal_id) {
+            p.proposer = proposer;
         }
-        None => None,
     }
 }
 
+// Finds the transaction that emitted the proposal's submit_proposal event, whose MsgSubmitProposal was
+// signed by the proposer. Returns None if the node has no such transaction indexed
+async fn fetch_proposer(
+    client: &mut TxServiceClient<tonic::transport::Channel>,
+    proposal_id: u64,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let response = client
+        .get_txs_event(GetTxsEventRequest {
+            events: vec![format!("submit_proposal.proposal_id='{}'", proposal_id)],
+            order_by: OrderBy::Asc as i32,
+            page: 1,
+            limit: 1,
+            ..Default::default()
+        })
+        .await?
+        .into_inner();
+    let messages = response
+        .txs
+        .into_iter()
+        .next()
+        .and_then(|tx| tx.body)
+        .map(|body| body.messages)
+        .unwrap_or_default();
+    Ok(messages.iter().find_map(|m| match m.type_url.as_str() {
+        MSG_SUBMIT_PROPOSAL_TYPE_URL => MsgSubmitProposal::decode(m.value.as_slice())
+            .ok()
+            .map(|msg| msg.proposer),
+        MSG_SUBMIT_PROPOSAL_V1_TYPE_URL => MsgSubmitProposalV1::decode(m.value.as_slice())
+            .ok()
+            .map(|msg| msg.proposer),
+        _ => None,
+    }))
+}
+
+// Returns the stored result of searching for the proposal's proposer, None if it has not been searched for
+fn get_stored_proposer(db: &rocksdb::DB, proposal_id: u64) -> Option<Option<String>> {
+    let key = format!("{}{}", PROPOSAL_PROPOSER_KEY_PREFIX, proposal_id);
+    let data = db.get(key.as_bytes()).unwrap()?;
+    bincode::deserialize(&data).ok()
+}
+
+fn store_proposer(db: &rocksdb::DB, proposal_id: u64, proposer: &Option<String>) {
+    let key = format!("{}{}", PROPOSAL_PROPOSER_KEY_PREFIX, proposal_id);
+    db.put(key.as_bytes(), bincode::serialize(proposer).unwrap())
+        .unwrap();
+}
+
+fn get_cached_proposals(db: &rocksdb::DB) -> Option<Vec<ProposalInfo>> {
+    const PROPOSALS_CACHE_KEY: &[u8] = b"proposals";
+    get_cached(db, PROPOSALS_CACHE_KEY, CACHE_DURATION)
+}
+
 fn cache_proposals(db: &rocksdb::DB, proposals: &[ProposalInfo]) {
     let key = b"proposals";
     let encoded = bincode::serialize(proposals).unwrap();
     db.put(key, encoded).unwrap();
 }
 
-impl From<Proposal> for ProposalInfo {
-    fn from(p: Proposal) -> Self {
-        // Parse the encoded title string which contains multiple fields
-        let (title, description) = if let Some(content) = &p.content {
-            let raw_value = content.value.to_vec();
-            let value_str: String = raw_value.into_iter().map(|b| b as char).collect();
+impl From<ProtoTallyResult> for TallyResult {
+    fn from(t: ProtoTallyResult) -> Self {
+        TallyResult {
+            yes: t.yes,
+            abstain: t.abstain,
+            no: t.no,
+            no_with_veto: t.no_with_veto,
+        }
+    }
+}
 
-            // Split on the control characters
-            let parts: Vec<&str> = value_str
-                .split(['\u{001b}', '\u{0012}', '\u{001a}'].as_ref())
-                .collect();
+impl LiveTally {
+    /// Evaluates `tally` the same way the gov module does when voting ends
+    pub fn new(tally: TallyResult, bonded_tokens: String, params: &TallyParams, now: u64) -> Self {
+        let amount = |v: &str| v.parse::<f64>().unwrap_or_default();
+        let (yes, abstain, no, veto) = (
+            amount(&tally.yes),
+            amount(&tally.abstain),
+            amount(&tally.no),
+            amount(&tally.no_with_veto),
+        );
+        let bonded = amount(&bonded_tokens);
+        let total = yes + abstain + no + veto;
+        let ratio = |n: f64, d: f64| if d > 0.0 { n / d } else { 0.0 };
 
-            // parts[1] should be the title, parts[2] should be the description
-            let clean_title = parts.get(1).unwrap_or(&"").trim();
-            let clean_description = parts.get(2).unwrap_or(&"").trim();
+        let turnout = ratio(total, bonded);
+        let yes_ratio = ratio(yes, total - abstain);
+        let veto_ratio = ratio(veto, total);
+        let quorum_reached = total > 0.0 && turnout >= params.quorum;
+        let passing = quorum_reached
+            && total - abstain > 0.0
+            && veto_ratio <= params.veto_threshold
+            && yes_ratio > params.threshold;
 
-            (clean_title.to_string(), clean_description.to_string())
-        } else {
-            (String::new(), String::new())
-        };
+        LiveTally {
+            tally,
+            bonded_tokens,
+            turnout_percent: turnout * 100.0,
+            yes_percent: yes_ratio * 100.0,
+            no_with_veto_percent: veto_ratio * 100.0,
+            quorum_percent: params.quorum * 100.0,
+            threshold_percent: params.threshold * 100.0,
+            veto_threshold_percent: params.veto_threshold * 100.0,
+            quorum_reached,
+            passing,
+            last_updated: now,
+        }
+    }
+}
 
+impl From<Proposal> for ProposalInfo {
+    fn from(p: Proposal) -> Self {
         ProposalInfo {
             proposal_id: p.proposal_id,
-            content: p.content.map(|c| ProposalContent {
-                type_url: c.type_url,
-                title,
-                description,
-                value: c.value,
-            }),
+            content: p
+                .content
+                .map(|c| ProposalContent::decode(c.type_url, &c.value)),
+            messages: Vec::new(),
+            metadata: None,
+            proposer: None,
             status: p.status,
-            final_tally_result: p.final_tally_result.map(|t| TallyResult {
-                yes: t.yes,
-                abstain: t.abstain,
-                no: t.no,
-                no_with_veto: t.no_with_veto,
-            }),
+            final_tally_result: p.final_tally_result.map(TallyResult::from),
+            live_tally: None,
             submit_time: p.submit_time.map(|t| {
                 chrono::DateTime::from_timestamp(t.seconds, 0)
                     .unwrap()
@@ -174,11 +403,7 @@ impl From<Proposal> for ProposalInfo {
                     .unwrap()
                     .to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)
             }),
-            total_deposit: p
-                .total_deposit
-                .into_iter()
-                .map(|c| format!("{}{}", c.amount, c.denom))
-                .collect(),
+            total_deposit: p.total_deposit.into_iter().map(Coin::from).collect(),
             voting_start_time: p.voting_start_time.map(|t| {
                 chrono::DateTime::from_timestamp(t.seconds, 0)
                     .unwrap()
@@ -197,6 +422,87 @@ impl From<Proposal> for ProposalInfo {
     }
 }
 
+/// Updates the live tally of every active proposal whose tally is older than LIVE_TALLY_CACHE_DURATION,
+/// saving the result to the proposal cache. Failures are logged and the previous tallies are kept
+async fn refresh_live_tallies(db: &rocksdb::DB, contact: &Contact, proposals: &mut [ProposalInfo]) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let stale = proposals.iter().any(|p| {
+        p.is_active()
+            && p.live_tally
+                .as_ref()
+                .is_none_or(|t| now - t.last_updated >= LIVE_TALLY_CACHE_DURATION)
+    });
+    if !stale {
+        return;
+    }
+    match update_live_tallies(contact, proposals, now).await {
+        Ok(()) => cache_proposals(db, proposals),
+        Err(e) => error!("Failed to update live tallies: {}", e),
+    }
+}
+
+async fn update_live_tallies(
+    contact: &Contact,
+    proposals: &mut [ProposalInfo],
+    now: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut gov = GovQueryClient::connect(contact.get_url()).await?;
+    let params = fetch_tally_params(&mut gov).await?;
+    let bonded_tokens = fetch_bonded_tokens(contact).await?;
+
+    for proposal in proposals.iter_mut().filter(|p| p.is_active()) {
+        let tally = gov
+            .tally_result(QueryTallyResultRequest {
+                proposal_id: proposal.proposal_id,
+            })
+            .await?
+            .into_inner()
+            .tally;
+        proposal.live_tally = tally
+            .map(|t| LiveTally::new(TallyResult::from(t), bonded_tokens.clone(), &params, now));
+    }
+    Ok(())
+}
+
+pub async fn fetch_tally_params(
+    gov: &mut GovQueryClient<tonic::transport::Channel>,
+) -> Result<TallyParams, Box<dyn std::error::Error>> {
+    let params = gov
+        .params(QueryParamsRequest {
+            params_type: "tallying".to_string(),
+        })
+        .await?
+        .into_inner()
+        .tally_params
+        .ok_or("Chain returned no tally params")?;
+    Ok(TallyParams {
+        quorum: parse_dec_bytes(&params.quorum)?,
+        threshold: parse_dec_bytes(&params.threshold)?,
+        veto_threshold: parse_dec_bytes(&params.veto_threshold)?,
+    })
+}
+
+/// The amount of aalthea currently bonded to validators
+pub async fn fetch_bonded_tokens(contact: &Contact) -> Result<String, Box<dyn std::error::Error>> {
+    let mut staking = StakingQueryClient::connect(contact.get_url()).await?;
+    let pool = staking
+        .pool(QueryPoolRequest {})
+        .await?
+        .into_inner()
+        .pool
+        .ok_or("Chain returned no staking pool")?;
+    Ok(pool.bonded_tokens)
+}
+
+// The v1beta1 tally params are sdk.Dec values stored as bytes
+fn parse_dec_bytes(input: &[u8]) -> Result<f64, Box<dyn std::error::Error>> {
+    let text = String::from_utf8(input.to_vec())?;
+    Ok(Amount::from_sdk_dec(&text)?.to_f64())
+}
+
 pub async fn fetch_proposals_filtered(
     db: &rocksdb::DB,
     contact: &deep_space::Contact,
@@ -211,20 +517,363 @@ pub async fn fetch_proposals_filtered(
     })
 }
 
-pub fn start_proposal_cache_refresh_task(db: Arc<DB>, contact: Contact) {
+const PROPOSAL_VOTES_KEY_PREFIX: &str = "proposal_votes_";
+
+/// The gov module's VoteOption, 1 = Yes, 2 = Abstain, 3 = No, 4 = NoWithVeto
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WeightedVote {
+    pub option: i32,
+    pub weight: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VoteInfo {
+    pub proposal_id: u64,
+    pub voter: String,
+    pub options: Vec<WeightedVote>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProposalVotes {
+    pub proposal_id: u64,
+    pub votes: Vec<VoteInfo>,
+    pub last_updated: u64,
+}
+
+impl Cached for ProposalVotes {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+/// How a single address voted on a proposal, either directly or through the validators it delegates to
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AddressVote {
+    pub proposal_id: u64,
+    /// True if the address cast its own vote, which overrides any inherited votes
+    pub voted: bool,
+    pub options: Vec<WeightedVote>,
+    /// The votes of the address's validators, applied to the stake delegated to each when the address did not vote
+    pub inherited: Vec<InheritedVote>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InheritedVote {
+    pub validator_address: String,
+    pub delegated_amount: Amount,
+    pub options: Vec<WeightedVote>,
+}
+
+impl From<Vote> for VoteInfo {
+    #[allow(deprecated)]
+    fn from(v: Vote) -> Self {
+        // Votes cast before weighted voting was introduced only set the single option field
+        let options = if v.options.is_empty() && v.option != 0 {
+            vec![WeightedVote {
+                option: v.option,
+                weight: "1.000000000000000000".to_string(),
+            }]
+        } else {
+            v.options
+                .into_iter()
+                .map(|o| WeightedVote {
+                    option: o.option,
+                    weight: o.weight,
+                })
+                .collect()
+        };
+        VoteInfo {
+            proposal_id: v.proposal_id,
+            voter: v.voter,
+            options,
+        }
+    }
+}
+
+/// Fetches every vote cast on a proposal. The chain prunes votes once a proposal is tallied,
+/// so only proposals in their voting period will have any votes
+pub async fn fetch_proposal_votes(
+    db: &rocksdb::DB,
+    contact: &Contact,
+    proposal_id: u64,
+) -> Result<ProposalVotes, Box<dyn std::error::Error>> {
+    if let Some(votes) = get_cached_proposal_votes(db, proposal_id) {
+        return Ok(votes);
+    }
+
+    let mut client = GovQueryClient::connect(contact.get_url()).await?;
+    let mut votes = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let response = client
+            .votes(QueryVotesRequest {
+                proposal_id,
+                pagination: Some(PageRequest {
+                    key: next_key,
+                    offset: 0,
+                    limit: 1000,
+                    count_total: false,
+                    reverse: false,
+                }),
+            })
+            .await?
+            .into_inner();
+        votes.extend(response.votes.into_iter().map(VoteInfo::from));
+        match response.pagination {
+            Some(p) if !p.next_key.is_empty() => next_key = p.next_key,
+            _ => break,
+        }
+    }
+
+    let response = ProposalVotes {
+        proposal_id,
+        votes,
+        last_updated: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    cache_proposal_votes(db, &response);
+    info!(
+        "Fetched {} votes for proposal {}",
+        response.votes.len(),
+        proposal_id
+    );
+    Ok(response)
+}
+
+fn get_cached_proposal_votes(db: &rocksdb::DB, proposal_id: u64) -> Option<ProposalVotes> {
+    let key = format!("{}{}", PROPOSAL_VOTES_KEY_PREFIX, proposal_id);
+    get_cached(db, key.as_bytes(), CACHE_DURATION)
+}
+
+fn cache_proposal_votes(db: &rocksdb::DB, votes: &ProposalVotes) {
+    let key = format!("{}{}", PROPOSAL_VOTES_KEY_PREFIX, votes.proposal_id);
+    let encoded = bincode::serialize(votes).unwrap();
+    db.put(key.as_bytes(), encoded).unwrap();
+}
+
+/// Determines how `address` voted on every proposal currently in its voting period. If the address
+/// did not vote, the votes of the validators it delegates to are reported instead, since those
+/// votes are applied to its delegated stake when the proposal is tallied
+pub async fn fetch_address_votes(
+    db: &rocksdb::DB,
+    contact: &Contact,
+    address: CosmosAddress,
+) -> Result<Vec<AddressVote>, Box<dyn std::error::Error>> {
+    let voter = address.to_string();
+    let proposals = fetch_proposals_filtered(db, contact, Some(true)).await?;
+    let delegations = fetch_delegations(db, contact, address).await?.delegations;
+
+    let mut results = Vec::new();
+    for proposal in proposals {
+        let votes = fetch_proposal_votes(db, contact, proposal.proposal_id).await?;
+        if let Some(vote) = votes.votes.iter().find(|v| v.voter == voter) {
+            results.push(AddressVote {
+                proposal_id: proposal.proposal_id,
+                voted: true,
+                options: vote.options.clone(),
+                inherited: Vec::new(),
+            });
+            continue;
+        }
+
+        let mut inherited = Vec::new();
+        for delegation in delegations.iter() {
+            // A validator votes with the account that shares its operator address's bytes
+            let validator_account =
+                CosmosAddress::from_bech32(delegation.delegation.validator_address.clone())?
+                    .to_bech32(ALTHEA_PREFIX)?;
+            if let Some(vote) = votes.votes.iter().find(|v| v.voter == validator_account) {
+                inherited.push(InheritedVote {
+                    validator_address: delegation.delegation.validator_address.clone(),
+                    delegated_amount: delegation.balance.amount.clone(),
+                    options: vote.options.clone(),
+                });
+            }
+        }
+        results.push(AddressVote {
+            proposal_id: proposal.proposal_id,
+            voted: false,
+            options: Vec::new(),
+            inherited,
+        });
+    }
+    Ok(results)
+}
+
+const PROPOSAL_DEPOSITS_KEY_PREFIX: &str = "proposal_deposits_";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DepositInfo {
+    pub depositor: String,
+    pub amount: Vec<Coin>,
+}
+
+/// The deposits made on a proposal and how they compare to the minimum deposit required to enter voting
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProposalDeposits {
+    pub proposal_id: u64,
+    pub status: i32,
+    pub deposits: Vec<DepositInfo>,
+    pub total_deposit: Vec<Coin>,
+    pub min_deposit: Vec<Coin>,
+    /// The amount of each min_deposit denom still required before the proposal enters voting,
+    /// empty once the minimum has been met
+    pub remaining_deposit: Vec<Coin>,
+    pub deposit_end_time: Option<String>,
+    pub last_updated: u64,
+}
+
+impl Cached for ProposalDeposits {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+/// Fetches the deposits made on a proposal, returns None if the proposal does not exist. The chain removes
+/// deposits once they are refunded or burned, so only proposals that have not finished voting will list depositors
+pub async fn fetch_proposal_deposits(
+    db: &rocksdb::DB,
+    contact: &Contact,
+    proposal_id: u64,
+) -> Result<Option<ProposalDeposits>, Box<dyn std::error::Error>> {
+    if let Some(deposits) = get_cached_proposal_deposits(db, proposal_id) {
+        return Ok(Some(deposits));
+    }
+    let proposal = match fetch_proposals(db, contact)
+        .await?
+        .into_iter()
+        .find(|p| p.proposal_id == proposal_id)
+    {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+
+    let mut gov = GovQueryClient::connect(contact.get_url()).await?;
+    let mut deposits = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let response = gov
+            .deposits(QueryDepositsRequest {
+                proposal_id,
+                pagination: Some(PageRequest {
+                    key: next_key,
+                    offset: 0,
+                    limit: 1000,
+                    count_total: false,
+                    reverse: false,
+                }),
+            })
+            .await?
+            .into_inner();
+        deposits.extend(response.deposits.into_iter().map(|d| DepositInfo {
+            depositor: d.depositor,
+            amount: d.amount.into_iter().map(Coin::from).collect(),
+        }));
+        match response.pagination {
+            Some(p) if !p.next_key.is_empty() => next_key = p.next_key,
+            _ => break,
+        }
+    }
+    let min_deposit = fetch_min_deposit(&mut gov).await?;
+    let remaining_deposit = remaining_deposit(&min_deposit, &proposal.total_deposit);
+
+    let response = ProposalDeposits {
+        proposal_id,
+        status: proposal.status,
+        deposits,
+        total_deposit: proposal.total_deposit,
+        min_deposit,
+        remaining_deposit,
+        deposit_end_time: proposal.deposit_end_time,
+        last_updated: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    cache_proposal_deposits(db, &response);
+    Ok(Some(response))
+}
+
+pub async fn fetch_min_deposit(
+    gov: &mut GovQueryClient<tonic::transport::Channel>,
+) -> Result<Vec<Coin>, Box<dyn std::error::Error>> {
+    let params = gov
+        .params(QueryParamsRequest {
+            params_type: "deposit".to_string(),
+        })
+        .await?
+        .into_inner()
+        .deposit_params
+        .ok_or("Chain returned no deposit params")?;
+    Ok(params.min_deposit.into_iter().map(Coin::from).collect())
+}
+
+// The amount of each denom in `min_deposit` not yet covered by `total_deposit`
+fn remaining_deposit(min_deposit: &[Coin], total_deposit: &[Coin]) -> Vec<Coin> {
+    min_deposit
+        .iter()
+        .filter_map(|min| {
+            let required = min.amount.parse::<u128>().unwrap_or_default();
+            let deposited = total_deposit
+                .iter()
+                .filter(|c| c.denom == min.denom)
+                .map(|c| c.amount.parse::<u128>().unwrap_or_default())
+                .sum::<u128>();
+            let remaining = required.saturating_sub(deposited);
+            if remaining == 0 {
+                None
+            } else {
+                Some(Coin {
+                    denom: min.denom.clone(),
+                    amount: remaining.to_string(),
+                })
+            }
+        })
+        .collect()
+}
+
+fn get_cached_proposal_deposits(db: &rocksdb::DB, proposal_id: u64) -> Option<ProposalDeposits> {
+    let key = format!("{}{}", PROPOSAL_DEPOSITS_KEY_PREFIX, proposal_id);
+    get_cached(db, key.as_bytes(), CACHE_DURATION)
+}
+
+fn cache_proposal_deposits(db: &rocksdb::DB, deposits: &ProposalDeposits) {
+    let key = format!("{}{}", PROPOSAL_DEPOSITS_KEY_PREFIX, deposits.proposal_id);
+    let encoded = bincode::serialize(deposits).unwrap();
+    db.put(key.as_bytes(), encoded).unwrap();
+}
+
+pub fn start_proposal_cache_refresh_task(db: Arc<DB>, contact: Contact, webhooks: Vec<Webhook>) {
     tokio::spawn(async move {
         loop {
-            // Check if cache needs refresh
-            if get_cached_proposals(&db).is_none() {
-                info!("Proposal cache expired, refreshing...");
-                match fetch_proposals(&db, &contact).await {
-                    Ok(_) => info!("Successfully refreshed proposal cache"),
-                    Err(e) => error!("Failed to refresh proposal cache: {}", e),
+            // Check if cache needs refresh, live tallies are refreshed on every pass while voting is open
+            let proposals = match get_cached_proposals(&db) {
+                Some(mut proposals) => {
+                    refresh_live_tallies(&db, &contact, &mut proposals).await;
+                    Some(proposals)
+                }
+                None => {
+                    info!("Proposal cache expired, refreshing...");
+                    match fetch_proposals(&db, &contact).await {
+                        Ok(proposals) => {
+                            info!("Successfully refreshed proposal cache");
+                            Some(proposals)
+                        }
+                        Err(e) => {
+                            error!("Failed to refresh proposal cache: {}", e);
+                            None
+                        }
+                    }
                 }
+            };
+            if let Some(proposals) = proposals {
+                notify_status_changes(&db, &webhooks, &proposals);
             }
 
-            // Sleep for the cache duration before refreshing again
-            sleep(tokio::time::Duration::from_secs(CACHE_DURATION)).await;
+            // Sleep for the live tally duration before refreshing again
+            sleep(tokio::time::Duration::from_secs(LIVE_TALLY_CACHE_DURATION)).await;
         }
     });
 }
@@ -3,27 +3,49 @@ use crate::Opts;
 use actix_web::rt::System;
 use actix_web::web;
 use ambient::pools::InitPoolEvent;
-use ambient::{query_latest, search_for_pools, search_for_positions};
+use ambient::pricing::update_token_prices;
+use ambient::stats::{backfill_participants, update_dex_stats};
+use ambient::status::verify_dex_status;
+use ambient::{
+    query_latest, search_for_governance_events, search_for_pools, search_for_positions,
+    search_for_swaps, search_for_templates, validate_event_layouts,
+};
+use balances::start_balances_eviction_task;
 use clarity::{Address, Uint256};
 use database::pools::get_init_pools;
+use database::positions::build_active_ranged_positions;
+use database::templates::get_pool_templates;
 use database::{get_latest_searched_block, save_latest_searched_block};
 use deep_space::Contact;
 use log::{error, info};
+use notifications::{get_webhooks, start_notification_delivery_task};
+use serde::de::DeserializeOwned;
 use std::cmp::min;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use token_mappings::{get_token_overrides, refresh_token_registry};
+use transactions::start_account_txs_eviction_task;
 use web30::client::Web3;
 
 pub mod abi_util;
+pub mod account;
 pub mod ambient;
+pub mod amount;
+pub mod balances;
 pub mod database;
 pub mod delegations;
 pub mod endpoints;
 pub mod error;
+pub mod gov_v1;
 pub mod governance;
+pub mod notifications;
+pub mod proposal_content;
+pub mod staking;
 pub mod token_mappings;
+pub mod transactions;
+pub mod validator_history;
 pub mod validators;
 
 pub const ALTHEA_GRPC_URL: &str = "http://66.172.36.142:3890";
@@ -49,9 +71,16 @@ const DEFAULT_TOKEN_ADDRESSES: &[&str] = &[
     "0x30dA8589BFa1E509A319489E014d384b87815D89",
     "0x9676519d99E390A180Ab1445d5d857E3f6869065",
 ];
+/// USDC and USDT, the stablecoins tokens are priced against unless others are configured
+const DEFAULT_STABLECOINS: &[&str] = &[
+    "0x80b5a32E4F032B2a058b4F29EC95EEfEEB87aDcd",
+    "0xd567B3d7B8FE3C79a1AD8dA978812cfC4Fa05e75",
+];
 /// These are the poolIdx values used when creating pools in our scripts/tests.
 /// Template creation requires governance permission (Ops role) but any user can create a
 /// pool using these templates permissionlessly.
+/// Templates announced by SetPoolTemplate events are discovered automatically, these are kept
+/// for databases whose indexing began before template events were indexed.
 const DEFAULT_POOL_TEMPLATES: &[u64] = &[36000, 36001];
 const DEFAULT_QUERIER: &str = "0xbf660843528035a5a4921534e156a27e64b231fe";
 
@@ -65,22 +94,133 @@ pub fn get_althea_web3(timeout: Duration) -> Web3 {
     Web3::new(ALTHEA_ETH_RPC_URL, timeout)
 }
 
+/// Formats a protobuf timestamp as RFC 3339, the format the chain's REST API uses
+pub fn format_timestamp(t: prost_types::Timestamp) -> Option<String> {
+    chrono::DateTime::from_timestamp(t.seconds, 0)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true))
+}
+
+/// A cached response which records when it was fetched
+pub trait Cached {
+    fn last_updated(&self) -> u64;
+}
+
+/// Lists are cached as a whole, so the first entry's time applies to all of them and an empty list is expired
+impl<T: Cached> Cached for Vec<T> {
+    fn last_updated(&self) -> u64 {
+        self.first().map(|t| t.last_updated()).unwrap_or_default()
+    }
+}
+
+/// Returns the value cached under `key` if it was fetched less than `ttl` seconds ago. Values written
+/// before a change to `T` no longer decode and are treated as expired
+pub fn get_cached<T: DeserializeOwned + Cached>(
+    db: &rocksdb::DB,
+    key: &[u8],
+    ttl: u64,
+) -> Option<T> {
+    let data = db.get(key).unwrap()?;
+    let cached: T = bincode::deserialize(&data).ok()?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    if now.saturating_sub(cached.last_updated()) < ttl {
+        Some(cached)
+    } else {
+        None
+    }
+}
+
+/// Every `ttl` seconds, deletes the values cached under `prefix` that have expired. `name` describes the
+/// values in logs
+pub fn start_eviction_task<T: DeserializeOwned + Cached + 'static>(
+    db: Arc<rocksdb::DB>,
+    prefix: &'static str,
+    ttl: u64,
+    name: &'static str,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(ttl)).await;
+            match evict_expired::<T>(&db, prefix, ttl) {
+                Ok(0) => {}
+                Ok(evicted) => info!("Evicted {} expired {}", evicted, name),
+                Err(e) => error!("Failed to evict expired {}: {}", name, e),
+            }
+        }
+    });
+}
+
+/// Deletes the values cached under `prefix` that were fetched `ttl` or more seconds ago, or no longer decode,
+/// and returns how many were deleted
+pub fn evict_expired<T: DeserializeOwned + Cached>(
+    db: &rocksdb::DB,
+    prefix: &str,
+    ttl: u64,
+) -> Result<u64, rocksdb::Error> {
+    let prefix = prefix.as_bytes();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut batch = rocksdb::WriteBatch::default();
+    let mut evicted = 0;
+    for entry in db.prefix_iterator(prefix) {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix) {
+                    break;
+                }
+                let expired = match bincode::deserialize::<T>(&v) {
+                    Ok(cached) => now.saturating_sub(cached.last_updated()) >= ttl,
+                    Err(_) => true,
+                };
+                if expired {
+                    batch.delete(&k);
+                    evicted += 1;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    db.write(batch)?;
+    Ok(evicted)
+}
+
 pub fn start_ambient_indexer(opts: Opts, db: Arc<rocksdb::DB>) {
+    // A layout that disagrees with its event signature would misread every log of that event
+    validate_event_layouts().expect("Invalid event layout");
     let tokens = get_tokens(&opts);
-    let templates = get_templates(&opts);
+    let stablecoins = get_stablecoins(&opts);
 
     // Start cache refresh tasks
     let contact = get_althea_contact(TIMEOUT);
     start_validator_cache_refresh_task(db.clone(), contact.clone());
-    start_proposal_cache_refresh_task(db.clone(), contact.clone());
+    let webhooks = get_webhooks(&opts);
+    start_proposal_cache_refresh_task(db.clone(), contact.clone(), webhooks.clone());
+    start_notification_delivery_task(db.clone(), webhooks);
     start_delegation_cache_refresh_task(db.clone(), contact);
+    start_account_txs_eviction_task(db.clone());
+    start_balances_eviction_task(db.clone());
 
     thread::spawn(move || {
         let db = db.clone();
         let runner = System::new();
 
         let web3 = get_althea_web3(TIMEOUT);
+        let contact = get_althea_contact(TIMEOUT);
+        let token_overrides = get_token_overrides(&opts);
+        build_active_ranged_positions(&db);
+        backfill_participants(&db);
         runner.block_on(async move {
+            // Fields whose governance events were indexed before they were tracked are corrected at startup,
+            // and the status is checked again once caught up since the events replayed until then may
+            // overwrite the correction
+            if let Err(e) = verify_dex_status(&db, &web3).await {
+                error!("Error verifying dex status against the contract: {}", e);
+            }
+            let mut dex_status_verified = false;
             loop {
                 let start_block =
                     get_latest_searched_block(&db).unwrap_or(DEFAULT_START_SEARCH_BLOCK.into());
@@ -90,30 +230,61 @@ pub fn start_ambient_indexer(opts: Opts, db: Arc<rocksdb::DB>) {
                     thread::sleep(Duration::from_secs(10));
                     continue;
                 }
-                let end_block = min(
-                    start_block + DEFAULT_SEARCH_RANGE.into(),
-                    current_block.unwrap(),
-                );
+                let current_block = current_block.unwrap();
+                let end_block = min(start_block + DEFAULT_SEARCH_RANGE.into(), current_block);
+                if let Err(e) =
+                    search_for_governance_events(&db, &web3, start_block, end_block).await
+                {
+                    error!("Error searching for governance events: {}", e);
+                }
+                if let Err(e) = search_for_templates(&db, &web3, start_block, end_block).await {
+                    error!("Error searching for pool templates: {}", e);
+                }
+                // Templates are rediscovered each pass so that newly announced templates are indexed
+                let templates = get_templates(&opts, &db);
                 if let Err(e) = search_for_pools(&db, &web3, start_block, end_block).await {
                     error!("Error searching for pools: {}", e);
                 }
+                refresh_token_registry(&db, &web3, &contact, &token_overrides).await;
                 if let Err(e) =
                     search_for_positions(&db, &web3, &tokens, &templates, start_block, end_block)
                         .await
                 {
                     error!("Error searching for positions: {}", e);
                 }
+                if let Err(e) =
+                    search_for_swaps(&db, &web3, &tokens, &templates, start_block, end_block).await
+                {
+                    error!("Error searching for swaps: {}", e);
+                }
                 save_latest_searched_block(&db, end_block);
 
+                // The status is checked against the contract again once the governance log has caught up
+                if !dex_status_verified && end_block == current_block {
+                    match verify_dex_status(&db, &web3).await {
+                        Ok(_) => dex_status_verified = true,
+                        Err(e) => error!("Error verifying dex status against the contract: {}", e),
+                    }
+                }
+
                 if end_block != start_block {
                     let pools = get_init_pools(&db);
                     let pools = pools
                         .iter()
                         .map(|p| (p.base, p.quote, p.pool_idx))
                         .collect::<Vec<_>>();
-                    if let Err(e) = query_latest(&db, &web3, &pools).await {
+                    // Prices are only recorded into pool history once the indexer has caught up,
+                    // before then the queried prices do not correspond to end_block
+                    let snapshot_block = if end_block == current_block {
+                        Some(end_block)
+                    } else {
+                        None
+                    };
+                    if let Err(e) = query_latest(&db, &web3, &pools, snapshot_block).await {
                         error!("Error querying latest: {}", e);
                     }
+                    update_token_prices(&db, &stablecoins);
+                    update_dex_stats(&db);
                 }
 
                 if opts.compact {
@@ -143,21 +314,57 @@ fn get_tokens(opts: &Opts) -> Vec<Address> {
     }
 }
 
-fn get_templates(opts: &Opts) -> Vec<Uint256> {
-    if opts.pool_templates.is_empty() {
-        DEFAULT_POOL_TEMPLATES.to_vec()
+fn get_stablecoins(opts: &Opts) -> Vec<Address> {
+    if opts.stablecoins.is_empty() {
+        DEFAULT_STABLECOINS
+            .iter()
+            .map(|v| Address::from_str(v).unwrap())
+            .collect::<Vec<_>>()
     } else {
-        opts.pool_templates.clone()
+        opts.stablecoins.clone()
+    }
+}
+
+// Returns the configured templates if any were given, otherwise the indexed templates along with the defaults.
+// Disabled templates are included since pools created before the template was disabled remain usable.
+fn get_templates(opts: &Opts, db: &rocksdb::DB) -> Vec<Uint256> {
+    if !opts.pool_templates.is_empty() {
+        return opts
+            .pool_templates
+            .iter()
+            .map(|v| (*v).into())
+            .collect::<Vec<_>>();
+    }
+    let mut templates = DEFAULT_POOL_TEMPLATES
+        .iter()
+        .map(|v| (*v).into())
+        .collect::<Vec<Uint256>>();
+    for template in get_pool_templates(db) {
+        if !templates.contains(&template.pool_idx) {
+            templates.push(template.pool_idx);
+        }
     }
-    .iter()
-    .map(|v| (*v).into())
-    .collect::<Vec<_>>()
+    templates
 }
 
 pub fn register_endpoints(cfg: &mut web::ServiceConfig) {
     cfg.service(endpoints::get_validators)
+        .service(endpoints::get_validator_rank_changes)
+        .service(endpoints::get_validator_detail)
+        .service(endpoints::get_validator_history)
+        .service(endpoints::get_validator_commission)
+        .service(endpoints::get_staking_apr)
         .service(endpoints::get_proposals)
-        .service(endpoints::get_delegations);
+        .service(endpoints::get_proposal_votes)
+        .service(endpoints::get_proposal_deposits)
+        .service(endpoints::get_address_votes)
+        .service(endpoints::get_delegations)
+        .service(endpoints::get_delegation_cache_stats)
+        .service(endpoints::get_balances)
+        .service(endpoints::get_account)
+        .service(endpoints::get_account_txs)
+        .service(endpoints::get_tokens)
+        .service(endpoints::get_prices);
 }
 
 pub use delegations::start_delegation_cache_refresh_task;
@@ -0,0 +1,341 @@
+// Sends webhook notifications when a governance proposal changes status
+
+use futures::stream::{self, StreamExt};
+use log::{error, info, warn};
+use rocksdb::DB;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+
+use crate::althea::governance::ProposalInfo;
+use crate::Opts;
+
+const PROPOSAL_STATUS_SNAPSHOT_KEY: &str = "proposal_status_snapshot";
+const PENDING_NOTIFICATION_KEY_PREFIX: &str = "proposal_notification_pending_";
+/// The number of times delivery to a single webhook is attempted before the notification is dropped,
+/// with the backoff capped at an hour this spans roughly eight hours
+const DELIVERY_ATTEMPTS: u32 = 16;
+/// Seconds between passes over the queue, also the delay before the first retry
+const DELIVERY_INTERVAL: u64 = 10;
+const MAX_DELIVERY_BACKOFF: u64 = 3600;
+const DELIVERY_CONCURRENCY: usize = 4;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The payload format expected by a webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookFormat {
+    /// The ProposalNotification serialized as JSON
+    Generic,
+    Discord,
+    Slack,
+}
+
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub url: String,
+    pub format: WebhookFormat,
+}
+
+/// Collects the webhooks configured on the command line
+pub fn get_webhooks(opts: &Opts) -> Vec<Webhook> {
+    let with_format = |urls: &[String], format: WebhookFormat| {
+        urls.iter()
+            .map(|url| Webhook {
+                url: url.clone(),
+                format,
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut webhooks = with_format(&opts.webhook_urls, WebhookFormat::Generic);
+    webhooks.extend(with_format(
+        &opts.discord_webhook_urls,
+        WebhookFormat::Discord,
+    ));
+    webhooks.extend(with_format(&opts.slack_webhook_urls, WebhookFormat::Slack));
+    webhooks
+}
+
+/// A proposal status transition, sent as-is to generic webhooks
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProposalNotification {
+    /// One of deposit_period, voting_period, passed, rejected or failed
+    pub event: String,
+    pub proposal_id: u64,
+    pub title: String,
+    /// The status the proposal was in at the previous refresh, None for newly submitted proposals
+    pub previous_status: Option<i32>,
+    pub status: i32,
+    pub deposit_end_time: Option<String>,
+    pub voting_end_time: Option<String>,
+}
+
+impl ProposalNotification {
+    fn new(proposal: &ProposalInfo, previous_status: Option<i32>) -> Option<Self> {
+        let event = match proposal.status {
+            1 => "deposit_period",
+            2 => "voting_period",
+            3 => "passed",
+            4 => "rejected",
+            5 => "failed",
+            _ => return None,
+        };
+        Some(ProposalNotification {
+            event: event.to_string(),
+            proposal_id: proposal.proposal_id,
+            title: proposal
+                .content
+                .as_ref()
+                .map(|c| c.title.clone())
+                .unwrap_or_default(),
+            previous_status,
+            status: proposal.status,
+            deposit_end_time: proposal.deposit_end_time.clone(),
+            voting_end_time: proposal.voting_end_time.clone(),
+        })
+    }
+
+    /// A human readable summary used by the chat formatted payloads
+    pub fn message(&self) -> String {
+        let action = match self.status {
+            1 => format!(
+                "has been submitted and is accepting deposits until {}",
+                self.deposit_end_time.as_deref().unwrap_or("unknown")
+            ),
+            2 => format!(
+                "has entered its voting period, voting ends {}",
+                self.voting_end_time.as_deref().unwrap_or("unknown")
+            ),
+            3 => "has passed".to_string(),
+            4 => "has been rejected".to_string(),
+            _ => "has failed".to_string(),
+        };
+        format!(
+            "Proposal #{} \"{}\" {}",
+            self.proposal_id, self.title, action
+        )
+    }
+
+    fn payload(&self, format: WebhookFormat) -> serde_json::Value {
+        match format {
+            WebhookFormat::Generic => json!(self),
+            WebhookFormat::Discord => json!({ "content": self.message() }),
+            WebhookFormat::Slack => json!({ "text": self.message() }),
+        }
+    }
+}
+
+/// Compares `proposals` against the statuses seen at the previous refresh and queues a notification of
+/// each change for every configured webhook. The first refresh only records the current statuses so that
+/// existing proposals are not announced. Queued notifications are sent by the delivery task, so the
+/// status snapshot only advances once every change it covers has been persisted
+pub fn notify_status_changes(db: &rocksdb::DB, webhooks: &[Webhook], proposals: &[ProposalInfo]) {
+    let current: Vec<(u64, i32)> = proposals
+        .iter()
+        .map(|p| (p.proposal_id, p.status))
+        .collect();
+    let previous = match get_status_snapshot(db) {
+        Some(previous) => previous,
+        None => {
+            save_status_snapshot(db, &current);
+            return;
+        }
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut batch = rocksdb::WriteBatch::default();
+    for proposal in proposals {
+        let previous_status = previous
+            .iter()
+            .find(|(id, _)| *id == proposal.proposal_id)
+            .map(|(_, status)| *status);
+        if previous_status == Some(proposal.status) {
+            continue;
+        }
+        let Some(notification) = ProposalNotification::new(proposal, previous_status) else {
+            continue;
+        };
+        info!("Proposal status change: {}", notification.message());
+        for webhook in webhooks {
+            let pending = PendingDelivery {
+                notification: notification.clone(),
+                webhook_hash: webhook_hash(webhook),
+                attempts: 0,
+                next_attempt: now,
+            };
+            batch.put(
+                pending.key().as_bytes(),
+                bincode::serialize(&pending).unwrap(),
+            );
+        }
+    }
+    batch.put(
+        PROPOSAL_STATUS_SNAPSHOT_KEY.as_bytes(),
+        bincode::serialize(&current).unwrap(),
+    );
+    if let Err(e) = db.write(batch) {
+        error!("Failed to queue proposal notifications: {}", e);
+    }
+}
+
+/// A notification waiting to be delivered to the webhook whose url hashes to `webhook_hash`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct PendingDelivery {
+    notification: ProposalNotification,
+    webhook_hash: String,
+    /// The number of failed deliveries so far
+    attempts: u32,
+    /// The unix time before which delivery is not retried
+    next_attempt: u64,
+}
+
+impl PendingDelivery {
+    fn key(&self) -> String {
+        format!(
+            "{}{}_{}_{}",
+            PENDING_NOTIFICATION_KEY_PREFIX,
+            self.notification.proposal_id,
+            self.notification.status,
+            self.webhook_hash
+        )
+    }
+
+    // Exponential backoff from DELIVERY_INTERVAL, capped at MAX_DELIVERY_BACKOFF
+    fn backoff(&self) -> u64 {
+        DELIVERY_INTERVAL
+            .saturating_mul(2u64.saturating_pow(self.attempts))
+            .min(MAX_DELIVERY_BACKOFF)
+    }
+}
+
+// Webhook urls often contain secrets, so the url is hashed rather than stored in the database
+fn webhook_hash(webhook: &Webhook) -> String {
+    sha256::digest(webhook.url.as_str())
+}
+
+fn get_pending_deliveries(db: &rocksdb::DB) -> Vec<PendingDelivery> {
+    let prefix = PENDING_NOTIFICATION_KEY_PREFIX.as_bytes();
+    let mut pending = vec![];
+    for entry in db.prefix_iterator(prefix) {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix) {
+                    break;
+                }
+                match bincode::deserialize::<PendingDelivery>(&v) {
+                    Ok(p) => pending.push(p),
+                    Err(_) => db.delete(&k).unwrap(),
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    pending
+}
+
+/// Delivers queued notifications in the background, retrying failed deliveries on later passes with
+/// exponential backoff so that a webhook outage delays notifications rather than losing them
+pub fn start_notification_delivery_task(db: Arc<DB>, webhooks: Vec<Webhook>) {
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder().timeout(DELIVERY_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                error!("Failed to create webhook client: {}", e);
+                return;
+            }
+        };
+        loop {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let due: Vec<PendingDelivery> = get_pending_deliveries(&db)
+                .into_iter()
+                .filter(|p| p.next_attempt <= now)
+                .collect();
+            stream::iter(due)
+                .for_each_concurrent(DELIVERY_CONCURRENCY, |pending| {
+                    deliver(&db, &client, &webhooks, pending, now)
+                })
+                .await;
+
+            sleep(Duration::from_secs(DELIVERY_INTERVAL)).await;
+        }
+    });
+}
+
+// Makes a single delivery attempt, removing the notification from the queue once it is delivered or
+// has exhausted its attempts and otherwise scheduling the next attempt
+async fn deliver(
+    db: &rocksdb::DB,
+    client: &reqwest::Client,
+    webhooks: &[Webhook],
+    mut pending: PendingDelivery,
+    now: u64,
+) {
+    let key = pending.key();
+    let notification = &pending.notification;
+    // The webhook may have been removed from the configuration since the notification was queued
+    let Some(webhook) = webhooks
+        .iter()
+        .find(|w| webhook_hash(w) == pending.webhook_hash)
+    else {
+        db.delete(key.as_bytes()).unwrap();
+        return;
+    };
+
+    let result = client
+        .post(&webhook.url)
+        .json(&notification.payload(webhook.format))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    let e = match result {
+        Ok(_) => {
+            db.delete(key.as_bytes()).unwrap();
+            return;
+        }
+        Err(e) => e,
+    };
+
+    pending.attempts += 1;
+    if pending.attempts >= DELIVERY_ATTEMPTS {
+        error!(
+            "Giving up on {:?} webhook delivery of proposal {} after {} attempts: {}",
+            webhook.format,
+            notification.proposal_id,
+            pending.attempts,
+            e.without_url()
+        );
+        db.delete(key.as_bytes()).unwrap();
+        return;
+    }
+    warn!(
+        "{:?} webhook delivery of proposal {} failed (attempt {}/{}): {}",
+        webhook.format,
+        notification.proposal_id,
+        pending.attempts,
+        DELIVERY_ATTEMPTS,
+        e.without_url()
+    );
+    pending.next_attempt = now + pending.backoff();
+    db.put(key.as_bytes(), bincode::serialize(&pending).unwrap())
+        .unwrap();
+}
+
+fn get_status_snapshot(db: &rocksdb::DB) -> Option<Vec<(u64, i32)>> {
+    match db.get(PROPOSAL_STATUS_SNAPSHOT_KEY.as_bytes()).unwrap() {
+        Some(data) => bincode::deserialize(&data).ok(),
+        None => None,
+    }
+}
+
+fn save_status_snapshot(db: &rocksdb::DB, statuses: &[(u64, i32)]) {
+    let encoded = bincode::serialize(statuses).unwrap();
+    db.put(PROPOSAL_STATUS_SNAPSHOT_KEY.as_bytes(), encoded)
+        .unwrap();
+}
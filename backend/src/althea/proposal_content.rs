@@ -0,0 +1,429 @@
+// Decodes the `content` of a v1beta1 proposal and the `messages` of a gov v1 proposal according to their type_url
+
+use althea_proto::althea::nativedex::v1::{
+    AuthorityTransferProposal, CollectTreasuryProposal, HotPathOpenProposal, SetSafeModeProposal,
+    SetTreasuryProposal, TransferGovernanceProposal, UpgradeProxyProposal,
+};
+use althea_proto::canto::erc20::v1::{
+    RegisterCoinProposal, RegisterErc20Proposal, ToggleTokenConversionProposal,
+};
+use cosmos_sdk_proto_althea::cosmos::bank::v1beta1::MsgSend;
+use cosmos_sdk_proto_althea::cosmos::distribution::v1beta1::CommunityPoolSpendProposal;
+use cosmos_sdk_proto_althea::cosmos::gov::v1beta1::TextProposal;
+use cosmos_sdk_proto_althea::cosmos::params::v1beta1::ParameterChangeProposal;
+use cosmos_sdk_proto_althea::cosmos::upgrade::v1beta1::{
+    MsgCancelUpgrade, MsgSoftwareUpgrade, Plan, SoftwareUpgradeProposal,
+};
+use log::warn;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::althea::gov_v1::MsgExecLegacyContent;
+use crate::althea::governance::Coin;
+
+pub const TEXT_PROPOSAL_TYPE_URL: &str = "/cosmos.gov.v1beta1.TextProposal";
+pub const PARAMETER_CHANGE_PROPOSAL_TYPE_URL: &str =
+    "/cosmos.params.v1beta1.ParameterChangeProposal";
+pub const SOFTWARE_UPGRADE_PROPOSAL_TYPE_URL: &str =
+    "/cosmos.upgrade.v1beta1.SoftwareUpgradeProposal";
+pub const COMMUNITY_POOL_SPEND_PROPOSAL_TYPE_URL: &str =
+    "/cosmos.distribution.v1beta1.CommunityPoolSpendProposal";
+pub const REGISTER_COIN_PROPOSAL_TYPE_URL: &str = "/canto.erc20.v1.RegisterCoinProposal";
+pub const REGISTER_ERC20_PROPOSAL_TYPE_URL: &str = "/canto.erc20.v1.RegisterERC20Proposal";
+pub const TOGGLE_TOKEN_CONVERSION_PROPOSAL_TYPE_URL: &str =
+    "/canto.erc20.v1.ToggleTokenConversionProposal";
+pub const UPGRADE_PROXY_PROPOSAL_TYPE_URL: &str = "/althea.nativedex.v1.UpgradeProxyProposal";
+pub const COLLECT_TREASURY_PROPOSAL_TYPE_URL: &str = "/althea.nativedex.v1.CollectTreasuryProposal";
+pub const SET_TREASURY_PROPOSAL_TYPE_URL: &str = "/althea.nativedex.v1.SetTreasuryProposal";
+pub const AUTHORITY_TRANSFER_PROPOSAL_TYPE_URL: &str =
+    "/althea.nativedex.v1.AuthorityTransferProposal";
+pub const HOT_PATH_OPEN_PROPOSAL_TYPE_URL: &str = "/althea.nativedex.v1.HotPathOpenProposal";
+pub const SET_SAFE_MODE_PROPOSAL_TYPE_URL: &str = "/althea.nativedex.v1.SetSafeModeProposal";
+pub const TRANSFER_GOVERNANCE_PROPOSAL_TYPE_URL: &str =
+    "/althea.nativedex.v1.TransferGovernanceProposal";
+
+pub const MSG_EXEC_LEGACY_CONTENT_TYPE_URL: &str = "/cosmos.gov.v1.MsgExecLegacyContent";
+pub const MSG_SEND_TYPE_URL: &str = "/cosmos.bank.v1beta1.MsgSend";
+pub const MSG_SOFTWARE_UPGRADE_TYPE_URL: &str = "/cosmos.upgrade.v1beta1.MsgSoftwareUpgrade";
+pub const MSG_CANCEL_UPGRADE_TYPE_URL: &str = "/cosmos.upgrade.v1beta1.MsgCancelUpgrade";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProposalContent {
+    pub type_url: String,
+    pub title: String,
+    pub description: String,
+    pub details: ProposalDetails,
+}
+
+/// The type specific fields of a proposal's content
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ProposalDetails {
+    Text,
+    ParameterChange {
+        changes: Vec<ParamChange>,
+    },
+    SoftwareUpgrade {
+        plan: Option<UpgradePlan>,
+    },
+    CommunityPoolSpend {
+        recipient: String,
+        amount: Vec<Coin>,
+    },
+    RegisterCoin {
+        base: Option<String>,
+        display: Option<String>,
+        symbol: Option<String>,
+    },
+    RegisterErc20 {
+        erc20_address: String,
+    },
+    ToggleTokenConversion {
+        token: String,
+    },
+    /// The nativedex module's proposals are executed as protocolCmd calls on CrocSwapDex
+    UpgradeProxy {
+        callpath_address: String,
+        callpath_index: u64,
+    },
+    CollectTreasury {
+        token_address: String,
+    },
+    SetTreasury {
+        treasury_address: String,
+    },
+    AuthorityTransfer {
+        auth_address: String,
+    },
+    HotPathOpen {
+        open: bool,
+    },
+    SetSafeMode {
+        lock_dex: bool,
+    },
+    TransferGovernance {
+        ops: String,
+        emergency: String,
+    },
+    /// A proposal type we do not decode, or content that failed to decode
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParamChange {
+    pub subspace: String,
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpgradePlan {
+    pub name: String,
+    pub height: i64,
+    pub info: String,
+}
+
+impl From<Plan> for UpgradePlan {
+    fn from(plan: Plan) -> Self {
+        UpgradePlan {
+            name: plan.name,
+            height: plan.height,
+            info: plan.info,
+        }
+    }
+}
+
+/// A single message executed by a gov v1 proposal if it passes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProposalMessage {
+    pub type_url: String,
+    pub details: MessageDetails,
+}
+
+/// The type specific fields of a gov v1 proposal message
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum MessageDetails {
+    /// A v1beta1 proposal submitted through gov v1
+    ExecLegacyContent {
+        content: Option<ProposalContent>,
+        authority: String,
+    },
+    Send {
+        from_address: String,
+        to_address: String,
+        amount: Vec<Coin>,
+    },
+    SoftwareUpgrade {
+        authority: String,
+        plan: Option<UpgradePlan>,
+    },
+    CancelUpgrade {
+        authority: String,
+    },
+    /// A message type we do not decode, or a message that failed to decode
+    Unknown,
+}
+
+impl ProposalMessage {
+    /// Decodes the protobuf encoded `value` of a proposal message according to `type_url`, messages
+    /// that cannot be decoded are logged and returned as Unknown
+    pub fn decode(type_url: String, value: &[u8]) -> ProposalMessage {
+        let details = match Self::decode_details(&type_url, value) {
+            Ok(details) => details,
+            Err(e) => {
+                warn!("Failed to decode proposal message {}: {}", type_url, e);
+                MessageDetails::Unknown
+            }
+        };
+        ProposalMessage { type_url, details }
+    }
+
+    fn decode_details(type_url: &str, value: &[u8]) -> Result<MessageDetails, prost::DecodeError> {
+        let details = match type_url {
+            MSG_EXEC_LEGACY_CONTENT_TYPE_URL => {
+                let msg = MsgExecLegacyContent::decode(value)?;
+                MessageDetails::ExecLegacyContent {
+                    content: msg
+                        .content
+                        .map(|c| ProposalContent::decode(c.type_url, &c.value)),
+                    authority: msg.authority,
+                }
+            }
+            MSG_SEND_TYPE_URL => {
+                let msg = MsgSend::decode(value)?;
+                MessageDetails::Send {
+                    from_address: msg.from_address,
+                    to_address: msg.to_address,
+                    amount: msg.amount.into_iter().map(Coin::from).collect(),
+                }
+            }
+            MSG_SOFTWARE_UPGRADE_TYPE_URL => {
+                let msg = MsgSoftwareUpgrade::decode(value)?;
+                MessageDetails::SoftwareUpgrade {
+                    authority: msg.authority,
+                    plan: msg.plan.map(UpgradePlan::from),
+                }
+            }
+            MSG_CANCEL_UPGRADE_TYPE_URL => {
+                let msg = MsgCancelUpgrade::decode(value)?;
+                MessageDetails::CancelUpgrade {
+                    authority: msg.authority,
+                }
+            }
+            _ => MessageDetails::Unknown,
+        };
+        Ok(details)
+    }
+
+    /// The legacy content carried by this message, if it is a MsgExecLegacyContent
+    pub fn legacy_content(&self) -> Option<&ProposalContent> {
+        match &self.details {
+            MessageDetails::ExecLegacyContent { content, .. } => content.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Every v1beta1 Content message begins with a title and description, this is used to recover them
+/// from content types we do not otherwise decode
+#[derive(Clone, PartialEq, Message)]
+struct GenericContent {
+    #[prost(string, tag = "1")]
+    title: String,
+    #[prost(string, tag = "2")]
+    description: String,
+}
+
+impl ProposalContent {
+    /// Decodes the protobuf encoded `value` of a proposal's content according to `type_url`, content
+    /// that cannot be decoded is logged and returned with whatever title and description can be recovered
+    pub fn decode(type_url: String, value: &[u8]) -> ProposalContent {
+        match Self::decode_typed(&type_url, value) {
+            Ok(Some((title, description, details))) => ProposalContent {
+                type_url,
+                title,
+                description,
+                details,
+            },
+            Ok(None) => Self::decode_generic(type_url, value),
+            Err(e) => {
+                warn!("Failed to decode proposal content {}: {}", type_url, e);
+                Self::decode_generic(type_url, value)
+            }
+        }
+    }
+
+    fn decode_typed(
+        type_url: &str,
+        value: &[u8],
+    ) -> Result<Option<(String, String, ProposalDetails)>, prost::DecodeError> {
+        let decoded = match type_url {
+            TEXT_PROPOSAL_TYPE_URL => {
+                let p = TextProposal::decode(value)?;
+                (p.title, p.description, ProposalDetails::Text)
+            }
+            PARAMETER_CHANGE_PROPOSAL_TYPE_URL => {
+                let p = ParameterChangeProposal::decode(value)?;
+                let changes = p
+                    .changes
+                    .into_iter()
+                    .map(|c| ParamChange {
+                        subspace: c.subspace,
+                        key: c.key,
+                        value: c.value,
+                    })
+                    .collect();
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::ParameterChange { changes },
+                )
+            }
+            SOFTWARE_UPGRADE_PROPOSAL_TYPE_URL => {
+                let p = SoftwareUpgradeProposal::decode(value)?;
+                let plan = p.plan.map(UpgradePlan::from);
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::SoftwareUpgrade { plan },
+                )
+            }
+            COMMUNITY_POOL_SPEND_PROPOSAL_TYPE_URL => {
+                let p = CommunityPoolSpendProposal::decode(value)?;
+                let amount = p.amount.into_iter().map(Coin::from).collect();
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::CommunityPoolSpend {
+                        recipient: p.recipient,
+                        amount,
+                    },
+                )
+            }
+            REGISTER_COIN_PROPOSAL_TYPE_URL => {
+                let p = RegisterCoinProposal::decode(value)?;
+                let metadata = p.metadata;
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::RegisterCoin {
+                        base: metadata.as_ref().map(|m| m.base.clone()),
+                        display: metadata.as_ref().map(|m| m.display.clone()),
+                        symbol: metadata.as_ref().map(|m| m.symbol.clone()),
+                    },
+                )
+            }
+            REGISTER_ERC20_PROPOSAL_TYPE_URL => {
+                let p = RegisterErc20Proposal::decode(value)?;
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::RegisterErc20 {
+                        erc20_address: p.erc20address,
+                    },
+                )
+            }
+            TOGGLE_TOKEN_CONVERSION_PROPOSAL_TYPE_URL => {
+                let p = ToggleTokenConversionProposal::decode(value)?;
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::ToggleTokenConversion { token: p.token },
+                )
+            }
+            UPGRADE_PROXY_PROPOSAL_TYPE_URL => {
+                let p = UpgradeProxyProposal::decode(value)?;
+                let metadata = p.metadata.unwrap_or_default();
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::UpgradeProxy {
+                        callpath_address: metadata.callpath_address,
+                        callpath_index: metadata.callpath_index,
+                    },
+                )
+            }
+            COLLECT_TREASURY_PROPOSAL_TYPE_URL => {
+                let p = CollectTreasuryProposal::decode(value)?;
+                let metadata = p.metadata.unwrap_or_default();
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::CollectTreasury {
+                        token_address: metadata.token_address,
+                    },
+                )
+            }
+            SET_TREASURY_PROPOSAL_TYPE_URL => {
+                let p = SetTreasuryProposal::decode(value)?;
+                let metadata = p.metadata.unwrap_or_default();
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::SetTreasury {
+                        treasury_address: metadata.treasury_address,
+                    },
+                )
+            }
+            AUTHORITY_TRANSFER_PROPOSAL_TYPE_URL => {
+                let p = AuthorityTransferProposal::decode(value)?;
+                let metadata = p.metadata.unwrap_or_default();
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::AuthorityTransfer {
+                        auth_address: metadata.auth_address,
+                    },
+                )
+            }
+            HOT_PATH_OPEN_PROPOSAL_TYPE_URL => {
+                let p = HotPathOpenProposal::decode(value)?;
+                let metadata = p.metadata.unwrap_or_default();
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::HotPathOpen {
+                        open: metadata.open,
+                    },
+                )
+            }
+            SET_SAFE_MODE_PROPOSAL_TYPE_URL => {
+                let p = SetSafeModeProposal::decode(value)?;
+                let metadata = p.metadata.unwrap_or_default();
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::SetSafeMode {
+                        lock_dex: metadata.lock_dex,
+                    },
+                )
+            }
+            TRANSFER_GOVERNANCE_PROPOSAL_TYPE_URL => {
+                let p = TransferGovernanceProposal::decode(value)?;
+                let metadata = p.metadata.unwrap_or_default();
+                (
+                    p.title,
+                    p.description,
+                    ProposalDetails::TransferGovernance {
+                        ops: metadata.ops,
+                        emergency: metadata.emergency,
+                    },
+                )
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(decoded))
+    }
+
+    fn decode_generic(type_url: String, value: &[u8]) -> ProposalContent {
+        let (title, description) = match GenericContent::decode(value) {
+            Ok(c) => (c.title, c.description),
+            Err(_) => (String::new(), String::new()),
+        };
+        ProposalContent {
+            type_url,
+            title,
+            description,
+            details: ProposalDetails::Unknown,
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Estimates the rewards earned by staking from the chain's mint, distribution and staking state
+
+use cosmos_sdk_proto_althea::cosmos::bank::v1beta1::{
+    query_client::QueryClient as BankQueryClient, QuerySupplyOfRequest,
+};
+use cosmos_sdk_proto_althea::cosmos::distribution::v1beta1::{
+    query_client::QueryClient as DistributionQueryClient,
+    QueryParamsRequest as DistributionParamsRequest,
+};
+use cosmos_sdk_proto_althea::cosmos::mint::v1beta1::{
+    query_client::QueryClient as MintQueryClient, QueryAnnualProvisionsRequest,
+    QueryInflationRequest,
+};
+use deep_space::Contact;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::althea::amount::Amount;
+use crate::althea::governance::fetch_bonded_tokens;
+use crate::althea::{get_cached, Cached, CACHE_DURATION};
+
+const STAKING_APR_KEY: &[u8] = b"staking_apr";
+const STAKING_DENOM: &str = "aalthea";
+
+/// The network-wide staking APR, before validator commission. All percentages are in the range 0 to 100
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StakingApr {
+    pub apr_percent: f64,
+    pub inflation_percent: f64,
+    pub bonded_ratio_percent: f64,
+    pub community_tax_percent: f64,
+    /// The aalthea minted per year at the current inflation rate
+    pub annual_provisions: Amount,
+    pub bonded_tokens: Amount,
+    pub total_supply: Amount,
+    pub last_updated: u64,
+}
+
+impl Cached for StakingApr {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+impl StakingApr {
+    /// The APR earned by delegating to a validator charging `commission_rate`, a fraction from 0 to 1
+    pub fn validator_apr(&self, commission_rate: f64) -> f64 {
+        self.apr_percent * (1.0 - commission_rate)
+    }
+}
+
+/// Returns the cached staking APR, recomputing it if the cache has expired
+pub async fn fetch_staking_apr(
+    db: &rocksdb::DB,
+    contact: &Contact,
+) -> Result<StakingApr, Box<dyn std::error::Error>> {
+    if let Some(apr) = get_cached_staking_apr(db) {
+        return Ok(apr);
+    }
+
+    let mut mint = MintQueryClient::connect(contact.get_url()).await?;
+    let annual_provisions = String::from_utf8(
+        mint.annual_provisions(QueryAnnualProvisionsRequest {})
+            .await?
+            .into_inner()
+            .annual_provisions,
+    )?;
+    let inflation = String::from_utf8(
+        mint.inflation(QueryInflationRequest {})
+            .await?
+            .into_inner()
+            .inflation,
+    )?;
+
+    let mut distribution = DistributionQueryClient::connect(contact.get_url()).await?;
+    let community_tax = distribution
+        .params(DistributionParamsRequest {})
+        .await?
+        .into_inner()
+        .params
+        .ok_or("Chain returned no distribution params")?
+        .community_tax;
+
+    let mut bank = BankQueryClient::connect(contact.get_url()).await?;
+    let total_supply = match bank
+        .supply_of(QuerySupplyOfRequest {
+            denom: STAKING_DENOM.to_string(),
+        })
+        .await?
+        .into_inner()
+        .amount
+    {
+        Some(c) => Amount::parse(&c.amount)?,
+        None => Amount::from(0u128),
+    };
+    let bonded_tokens = Amount::parse(&fetch_bonded_tokens(contact).await?)?;
+
+    // Annual provisions are a Dec, the remaining amounts are Ints
+    let annual_provisions = Amount::from_sdk_dec(&annual_provisions)?.with_scale(0);
+    let provisions = annual_provisions.to_f64();
+    let bonded = bonded_tokens.to_f64();
+    let supply = total_supply.to_f64();
+    let community_tax = Amount::from_sdk_dec(&community_tax)?.to_f64();
+    let apr = if bonded > 0.0 {
+        provisions * (1.0 - community_tax) / bonded
+    } else {
+        0.0
+    };
+    let bonded_ratio = if supply > 0.0 { bonded / supply } else { 0.0 };
+
+    let staking_apr = StakingApr {
+        apr_percent: apr * 100.0,
+        inflation_percent: Amount::from_sdk_dec(&inflation)?.to_f64() * 100.0,
+        bonded_ratio_percent: bonded_ratio * 100.0,
+        community_tax_percent: community_tax * 100.0,
+        annual_provisions,
+        bonded_tokens,
+        total_supply,
+        last_updated: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    cache_staking_apr(db, &staking_apr);
+    info!("Staking APR is {:.2}%", staking_apr.apr_percent);
+    Ok(staking_apr)
+}
+
+fn get_cached_staking_apr(db: &rocksdb::DB) -> Option<StakingApr> {
+    get_cached(db, STAKING_APR_KEY, CACHE_DURATION)
+}
+
+fn cache_staking_apr(db: &rocksdb::DB, apr: &StakingApr) {
+    let encoded = bincode::serialize(apr).unwrap();
+    db.put(STAKING_APR_KEY, encoded).unwrap();
+}
@@ -1 +1,270 @@
-const _TOKEN_MAPPINGS: &[(&str, &str, u32)] = &[("aalthea", "althea", 18)];
+// The token registry, which describes every token found in an indexed pool and maps ERC20s to the Cosmos
+// denoms they are convertible with through the erc20 module
+
+use althea_proto::canto::erc20::v1::{
+    query_client::QueryClient as Erc20QueryClient, QueryTokenPairsRequest,
+};
+use clarity::Address;
+use cosmos_sdk_proto_althea::cosmos::base::query::v1beta1::PageRequest;
+use deep_space::Contact;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use web30::client::Web3;
+
+use crate::althea::database::pools::get_init_pools;
+use crate::althea::{CACHE_DURATION, DEFAULT_QUERIER};
+use crate::Opts;
+
+/// Native and bridged denoms with their display name and exponent, the number of decimal places between
+/// the base unit and the display unit
+const TOKEN_MAPPINGS: &[(&str, &str, u32)] = &[("aalthea", "althea", 18)];
+
+const TOKEN_REGISTRY_KEY: &[u8] = b"token_registry";
+const TOKEN_METADATA_KEY_PREFIX: &str = "token_metadata_";
+
+/// The exponent of `denom`, unknown denoms are reported in their base units
+pub fn denom_exponent(denom: &str) -> u32 {
+    TOKEN_MAPPINGS
+        .iter()
+        .find(|(base, _, _)| *base == denom)
+        .map(|(_, _, exponent)| *exponent)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TokenInfo {
+    /// The ERC20 address, the zero address is the native token as in Ambient pools
+    pub address: Address,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// The Cosmos denom this token converts to and from, if the erc20 module has a token pair for it
+    pub denom: Option<String>,
+    /// Whether conversion through the token pair is currently enabled
+    pub conversion_enabled: Option<bool>,
+    pub display_name: Option<String>,
+    pub logo_uri: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TokenRegistry {
+    pub tokens: Vec<TokenInfo>,
+    pub last_updated: u64,
+}
+
+/// Static token details from the file given by --token-overrides, fields that are set replace the values
+/// read from the chain
+#[derive(Debug, Deserialize, Clone)]
+pub struct TokenOverride {
+    pub address: Address,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub display_name: Option<String>,
+    pub logo_uri: Option<String>,
+}
+
+/// Reads the token overrides file, a JSON array of TokenOverride. A missing or malformed file is logged
+/// and ignored so that the registry is still served
+pub fn get_token_overrides(opts: &Opts) -> Vec<TokenOverride> {
+    let Some(path) = &opts.token_overrides else {
+        return vec![];
+    };
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            error!("Failed to read token overrides from {}: {}", path, e);
+            return vec![];
+        }
+    };
+    match serde_json::from_str(&contents) {
+        Ok(overrides) => overrides,
+        Err(e) => {
+            error!("Failed to parse token overrides from {}: {}", path, e);
+            vec![]
+        }
+    }
+}
+
+/// Rebuilds the token registry if it has expired
+pub async fn refresh_token_registry(
+    db: &rocksdb::DB,
+    web3: &Web3,
+    contact: &Contact,
+    overrides: &[TokenOverride],
+) {
+    if let Some(registry) = get_token_registry(db) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        if now - registry.last_updated < CACHE_DURATION {
+            return;
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut addresses = vec![];
+    for pool in get_init_pools(db) {
+        for token in [pool.base, pool.quote] {
+            if seen.insert(token) {
+                addresses.push(token);
+            }
+        }
+    }
+
+    let mut tokens = vec![];
+    for address in addresses {
+        match fetch_token_metadata(db, web3, address).await {
+            Ok(token) => tokens.push(token),
+            Err(e) => warn!("Failed to fetch metadata of token {}: {}", address, e),
+        }
+    }
+
+    // A failure to query the token pairs leaves the denoms unset until the next refresh
+    match fetch_token_pairs(contact).await {
+        Ok(pairs) => {
+            for token in tokens.iter_mut() {
+                if let Some((_, denom, enabled)) =
+                    pairs.iter().find(|(a, _, _)| *a == token.address)
+                {
+                    token.denom = Some(denom.clone());
+                    token.conversion_enabled = Some(*enabled);
+                }
+            }
+        }
+        Err(e) => error!("Failed to fetch erc20 token pairs: {}", e),
+    }
+
+    for o in overrides {
+        if let Some(token) = tokens.iter_mut().find(|t| t.address == o.address) {
+            if let Some(name) = &o.name {
+                token.name = name.clone();
+            }
+            if let Some(symbol) = &o.symbol {
+                token.symbol = symbol.clone();
+            }
+            token.display_name = o.display_name.clone().or(token.display_name.take());
+            token.logo_uri = o.logo_uri.clone().or(token.logo_uri.take());
+        }
+    }
+
+    info!("Token registry contains {} tokens", tokens.len());
+    let registry = TokenRegistry {
+        tokens,
+        last_updated: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    db.put(TOKEN_REGISTRY_KEY, bincode::serialize(&registry).unwrap())
+        .unwrap();
+}
+
+/// Returns the most recently built token registry, regardless of its age
+pub fn get_token_registry(db: &rocksdb::DB) -> Option<TokenRegistry> {
+    match db.get(TOKEN_REGISTRY_KEY).unwrap() {
+        Some(data) => bincode::deserialize(&data).ok(),
+        None => None,
+    }
+}
+
+/// Looks up a single token in the registry
+pub fn get_token_info(db: &rocksdb::DB, address: Address) -> Option<TokenInfo> {
+    get_token_registry(db)?
+        .tokens
+        .into_iter()
+        .find(|t| t.address == address)
+}
+
+// ERC20 metadata does not change, so it is fetched once per token and stored without expiry
+async fn fetch_token_metadata(
+    db: &rocksdb::DB,
+    web3: &Web3,
+    address: Address,
+) -> Result<TokenInfo, Box<dyn std::error::Error>> {
+    if address == Address::default() {
+        return Ok(native_token());
+    }
+    let key = format!("{}{}", TOKEN_METADATA_KEY_PREFIX, address);
+    if let Some(data) = db.get(key.as_bytes()).unwrap() {
+        if let Ok(token) = bincode::deserialize::<TokenInfo>(&data) {
+            return Ok(token);
+        }
+    }
+
+    let caller = Address::from_str(DEFAULT_QUERIER).unwrap();
+    let name = web3.get_erc20_name(address, caller).await?;
+    let symbol = web3.get_erc20_symbol(address, caller).await?;
+    let decimals = web3.get_erc20_decimals(address, caller).await?;
+    let token = TokenInfo {
+        address,
+        name,
+        symbol,
+        decimals: decimals
+            .to_string()
+            .parse()
+            .map_err(|_| format!("Token {} has invalid decimals {}", address, decimals))?,
+        denom: None,
+        conversion_enabled: None,
+        display_name: None,
+        logo_uri: None,
+    };
+    db.put(key.as_bytes(), bincode::serialize(&token).unwrap())
+        .unwrap();
+    Ok(token)
+}
+
+// Ambient pools use the zero address for the native token
+fn native_token() -> TokenInfo {
+    let (denom, display, exponent) = TOKEN_MAPPINGS[0];
+    TokenInfo {
+        address: Address::default(),
+        name: "Althea".to_string(),
+        symbol: display.to_uppercase(),
+        decimals: exponent as u8,
+        denom: Some(denom.to_string()),
+        conversion_enabled: None,
+        display_name: None,
+        logo_uri: None,
+    }
+}
+
+/// Returns every erc20 module token pair as (erc20 address, denom, enabled)
+pub async fn fetch_token_pairs(
+    contact: &Contact,
+) -> Result<Vec<(Address, String, bool)>, Box<dyn std::error::Error>> {
+    let mut client = Erc20QueryClient::connect(contact.get_url()).await?;
+    let mut token_pairs = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let response = client
+            .token_pairs(QueryTokenPairsRequest {
+                pagination: Some(PageRequest {
+                    key: next_key,
+                    offset: 0,
+                    limit: 1000,
+                    count_total: false,
+                    reverse: false,
+                }),
+            })
+            .await?
+            .into_inner();
+        token_pairs.extend(response.token_pairs);
+        match response.pagination {
+            Some(p) if !p.next_key.is_empty() => next_key = p.next_key,
+            _ => break,
+        }
+    }
+
+    let mut pairs = vec![];
+    for pair in token_pairs {
+        match Address::from_str(&pair.erc20_address) {
+            Ok(address) => pairs.push((address, pair.denom, pair.enabled)),
+            Err(e) => warn!("Invalid erc20 address in token pair {}: {}", pair.denom, e),
+        }
+    }
+    Ok(pairs)
+}
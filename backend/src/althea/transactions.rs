@@ -0,0 +1,384 @@
+// An account's Cosmos transaction history, searched through the tx service by the events the chain
+// emits for the account and normalized into an activity feed of the message types users care about
+
+use cosmos_sdk_proto_althea::cosmos::bank::v1beta1::MsgSend;
+use cosmos_sdk_proto_althea::cosmos::base::v1beta1::Coin as ProtoCoin;
+use cosmos_sdk_proto_althea::cosmos::distribution::v1beta1::MsgWithdrawDelegatorReward;
+use cosmos_sdk_proto_althea::cosmos::gov::v1beta1::MsgVote;
+use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::{
+    MsgBeginRedelegate, MsgDelegate, MsgUndelegate,
+};
+use cosmos_sdk_proto_althea::cosmos::tx::v1beta1::{
+    service_client::ServiceClient as TxServiceClient, GetTxsEventRequest, OrderBy, Tx,
+};
+use deep_space::{Address as CosmosAddress, Contact};
+use log::warn;
+use prost::Message;
+use rocksdb::DB;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::althea::delegations::Balance;
+use crate::althea::error::AltheaError;
+use crate::althea::gov_v1::MsgVote as MsgVoteV1;
+use crate::althea::{get_cached, start_eviction_task, Cached, CACHE_DURATION};
+
+const ACCOUNT_TXS_KEY_PREFIX: &str = "account_txs_";
+pub const DEFAULT_TXS_LIMIT: u64 = 20;
+pub const MAX_TXS_LIMIT: u64 = 100;
+/// The furthest into an account's history a page may end, as each page fetches every transaction before it
+pub const MAX_TXS_DEPTH: u64 = 2000;
+
+pub const MSG_DELEGATE_TYPE_URL: &str = "/cosmos.staking.v1beta1.MsgDelegate";
+pub const MSG_UNDELEGATE_TYPE_URL: &str = "/cosmos.staking.v1beta1.MsgUndelegate";
+pub const MSG_BEGIN_REDELEGATE_TYPE_URL: &str = "/cosmos.staking.v1beta1.MsgBeginRedelegate";
+pub const MSG_WITHDRAW_DELEGATOR_REWARD_TYPE_URL: &str =
+    "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward";
+pub const MSG_VOTE_TYPE_URL: &str = "/cosmos.gov.v1beta1.MsgVote";
+pub const MSG_VOTE_V1_TYPE_URL: &str = "/cosmos.gov.v1.MsgVote";
+pub const MSG_SEND_TYPE_URL: &str = "/cosmos.bank.v1beta1.MsgSend";
+pub const MSG_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+/// The fields of ibc-go's MsgTransfer reported in the activity feed, as it is not included in the cosmos
+/// protos. The source port, timeouts and memo are skipped when decoding
+#[derive(Clone, PartialEq, Message)]
+struct MsgTransfer {
+    #[prost(string, tag = "2")]
+    source_channel: String,
+    #[prost(message, optional, tag = "3")]
+    token: Option<ProtoCoin>,
+    #[prost(string, tag = "4")]
+    sender: String,
+    #[prost(string, tag = "5")]
+    receiver: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountTxs {
+    pub address: String,
+    pub page: u64,
+    pub limit: u64,
+    /// True if there are older transactions on the following pages
+    pub has_more: bool,
+    pub txs: Vec<AccountTx>,
+    pub last_updated: u64,
+}
+
+impl Cached for AccountTxs {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountTx {
+    pub hash: String,
+    pub height: i64,
+    pub timestamp: String,
+    /// False if the transaction was included in a block but failed to execute
+    pub success: bool,
+    pub fee: Vec<Balance>,
+    pub memo: String,
+    pub activities: Vec<Activity>,
+}
+
+/// A single message of a transaction
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Activity {
+    pub type_url: String,
+    pub details: ActivityDetails,
+}
+
+/// The normalized fields of each supported message type
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ActivityDetails {
+    Delegate {
+        validator_address: String,
+        amount: Option<Balance>,
+    },
+    Undelegate {
+        validator_address: String,
+        amount: Option<Balance>,
+    },
+    Redelegate {
+        validator_src_address: String,
+        validator_dst_address: String,
+        amount: Option<Balance>,
+    },
+    WithdrawRewards {
+        validator_address: String,
+    },
+    /// The gov module's VoteOption, 1 = Yes, 2 = Abstain, 3 = No, 4 = NoWithVeto
+    Vote {
+        proposal_id: u64,
+        option: i32,
+    },
+    Send {
+        from_address: String,
+        to_address: String,
+        amount: Vec<Balance>,
+    },
+    IbcTransfer {
+        source_channel: String,
+        sender: String,
+        receiver: String,
+        token: Option<Balance>,
+    },
+    /// A message type we do not decode, or a message that failed to decode
+    Other,
+}
+
+impl Activity {
+    /// Decodes the protobuf encoded `value` of a transaction message according to `type_url`, messages
+    /// that cannot be decoded are logged and returned as Other
+    pub fn decode(type_url: String, value: &[u8]) -> Activity {
+        let details = match Self::decode_details(&type_url, value) {
+            Ok(details) => details,
+            Err(e) => {
+                warn!("Failed to decode transaction message {}: {}", type_url, e);
+                ActivityDetails::Other
+            }
+        };
+        Activity { type_url, details }
+    }
+
+    fn decode_details(
+        type_url: &str,
+        value: &[u8],
+    ) -> Result<ActivityDetails, Box<dyn std::error::Error>> {
+        let details = match type_url {
+            MSG_DELEGATE_TYPE_URL => {
+                let msg = MsgDelegate::decode(value)?;
+                ActivityDetails::Delegate {
+                    validator_address: msg.validator_address,
+                    amount: msg.amount.map(to_balance).transpose()?,
+                }
+            }
+            MSG_UNDELEGATE_TYPE_URL => {
+                let msg = MsgUndelegate::decode(value)?;
+                ActivityDetails::Undelegate {
+                    validator_address: msg.validator_address,
+                    amount: msg.amount.map(to_balance).transpose()?,
+                }
+            }
+            MSG_BEGIN_REDELEGATE_TYPE_URL => {
+                let msg = MsgBeginRedelegate::decode(value)?;
+                ActivityDetails::Redelegate {
+                    validator_src_address: msg.validator_src_address,
+                    validator_dst_address: msg.validator_dst_address,
+                    amount: msg.amount.map(to_balance).transpose()?,
+                }
+            }
+            MSG_WITHDRAW_DELEGATOR_REWARD_TYPE_URL => {
+                let msg = MsgWithdrawDelegatorReward::decode(value)?;
+                ActivityDetails::WithdrawRewards {
+                    validator_address: msg.validator_address,
+                }
+            }
+            MSG_VOTE_TYPE_URL => {
+                let msg = MsgVote::decode(value)?;
+                ActivityDetails::Vote {
+                    proposal_id: msg.proposal_id,
+                    option: msg.option,
+                }
+            }
+            MSG_VOTE_V1_TYPE_URL => {
+                let msg = MsgVoteV1::decode(value)?;
+                ActivityDetails::Vote {
+                    proposal_id: msg.proposal_id,
+                    option: msg.option,
+                }
+            }
+            MSG_SEND_TYPE_URL => {
+                let msg = MsgSend::decode(value)?;
+                ActivityDetails::Send {
+                    from_address: msg.from_address,
+                    to_address: msg.to_address,
+                    amount: msg
+                        .amount
+                        .into_iter()
+                        .map(to_balance)
+                        .collect::<Result<_, _>>()?,
+                }
+            }
+            MSG_TRANSFER_TYPE_URL => {
+                let msg = MsgTransfer::decode(value)?;
+                ActivityDetails::IbcTransfer {
+                    source_channel: msg.source_channel,
+                    sender: msg.sender,
+                    receiver: msg.receiver,
+                    token: msg.token.map(to_balance).transpose()?,
+                }
+            }
+            _ => ActivityDetails::Other,
+        };
+        Ok(details)
+    }
+}
+
+fn to_balance(coin: ProtoCoin) -> Result<Balance, AltheaError> {
+    Balance::from_base_units(coin.denom, &coin.amount)
+}
+
+fn account_txs_key(address: &CosmosAddress, page: u64, limit: u64) -> String {
+    format!("{}{}_{}_{}", ACCOUNT_TXS_KEY_PREFIX, address, page, limit)
+}
+
+fn get_cached_account_txs(
+    db: &rocksdb::DB,
+    address: &CosmosAddress,
+    page: u64,
+    limit: u64,
+) -> Option<AccountTxs> {
+    let key = account_txs_key(address, page, limit);
+    get_cached(db, key.as_bytes(), CACHE_DURATION)
+}
+
+fn cache_account_txs(db: &rocksdb::DB, address: &CosmosAddress, txs: &AccountTxs) {
+    let key = account_txs_key(address, txs.page, txs.limit);
+    let encoded = bincode::serialize(txs).unwrap();
+    db.put(key.as_bytes(), encoded).unwrap();
+}
+
+/// Periodically deletes expired pages from the cache. Every address, page and limit requested is cached
+/// under its own key, and an expired page would otherwise remain until the same page is requested again
+pub fn start_account_txs_eviction_task(db: Arc<DB>) {
+    start_eviction_task::<AccountTxs>(
+        db,
+        ACCOUNT_TXS_KEY_PREFIX,
+        CACHE_DURATION,
+        "account transaction pages",
+    );
+}
+
+/// Fetches a page of the transactions sent by or sending funds to `address`, newest first. Pages start at
+/// 1 and hold up to `limit` transactions. The sent and received transactions are searched separately, so
+/// both are fetched up to the end of the page and merged before the page is cut from the merged list
+pub async fn fetch_account_txs(
+    db: &rocksdb::DB,
+    contact: &Contact,
+    address: CosmosAddress,
+    page: u64,
+    limit: u64,
+) -> Result<AccountTxs, Box<dyn std::error::Error>> {
+    if let Some(cached) = get_cached_account_txs(db, &address, page, limit) {
+        return Ok(cached);
+    }
+
+    let offset = (page - 1).saturating_mul(limit);
+    let depth = offset.saturating_add(limit);
+    // Every message signed by the account sets message.sender, transfers to it set transfer.recipient
+    let (sent, sent_more) =
+        search_txs(contact, format!("message.sender='{}'", address), depth).await?;
+    let (received, received_more) =
+        search_txs(contact, format!("transfer.recipient='{}'", address), depth).await?;
+
+    let mut txs = sent;
+    for tx in received {
+        if !txs.iter().any(|t| t.hash == tx.hash) {
+            txs.push(tx);
+        }
+    }
+    txs.sort_by(|a, b| b.height.cmp(&a.height));
+    // Each list holds its newest `depth` transactions, so the newest `depth` of the merged list are exact
+    let has_more = txs.len() as u64 > depth || sent_more || received_more;
+    let txs = txs
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+
+    let response = AccountTxs {
+        address: address.to_string(),
+        page,
+        limit,
+        has_more,
+        txs,
+        last_updated: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    cache_account_txs(db, &address, &response);
+    Ok(response)
+}
+
+// Returns the newest `count` transactions matching `event`, and whether there are more after them. Pages
+// are requested at a fixed size of MAX_TXS_LIMIT, so the last page may hold more than is needed and is cut
+async fn search_txs(
+    contact: &Contact,
+    event: String,
+    count: u64,
+) -> Result<(Vec<AccountTx>, bool), Box<dyn std::error::Error>> {
+    let mut client = TxServiceClient::connect(contact.get_url()).await?;
+    let mut txs = Vec::new();
+    let mut page = 1;
+    loop {
+        let response = client
+            .get_txs_event(GetTxsEventRequest {
+                events: vec![event.clone()],
+                order_by: OrderBy::Desc as i32,
+                page,
+                limit: MAX_TXS_LIMIT,
+                ..Default::default()
+            })
+            .await?
+            .into_inner();
+
+        let total = response.total;
+        let received = response.tx_responses.len();
+        // txs holds the decoded transaction of each entry of tx_responses
+        for (r, tx) in response.tx_responses.into_iter().zip(response.txs) {
+            txs.push(to_account_tx(
+                r.txhash,
+                r.height,
+                r.timestamp,
+                r.code == 0,
+                tx,
+            )?);
+        }
+        let fetched = txs.len() as u64;
+        if received == 0 || fetched >= count || fetched >= total {
+            txs.truncate(count as usize);
+            return Ok((txs, count < total));
+        }
+        page += 1;
+    }
+}
+
+fn to_account_tx(
+    hash: String,
+    height: i64,
+    timestamp: String,
+    success: bool,
+    tx: Tx,
+) -> Result<AccountTx, Box<dyn std::error::Error>> {
+    let fee = tx
+        .auth_info
+        .and_then(|a| a.fee)
+        .map(|f| f.amount)
+        .unwrap_or_default()
+        .into_iter()
+        .map(to_balance)
+        .collect::<Result<_, _>>()?;
+    let (memo, activities) = match tx.body {
+        Some(body) => (
+            body.memo,
+            body.messages
+                .into_iter()
+                .map(|m| Activity::decode(m.type_url, &m.value))
+                .collect(),
+        ),
+        None => (String::new(), vec![]),
+    };
+    Ok(AccountTx {
+        hash,
+        height,
+        timestamp,
+        success,
+        fee,
+        memo,
+        activities,
+    })
+}
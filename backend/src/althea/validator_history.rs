@@ -0,0 +1,273 @@
+// Records periodic snapshots of the validator set so that changes in voting power can be tracked over time
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::althea::amount::Amount;
+use crate::althea::validators::ValidatorInfo;
+
+pub const VALIDATOR_SNAPSHOT_PREFIX: &str = "validator-snapshot_";
+/// The minimum time between snapshots, refreshes within this period of the latest snapshot are not recorded
+pub const SNAPSHOT_INTERVAL: u64 = 3600;
+/// Snapshots older than this are deleted when a new snapshot is recorded
+pub const SNAPSHOT_RETENTION: u64 = 90 * 86400;
+/// The period compared by rank changes when none is given
+pub const DEFAULT_RANK_CHANGE_PERIOD: u64 = 86400;
+
+// Timestamps are zero padded so that snapshots sort by time
+fn snapshot_key(timestamp: u64) -> String {
+    format!("{}{:020}", VALIDATOR_SNAPSHOT_PREFIX, timestamp)
+}
+
+/// The validator set at a point in time
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidatorSnapshot {
+    pub timestamp: u64,
+    pub validators: Vec<SnapshotEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotEntry {
+    pub operator_address: String,
+    pub tokens: Amount,
+    pub delegator_shares: Amount,
+    pub status: i32,
+    pub jailed: bool,
+    pub commission_rate: Option<Amount>,
+    /// The validator's position by voting power, starting at 1
+    pub rank: u32,
+}
+
+/// A single validator's state at a point in time
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidatorHistoryPoint {
+    pub timestamp: u64,
+    pub tokens: Amount,
+    pub delegator_shares: Amount,
+    pub status: i32,
+    pub jailed: bool,
+    pub commission_rate: Option<Amount>,
+    pub rank: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidatorHistory {
+    pub operator_address: String,
+    pub points: Vec<ValidatorHistoryPoint>,
+}
+
+/// How a validator's rank and voting power have moved since the start of a period
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RankChange {
+    pub operator_address: String,
+    pub moniker: Option<String>,
+    pub rank: u32,
+    /// None if the validator was not in the set at the start of the period
+    pub previous_rank: Option<u32>,
+    /// Positive when the validator has moved up the rankings
+    pub rank_change: i64,
+    pub tokens: Amount,
+    pub previous_tokens: Amount,
+    /// The signed difference between tokens and previous_tokens
+    pub tokens_change: String,
+    pub tokens_change_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RankChanges {
+    /// The time of the snapshot the current validator set is compared against
+    pub since: u64,
+    pub changes: Vec<RankChange>,
+}
+
+impl From<&[ValidatorInfo]> for ValidatorSnapshot {
+    // Expects `validators` sorted by voting power, as returned by fetch_validators
+    fn from(validators: &[ValidatorInfo]) -> Self {
+        ValidatorSnapshot {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            validators: validators
+                .iter()
+                .enumerate()
+                .map(|(i, v)| SnapshotEntry {
+                    operator_address: v.operator_address.clone(),
+                    tokens: v.tokens.clone(),
+                    delegator_shares: v.delegator_shares.clone(),
+                    status: v.status,
+                    jailed: v.jailed,
+                    commission_rate: v.commission.clone(),
+                    rank: i as u32 + 1,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Records a snapshot of `validators` unless one was recorded within the last SNAPSHOT_INTERVAL, and
+/// removes snapshots past their retention period
+pub fn record_validator_snapshot(db: &rocksdb::DB, validators: &[ValidatorInfo]) {
+    if validators.is_empty() {
+        return;
+    }
+    let snapshot = ValidatorSnapshot::from(validators);
+    if let Some(latest) = get_latest_snapshot_time(db) {
+        if snapshot.timestamp < latest + SNAPSHOT_INTERVAL {
+            return;
+        }
+    }
+
+    let encoded = bincode::serialize(&snapshot).unwrap();
+    db.put(snapshot_key(snapshot.timestamp).as_bytes(), encoded)
+        .unwrap();
+    info!(
+        "Recorded snapshot of {} validators",
+        snapshot.validators.len()
+    );
+
+    let cutoff = snapshot_key(snapshot.timestamp.saturating_sub(SNAPSHOT_RETENTION));
+    let mut batch = rocksdb::WriteBatch::default();
+    batch.delete_range(snapshot_key(0).as_bytes(), cutoff.as_bytes());
+    if let Err(e) = db.write(batch) {
+        error!("Failed to remove expired validator snapshots: {}", e);
+    }
+}
+
+/// Gets every snapshot recorded at or after `since`, oldest first
+pub fn get_validator_snapshots(db: &rocksdb::DB, since: u64) -> Vec<ValidatorSnapshot> {
+    let prefix = VALIDATOR_SNAPSHOT_PREFIX.as_bytes();
+    let start = snapshot_key(since);
+    let mut snapshots = vec![];
+    let iter = db.iterator(rocksdb::IteratorMode::From(
+        start.as_bytes(),
+        rocksdb::Direction::Forward,
+    ));
+    for entry in iter {
+        match entry {
+            Ok((k, v)) => {
+                if !k.starts_with(prefix) {
+                    break;
+                }
+                match bincode::deserialize::<ValidatorSnapshot>(&v) {
+                    Ok(snapshot) => snapshots.push(snapshot),
+                    Err(e) => error!("Failed to decode validator snapshot: {}", e),
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    snapshots
+}
+
+fn get_latest_snapshot_time(db: &rocksdb::DB) -> Option<u64> {
+    let prefix = VALIDATOR_SNAPSHOT_PREFIX.as_bytes();
+    // Every snapshot key sorts before the prefix followed by a character greater than any digit
+    let end = format!("{}~", VALIDATOR_SNAPSHOT_PREFIX);
+    let mut iter = db.iterator(rocksdb::IteratorMode::From(
+        end.as_bytes(),
+        rocksdb::Direction::Reverse,
+    ));
+    match iter.next() {
+        Some(Ok((k, _))) if k.starts_with(prefix) => {
+            std::str::from_utf8(&k[prefix.len()..]).ok()?.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+/// Gets the recorded history of a single validator since `since`, oldest first
+pub fn get_validator_history(
+    db: &rocksdb::DB,
+    operator_address: &str,
+    since: u64,
+) -> ValidatorHistory {
+    let points = get_validator_snapshots(db, since)
+        .into_iter()
+        .filter_map(|s| {
+            let timestamp = s.timestamp;
+            s.validators
+                .into_iter()
+                .find(|v| v.operator_address == operator_address)
+                .map(|v| ValidatorHistoryPoint {
+                    timestamp,
+                    tokens: v.tokens,
+                    delegator_shares: v.delegator_shares,
+                    status: v.status,
+                    jailed: v.jailed,
+                    commission_rate: v.commission_rate,
+                    rank: v.rank,
+                })
+        })
+        .collect();
+    ValidatorHistory {
+        operator_address: operator_address.to_string(),
+        points,
+    }
+}
+
+/// Compares the current validator set against the oldest snapshot within the last `period` seconds,
+/// returns None if no snapshot has been recorded in that period
+pub fn get_rank_changes(
+    db: &rocksdb::DB,
+    validators: &[ValidatorInfo],
+    period: u64,
+) -> Option<RankChanges> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let previous = get_validator_snapshots(db, now.saturating_sub(period))
+        .into_iter()
+        .next()?;
+
+    let changes = validators
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let rank = i as u32 + 1;
+            let before = previous
+                .validators
+                .iter()
+                .find(|p| p.operator_address == v.operator_address);
+            let previous_tokens = before
+                .map(|p| p.tokens.clone())
+                .unwrap_or_else(|| Amount::from(0u128));
+            let difference = v.tokens.abs_diff(&previous_tokens);
+            let tokens_change = if v.tokens >= previous_tokens {
+                difference.to_string()
+            } else {
+                format!("-{}", difference)
+            };
+            let tokens_change_percent = if previous_tokens.is_zero() {
+                None
+            } else {
+                let percent = difference.to_f64() / previous_tokens.to_f64() * 100.0;
+                Some(if v.tokens >= previous_tokens {
+                    percent
+                } else {
+                    -percent
+                })
+            };
+            RankChange {
+                operator_address: v.operator_address.clone(),
+                moniker: v.description.as_ref().map(|d| d.moniker.clone()),
+                rank,
+                previous_rank: before.map(|p| p.rank),
+                rank_change: before
+                    .map(|p| p.rank as i64 - rank as i64)
+                    .unwrap_or_default(),
+                tokens: v.tokens.clone(),
+                previous_tokens,
+                tokens_change,
+                tokens_change_percent,
+            }
+        })
+        .collect();
+
+    Some(RankChanges {
+        since: previous.timestamp,
+        changes,
+    })
+}
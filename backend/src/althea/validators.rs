@@ -1,13 +1,29 @@
-use crate::althea::abi_util::format_decimal_18;
-use crate::althea::CACHE_DURATION;
+use crate::althea::amount::{Amount, SDK_DEC_PRECISION};
+use crate::althea::delegations::{fetch_withdraw_address, Balance};
+use crate::althea::staking::{fetch_staking_apr, StakingApr};
+use crate::althea::validator_history::record_validator_snapshot;
+use crate::althea::{format_timestamp, get_cached, Cached, ALTHEA_PREFIX, CACHE_DURATION};
 use crate::Arc;
+use clarity::utils::hex_str_to_bytes;
 use cosmos_sdk_proto_althea::cosmos::base::query::v1beta1::PageRequest;
-use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::{QueryValidatorsRequest, Validator};
-use deep_space::Contact;
+use cosmos_sdk_proto_althea::cosmos::crypto::ed25519::PubKey;
+use cosmos_sdk_proto_althea::cosmos::distribution::v1beta1::{
+    query_client::QueryClient as DistributionQueryClient, QueryValidatorCommissionRequest,
+    QueryValidatorOutstandingRewardsRequest, QueryValidatorSlashesRequest,
+};
+use cosmos_sdk_proto_althea::cosmos::slashing::v1beta1::{
+    query_client::QueryClient as SlashingQueryClient, QueryParamsRequest as SlashingParamsRequest,
+    QuerySigningInfoRequest,
+};
+use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::{
+    query_client::QueryClient as StakingQueryClient, Commission, QueryValidatorDelegationsRequest,
+    QueryValidatorRequest, QueryValidatorsRequest, Validator,
+};
+use deep_space::{Address as CosmosAddress, Contact};
 use log::{error, info};
+use prost::Message;
 use rocksdb::DB;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -16,16 +32,30 @@ pub struct ValidatorInfo {
     pub consensus_pubkey: Option<String>,
     pub jailed: bool,
     pub status: i32,
-    pub tokens: String,
-    pub delegator_shares: String,
+    /// Bonded aalthea
+    pub tokens: Amount,
+    /// Delegator shares are a Dec, reported with 18 decimal places
+    pub delegator_shares: Amount,
     pub description: Option<ValidatorDescription>,
     pub unbonding_height: i64,
     pub unbonding_time: Option<SystemTime>,
-    pub commission: Option<String>,
+    /// The current commission rate, a fraction with 18 decimal places
+    pub commission: Option<Amount>,
+    /// The commission rate along with the limits on changing it
+    pub commission_rates: Option<ValidatorCommission>,
     pub min_self_delegation: String,
+    /// The APR earned by this validator's delegators after commission, from 0 to 100. None if the
+    /// network APR could not be computed
+    pub estimated_apr: Option<f64>,
     pub last_updated: u64,
 }
 
+impl Cached for ValidatorInfo {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ValidatorDescription {
     pub moniker: String,
@@ -35,6 +65,77 @@ pub struct ValidatorDescription {
     pub details: String,
 }
 
+/// Commission rates are fractions with 18 decimal places
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidatorCommission {
+    pub rate: Amount,
+    pub max_rate: Amount,
+    pub max_change_rate: Amount,
+    pub update_time: Option<String>,
+}
+
+/// Everything known about a single validator, used by delegators to compare validators
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidatorDetail {
+    pub validator: ValidatorInfo,
+    pub signing_info: Option<SigningInfo>,
+    pub slashing_events: Vec<SlashingEvent>,
+    /// The amount of aalthea the operator has delegated to their own validator
+    pub self_delegation: String,
+    pub delegator_count: u64,
+    pub last_updated: u64,
+}
+
+impl Cached for ValidatorDetail {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+/// The rewards a validator has accumulated, which the operator can withdraw as commission or which are
+/// still owed to its delegators
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidatorRewards {
+    pub operator_address: String,
+    /// The operator's account, which shares the bytes of the operator address
+    pub account_address: String,
+    /// The address the operator's commission is paid to when withdrawn
+    pub withdraw_address: String,
+    /// Commission accumulated since the operator last withdrew it
+    pub commission: Vec<Balance>,
+    /// Every reward held by the distribution module for this validator, including its commission and its
+    /// delegators' unclaimed rewards
+    pub outstanding_rewards: Vec<Balance>,
+    pub last_updated: u64,
+}
+
+impl Cached for ValidatorRewards {
+    fn last_updated(&self) -> u64 {
+        self.last_updated
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SigningInfo {
+    pub consensus_address: String,
+    pub start_height: i64,
+    pub jailed_until: Option<String>,
+    pub tombstoned: bool,
+    /// The number of blocks missed within the current signed blocks window
+    pub missed_blocks_counter: i64,
+    pub signed_blocks_window: i64,
+    /// The share of blocks signed within the signed blocks window, from 0 to 100
+    pub uptime_percent: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SlashingEvent {
+    /// The distribution period in which the validator was slashed
+    pub validator_period: u64,
+    /// The fraction of stake slashed, with 18 decimal places
+    pub fraction: Amount,
+}
+
 pub async fn fetch_validators(
     db: &rocksdb::DB,
     contact: &deep_space::Contact,
@@ -60,14 +161,20 @@ pub async fn fetch_validators(
     let mut all_validators: Vec<ValidatorInfo> =
         validators.into_iter().map(ValidatorInfo::from).collect();
 
+    // The APR is refreshed alongside the validators, a failure leaves estimated_apr unset rather than
+    // failing the whole refresh
+    match fetch_staking_apr(db, contact).await {
+        Ok(staking_apr) => all_validators
+            .iter_mut()
+            .for_each(|v| v.set_estimated_apr(&staking_apr)),
+        Err(e) => error!("Failed to compute staking APR: {}", e),
+    }
+
     // Sort validators by tokens (voting power) in descending order
-    all_validators.sort_by(|a, b| {
-        let a_tokens = u128::from_str(&a.tokens).unwrap_or_default();
-        let b_tokens = u128::from_str(&b.tokens).unwrap_or_default();
-        b_tokens.cmp(&a_tokens)
-    });
+    all_validators.sort_by(|a, b| b.tokens.cmp(&a.tokens));
 
     cache_validators(db, &all_validators);
+    record_validator_snapshot(db, &all_validators);
     info!(
         "Successfully fetched and stored {} validators",
         all_validators.len()
@@ -76,24 +183,7 @@ pub async fn fetch_validators(
 }
 
 fn get_cached_validators(db: &rocksdb::DB) -> Option<Vec<ValidatorInfo>> {
-    let key = b"validators";
-    match db.get(key).unwrap() {
-        Some(data) => {
-            let validators: Vec<ValidatorInfo> = bincode::deserialize(&data).unwrap();
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-
-            // Cache for 5 minutes
-            if now - validators[0].last_updated < CACHE_DURATION {
-                Some(validators)
-            } else {
-                None
-            }
-        }
-        None => None,
-    }
+    get_cached(db, b"validators", CACHE_DURATION)
 }
 
 fn cache_validators(db: &rocksdb::DB, validators: &[ValidatorInfo]) {
@@ -104,10 +194,6 @@ fn cache_validators(db: &rocksdb::DB, validators: &[ValidatorInfo]) {
 
 impl From<Validator> for ValidatorInfo {
     fn from(v: Validator) -> Self {
-        let commission = v
-            .commission
-            .and_then(|c| c.commission_rates.map(|r| format_decimal_18(r.rate)));
-
         ValidatorInfo {
             operator_address: v.operator_address,
             consensus_pubkey: v
@@ -115,8 +201,8 @@ impl From<Validator> for ValidatorInfo {
                 .map(|p| String::from_utf8_lossy(&p.value).to_string()),
             jailed: v.jailed,
             status: v.status,
-            tokens: v.tokens,
-            delegator_shares: v.delegator_shares,
+            tokens: Amount::parse(&v.tokens).unwrap_or_else(|_| Amount::from(0u128)),
+            delegator_shares: parse_dec_or_zero(&v.delegator_shares),
             description: v.description.map(|d| ValidatorDescription {
                 moniker: d.moniker,
                 identity: d.identity,
@@ -128,8 +214,14 @@ impl From<Validator> for ValidatorInfo {
             unbonding_time: v
                 .unbonding_time
                 .map(|t| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(t.seconds as u64)),
-            commission,
+            commission: v
+                .commission
+                .as_ref()
+                .and_then(|c| c.commission_rates.as_ref())
+                .map(|r| parse_dec_or_zero(&r.rate)),
+            commission_rates: v.commission.map(ValidatorCommission::from),
             min_self_delegation: v.min_self_delegation,
+            estimated_apr: None,
             last_updated: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
@@ -138,7 +230,33 @@ impl From<Validator> for ValidatorInfo {
     }
 }
 
+impl From<Commission> for ValidatorCommission {
+    fn from(c: Commission) -> Self {
+        let rates = c.commission_rates.unwrap_or_default();
+        ValidatorCommission {
+            rate: parse_dec_or_zero(&rates.rate),
+            max_rate: parse_dec_or_zero(&rates.max_rate),
+            max_change_rate: parse_dec_or_zero(&rates.max_change_rate),
+            update_time: c.update_time.and_then(format_timestamp),
+        }
+    }
+}
+
+fn parse_dec_or_zero(input: &str) -> Amount {
+    Amount::from_sdk_dec(input).unwrap_or_else(|_| Amount::new(0u8, SDK_DEC_PRECISION))
+}
+
 impl ValidatorInfo {
+    /// Sets `estimated_apr` from the network APR and this validator's commission rate
+    pub fn set_estimated_apr(&mut self, staking_apr: &StakingApr) {
+        let rate = self
+            .commission
+            .as_ref()
+            .map(|rate| rate.to_f64())
+            .unwrap_or_default();
+        self.estimated_apr = Some(staking_apr.validator_apr(rate));
+    }
+
     pub fn is_active(&self) -> bool {
         self.status == 3 && !self.jailed
     }
@@ -186,3 +304,267 @@ pub async fn fetch_validator_by_address(
         .into_iter()
         .find(|v| v.operator_address == operator_address))
 }
+
+const VALIDATOR_DETAIL_KEY_PREFIX: &str = "validator_detail_";
+
+/// Fetches signing info, slashing history and delegation details for a single validator,
+/// returns None if no validator has the given operator address
+pub async fn fetch_validator_detail(
+    db: &rocksdb::DB,
+    contact: &deep_space::Contact,
+    operator_address: &str,
+) -> Result<Option<ValidatorDetail>, Box<dyn std::error::Error>> {
+    if let Some(detail) = get_cached_validator_detail(db, operator_address) {
+        return Ok(Some(detail));
+    }
+    let operator = match CosmosAddress::from_bech32(operator_address.to_string()) {
+        Ok(operator) => operator,
+        Err(_) => return Ok(None),
+    };
+
+    let mut staking = StakingQueryClient::connect(contact.get_url()).await?;
+    let validator = match staking
+        .validator(QueryValidatorRequest {
+            validator_addr: operator_address.to_string(),
+        })
+        .await
+    {
+        Ok(response) => match response.into_inner().validator {
+            Some(v) => v,
+            None => return Ok(None),
+        },
+        Err(status) if status.code() == tonic::Code::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let signing_info = match consensus_address(&validator)? {
+        Some(cons_address) => fetch_signing_info(contact, cons_address).await?,
+        None => None,
+    };
+
+    let mut distribution = DistributionQueryClient::connect(contact.get_url()).await?;
+    let mut slashing_events = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let response = distribution
+            .validator_slashes(QueryValidatorSlashesRequest {
+                validator_address: operator_address.to_string(),
+                starting_height: 0,
+                ending_height: u64::MAX,
+                pagination: Some(PageRequest {
+                    key: next_key,
+                    offset: 0,
+                    limit: 1000,
+                    count_total: false,
+                    reverse: false,
+                }),
+            })
+            .await?
+            .into_inner();
+        slashing_events.extend(response.slashes.into_iter().map(|s| SlashingEvent {
+            validator_period: s.validator_period,
+            fraction: parse_dec_or_zero(&s.fraction),
+        }));
+        match response.pagination {
+            Some(p) if !p.next_key.is_empty() => next_key = p.next_key,
+            _ => break,
+        }
+    }
+
+    // The operator's account shares the bytes of the operator address
+    let operator_account = CosmosAddress::from_bech32(operator.to_bech32(ALTHEA_PREFIX)?)?;
+    let self_delegation = contact
+        .get_delegation(operator, operator_account)
+        .await?
+        .and_then(|d| d.balance)
+        .map(|b| b.amount)
+        .unwrap_or_else(|| "0".to_string());
+
+    let delegator_count = staking
+        .validator_delegations(QueryValidatorDelegationsRequest {
+            validator_addr: operator_address.to_string(),
+            pagination: Some(PageRequest {
+                key: Vec::new(),
+                offset: 0,
+                limit: 1,
+                count_total: true,
+                reverse: false,
+            }),
+        })
+        .await?
+        .into_inner()
+        .pagination
+        .map(|p| p.total)
+        .unwrap_or_default();
+
+    let mut validator = ValidatorInfo::from(validator);
+    match fetch_staking_apr(db, contact).await {
+        Ok(staking_apr) => validator.set_estimated_apr(&staking_apr),
+        Err(e) => error!("Failed to compute staking APR: {}", e),
+    }
+
+    let detail = ValidatorDetail {
+        validator,
+        signing_info,
+        slashing_events,
+        self_delegation,
+        delegator_count,
+        last_updated: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    cache_validator_detail(db, &detail);
+    Ok(Some(detail))
+}
+
+// The consensus address is the first 20 bytes of the sha256 hash of the validator's ed25519 consensus key
+fn consensus_address(validator: &Validator) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let pubkey = match &validator.consensus_pubkey {
+        Some(pubkey) => PubKey::decode(pubkey.value.as_slice())?,
+        None => return Ok(None),
+    };
+    let hash = hex_str_to_bytes(&sha256::digest(pubkey.key.as_slice()))?;
+    let address = CosmosAddress::from_slice(&hash[..20], format!("{}valcons", ALTHEA_PREFIX))?;
+    Ok(Some(address.to_string()))
+}
+
+async fn fetch_signing_info(
+    contact: &deep_space::Contact,
+    consensus_address: String,
+) -> Result<Option<SigningInfo>, Box<dyn std::error::Error>> {
+    let mut slashing = SlashingQueryClient::connect(contact.get_url()).await?;
+    let info = match slashing
+        .signing_info(QuerySigningInfoRequest {
+            cons_address: consensus_address.clone(),
+        })
+        .await
+    {
+        Ok(response) => match response.into_inner().val_signing_info {
+            Some(info) => info,
+            None => return Ok(None),
+        },
+        // Validators which have never been bonded have no signing info
+        Err(status) if status.code() == tonic::Code::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let signed_blocks_window = slashing
+        .params(SlashingParamsRequest {})
+        .await?
+        .into_inner()
+        .params
+        .map(|p| p.signed_blocks_window)
+        .unwrap_or_default();
+    let uptime_percent = if signed_blocks_window > 0 {
+        100.0 * (1.0 - info.missed_blocks_counter as f64 / signed_blocks_window as f64)
+    } else {
+        100.0
+    };
+
+    Ok(Some(SigningInfo {
+        consensus_address,
+        start_height: info.start_height,
+        jailed_until: info.jailed_until.and_then(format_timestamp),
+        tombstoned: info.tombstoned,
+        missed_blocks_counter: info.missed_blocks_counter,
+        signed_blocks_window,
+        uptime_percent,
+    }))
+}
+
+fn get_cached_validator_detail(
+    db: &rocksdb::DB,
+    operator_address: &str,
+) -> Option<ValidatorDetail> {
+    let key = format!("{}{}", VALIDATOR_DETAIL_KEY_PREFIX, operator_address);
+    get_cached(db, key.as_bytes(), CACHE_DURATION)
+}
+
+fn cache_validator_detail(db: &rocksdb::DB, detail: &ValidatorDetail) {
+    let key = format!(
+        "{}{}",
+        VALIDATOR_DETAIL_KEY_PREFIX, detail.validator.operator_address
+    );
+    let encoded = bincode::serialize(detail).unwrap();
+    db.put(key.as_bytes(), encoded).unwrap();
+}
+
+const VALIDATOR_REWARDS_KEY_PREFIX: &str = "validator_rewards_";
+
+/// Fetches the accumulated commission and outstanding rewards of a single validator, returns None if no
+/// validator has the given operator address
+pub async fn fetch_validator_rewards(
+    db: &rocksdb::DB,
+    contact: &deep_space::Contact,
+    operator_address: &str,
+) -> Result<Option<ValidatorRewards>, Box<dyn std::error::Error>> {
+    if let Some(rewards) = get_cached_validator_rewards(db, operator_address) {
+        return Ok(Some(rewards));
+    }
+    // The distribution module reports empty rewards for unknown validators rather than an error
+    if fetch_validator_by_address(db, contact, operator_address)
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
+    let operator = CosmosAddress::from_bech32(operator_address.to_string())?;
+    let operator_account = CosmosAddress::from_bech32(operator.to_bech32(ALTHEA_PREFIX)?)?;
+
+    let mut distribution = DistributionQueryClient::connect(contact.get_url()).await?;
+    let commission = distribution
+        .validator_commission(QueryValidatorCommissionRequest {
+            validator_address: operator_address.to_string(),
+        })
+        .await?
+        .into_inner()
+        .commission
+        .map(|c| c.commission)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| Balance::from_dec_coin(c.denom, &c.amount))
+        .collect::<Result<Vec<_>, _>>()?;
+    let outstanding_rewards = distribution
+        .validator_outstanding_rewards(QueryValidatorOutstandingRewardsRequest {
+            validator_address: operator_address.to_string(),
+        })
+        .await?
+        .into_inner()
+        .rewards
+        .map(|r| r.rewards)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| Balance::from_dec_coin(c.denom, &c.amount))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let rewards = ValidatorRewards {
+        operator_address: operator_address.to_string(),
+        account_address: operator_account.to_string(),
+        withdraw_address: fetch_withdraw_address(contact, operator_account).await?,
+        commission,
+        outstanding_rewards,
+        last_updated: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    cache_validator_rewards(db, &rewards);
+    Ok(Some(rewards))
+}
+
+fn get_cached_validator_rewards(
+    db: &rocksdb::DB,
+    operator_address: &str,
+) -> Option<ValidatorRewards> {
+    let key = format!("{}{}", VALIDATOR_REWARDS_KEY_PREFIX, operator_address);
+    get_cached(db, key.as_bytes(), CACHE_DURATION)
+}
+
+fn cache_validator_rewards(db: &rocksdb::DB, rewards: &ValidatorRewards) {
+    let key = format!(
+        "{}{}",
+        VALIDATOR_REWARDS_KEY_PREFIX, rewards.operator_address
+    );
+    let encoded = bincode::serialize(rewards).unwrap();
+    db.put(key.as_bytes(), encoded).unwrap();
+}
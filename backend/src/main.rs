@@ -18,7 +18,12 @@ pub struct Opts {
     #[clap(short, long, value_delimiter = ',')]
     pool_tokens: Vec<Address>,
 
-    /// The poolIdx values for which pool templates exist
+    /// The stablecoins treated as worth one dollar when pricing tokens, defaults to USDC and USDT
+    #[clap(long, value_delimiter = ',')]
+    stablecoins: Vec<Address>,
+
+    /// The poolIdx values for which pool templates exist, if empty the templates are discovered
+    /// from indexed SetPoolTemplate events
     #[clap(short = 't', long, value_delimiter = ',')]
     pool_templates: Vec<u64>,
 
@@ -57,6 +62,22 @@ pub struct Opts {
     /// If true the database will be compacted on startup then the server will halt
     #[clap(long, default_value = "false")]
     compact_and_halt: bool,
+
+    /// Webhook urls which receive governance proposal status changes as JSON
+    #[clap(long, value_delimiter = ',')]
+    webhook_urls: Vec<String>,
+
+    /// Discord webhook urls which receive governance proposal status changes
+    #[clap(long, value_delimiter = ',')]
+    discord_webhook_urls: Vec<String>,
+
+    /// Slack webhook urls which receive governance proposal status changes
+    #[clap(long, value_delimiter = ',')]
+    slack_webhook_urls: Vec<String>,
+
+    /// A JSON file of static token details (display names, logos) which override those read from chain
+    #[clap(long)]
+    token_overrides: Option<String>,
 }
 
 #[tokio::main]
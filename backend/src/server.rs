@@ -1,8 +1,13 @@
 use std::sync::Arc;
 
 use crate::althea::endpoints::{
-    get_delegations, get_proposals, get_validators, query_all_burn_ranged, query_all_init_pools,
-    query_all_mint_ambient, query_all_mint_ranged, query_pool, user_pool_positions, user_positions,
+    get_account, get_account_txs, get_address_votes, get_balances, get_delegation_cache_stats,
+    get_delegations, get_prices, get_proposal_deposits, get_proposal_votes, get_proposals,
+    get_staking_apr, get_tokens, get_validator_commission, get_validator_detail,
+    get_validator_history, get_validator_rank_changes, get_validators, query_all_burn_ranged,
+    query_all_init_pools, query_all_mint_ambient, query_all_mint_ranged, query_dex_stats,
+    query_dex_status, query_governance_log, query_pool, query_pool_templates, user_pool_positions,
+    user_positions,
 };
 use crate::tls::{load_certs, load_private_key};
 use crate::Opts;
@@ -34,14 +39,37 @@ pub async fn start_server(opts: Opts, db: Arc<rocksdb::DB>) {
             .route("/", web::get().to(index))
             // chain endpoints
             .service(get_validators)
+            // rank-changes must be registered before the {operator} route it would otherwise match
+            .service(get_validator_rank_changes)
+            .service(get_validator_detail)
+            .service(get_validator_history)
+            .service(get_validator_commission)
+            .service(get_staking_apr)
             .service(get_proposals)
+            .service(get_proposal_votes)
+            .service(get_proposal_deposits)
+            .service(get_address_votes)
             .service(get_delegations)
+            .service(get_delegation_cache_stats)
+            .service(get_balances)
+            .service(get_account)
+            .service(get_account_txs)
+            .service(get_tokens)
+            .service(get_prices)
             // pool endpoints
             .service(query_all_init_pools)
             .service(query_pool)
+            .service(query_pool_templates)
             .service(query_all_mint_ranged)
             .service(query_all_burn_ranged)
             .service(query_all_mint_ambient)
+            // DEX protocol endpoints
+            .service(
+                web::scope("/dex")
+                    .service(query_governance_log)
+                    .service(query_dex_status)
+                    .service(query_dex_stats),
+            )
             // Graphcache-go endpoints
             .service(
                 web::scope("/gcgo")
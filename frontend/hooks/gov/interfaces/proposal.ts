@@ -14,7 +14,10 @@ export interface Proposal {
   };
   submit_time: string;
   deposit_end_time: string;
-  total_deposit: string[];
+  total_deposit: {
+    denom: string;
+    amount: string;
+  }[];
   voting_start_time: string;
   voting_end_time: string;
   last_updated: number;